@@ -1,19 +1,99 @@
 -- PostgreSQL版本的短链接服务表结构
 
+-- 创建工作空间表，多个团队共用同一部署时数据按工作空间隔离
+create table if not exists workspace
+(
+    id          bigint                             not null primary key,
+    name        varchar(128)                       not null,
+    create_time timestamp default CURRENT_TIMESTAMP null
+);
+
+-- 默认工作空间，存量数据归属该工作空间
+insert into workspace (id, name) values (0, 'default') on conflict (id) do nothing;
+
+comment on table workspace is '工作空间表';
+comment on column workspace.name is '名称';
+
+-- 创建用户表
+create table if not exists app_user
+(
+    id           bigint                             not null primary key,
+    workspace_id bigint                             not null references workspace (id),
+    name         varchar(128)                       not null,
+    email        varchar(255)                       null,
+    role         varchar(16)                        not null default 'viewer',
+    create_time  timestamp default CURRENT_TIMESTAMP null
+);
+
+-- 角色：存量表结构升级
+alter table app_user add column if not exists role varchar(16) not null default 'viewer';
+
+create unique index if not exists app_user_email_uindex on app_user (email);
+create index if not exists app_user_workspace_id_index on app_user (workspace_id);
+
+comment on table app_user is '用户表';
+comment on column app_user.workspace_id is '所属工作空间';
+comment on column app_user.name is '名称';
+comment on column app_user.email is '邮箱';
+comment on column app_user.role is '角色：viewer 只读，editor 创建并修改自己的链接，admin 全部权限';
+
 -- 创建链接历史记录表
 create table if not exists link_history
 (
     id          bigint                             not null primary key,
+    workspace_id bigint                            not null default 0,
+    owner_id    bigint                             null,
+    owner_key_id bigint                            null,
+    domain      varchar(255)                       not null default '',
     origin_url  varchar(4000)                      not null,
     link_type   integer                            null,
     expire_date timestamp                          null,
     active      boolean                            not null default true,
     link_hash   varchar(48)                        not null,
+    idle_ttl    bigint                             null,
+    idle_deadline timestamp                        null,
     create_time timestamp default CURRENT_TIMESTAMP null,
-    update_time timestamp default CURRENT_TIMESTAMP null,
-    constraint link_history_link_hash_uindex unique (link_hash)
+    update_time timestamp default CURRENT_TIMESTAMP null
 );
 
+-- 多域名支持：存量表结构升级，同一地址在不同域名下可分别生成短链
+-- 存量数据的域名为空，服务启动时自动设置为配置的默认域名（link.default_domain），也可手动执行：
+-- update link_history set domain = '<默认域名>' where domain = '';
+alter table link_history add column if not exists domain varchar(255) not null default '';
+alter table link_history drop constraint if exists link_history_link_hash_uindex;
+
+-- 多租户：存量表结构升级，同一地址在不同工作空间下可分别生成短链
+-- 唯一索引直接建在(workspace_id, domain, link_hash)上，重复执行时不会因不同工作空间的相同地址失败
+alter table link_history add column if not exists workspace_id bigint not null default 0;
+drop index if exists link_history_domain_link_hash_uindex;
+create unique index if not exists link_history_workspace_domain_link_hash_uindex on link_history (workspace_id, domain, link_hash);
+create index if not exists link_history_workspace_create_time_desc_index on link_history (workspace_id, create_time DESC);
+
+-- 链接所有权：存量表结构升级，存量链接没有创建人，只有admin可以修改
+alter table link_history add column if not exists owner_id bigint null;
+-- 创建人的API Key单独记录，owner_id只保存用户id
+alter table link_history add column if not exists owner_key_id bigint null;
+
+-- 空闲过期：存量表结构升级
+alter table link_history add column if not exists idle_ttl bigint null;
+alter table link_history add column if not exists idle_deadline timestamp null;
+
+-- 滥用举报隔离：存量表结构升级
+alter table link_history add column if not exists quarantined boolean not null default false;
+alter table link_history add column if not exists quarantine_time timestamp null;
+
+-- 签名链接：存量表结构升级，开启后只能通过未过期的签名链接访问
+alter table link_history add column if not exists require_signature boolean not null default false;
+
+-- 失效原因：存量表结构升级，只有过期和空闲失效的链接可以通过延期重新激活
+-- 存量失效链接按隔离状态补全原因，失效时间取最后修改时间
+alter table link_history add column if not exists deactivated_reason varchar(16) null;
+alter table link_history add column if not exists deactivated_at timestamp null;
+update link_history
+set deactivated_reason = case when quarantined then 'abuse' else 'expired' end,
+    deactivated_at = coalesce(update_time, create_time)
+where active = false and deactivated_reason is null;
+
 -- 创建索引
 create index if not exists link_history_link_type_index on link_history (link_type);
 
@@ -30,12 +110,140 @@ create index if not exists link_history_active_create_time_desc_index on link_hi
 -- 优化count查询的索引（只针对活跃链接）
 create index if not exists link_history_active_count_index on link_history (active) where active = true;
 
+-- 优化空闲过期查询的索引
+create index if not exists link_history_idle_deadline_index on link_history (idle_deadline) where active = true and idle_deadline is not null;
+
+-- 优化失效链接物理删除的索引
+drop index if exists link_history_inactive_update_time_index;
+create index if not exists link_history_inactive_deactivated_at_index on link_history (deactivated_at) where active = false;
+
 -- 添加表注释
 comment on table link_history is '链接历史记录表';
+comment on column link_history.workspace_id is '所属工作空间';
+comment on column link_history.owner_id is '创建人的用户id';
+comment on column link_history.owner_key_id is '创建时使用的API Key';
+comment on column link_history.domain is '短链所属域名';
 comment on column link_history.origin_url is '原始的地址';
 comment on column link_history.link_type is '链接类型 1:短期 2:长期';
 comment on column link_history.active is '是否有效的';
 comment on column link_history.link_hash is '链接的hash值';
+comment on column link_history.idle_ttl is '空闲有效期（秒），超过该时间未被访问则失效';
+comment on column link_history.idle_deadline is '空闲过期时间，每次访问后顺延';
+comment on column link_history.quarantined is '是否被隔离，隔离后跳转显示风险提示页';
+comment on column link_history.quarantine_time is '隔离时间';
+comment on column link_history.require_signature is '是否只能通过签名链接访问，生成签名链接或创建时指定后开启';
+comment on column link_history.deactivated_reason is '失效原因 expired:过期 idle:空闲 abuse:举报下架';
+comment on column link_history.deactivated_at is '失效时间，物理删除的保留期和重新激活的宽限期从该时间起算';
+
+-- 创建链接目标地址修改历史表，每次修改目标地址时记录修改前的地址
+create table if not exists link_history_revision
+(
+    id          bigint                             not null primary key,
+    link_id     bigint                             not null references link_history (id) on delete cascade,
+    origin_url  varchar(4000)                      not null,
+    actor       varchar(128)                       not null,
+    create_time timestamp default CURRENT_TIMESTAMP null
+);
+
+create index if not exists link_history_revision_link_id_index on link_history_revision (link_id, create_time DESC);
+
+comment on table link_history_revision is '链接目标地址修改历史表';
+comment on column link_history_revision.link_id is '链接id';
+comment on column link_history_revision.origin_url is '修改前的原始地址';
+comment on column link_history_revision.actor is '操作人';
+
+-- 创建链接访问统计表，点击次数先累计在Redis中，由后台任务定时刷新
+create table if not exists link_stats
+(
+    link_id         bigint    not null primary key references link_history (id) on delete cascade,
+    click_count     bigint    not null default 0,
+    last_clicked_at timestamp null
+);
+
+comment on table link_stats is '链接访问统计表';
+comment on column link_stats.click_count is '点击次数';
+comment on column link_stats.last_clicked_at is '最近点击时间';
+
+-- 创建点击事件表，重定向时产生的事件经内存队列批量写入
+create table if not exists click_event
+(
+    id              bigserial     not null primary key,
+    link_id         bigint        not null,
+    click_time      timestamp     not null,
+    referrer        varchar(2048) null,
+    user_agent      varchar(1024) null,
+    ip              varchar(64)   null,
+    accept_language varchar(256)  null,
+    referrer_domain varchar(255)  null,
+    browser         varchar(64)   null,
+    os              varchar(64)   null,
+    device          varchar(16)   null,
+    is_bot          boolean       not null default false
+);
+
+-- 来源分析：存量表结构升级
+alter table click_event add column if not exists referrer_domain varchar(255) null;
+alter table click_event add column if not exists browser varchar(64) null;
+alter table click_event add column if not exists os varchar(64) null;
+alter table click_event add column if not exists device varchar(16) null;
+
+-- 机器人识别：存量表结构升级
+alter table click_event add column if not exists is_bot boolean not null default false;
+
+create index if not exists click_event_link_id_click_time_index on click_event (link_id, click_time);
+
+-- 数据保留：按点击时间删除过期的点击事件
+create index if not exists click_event_click_time_index on click_event (click_time);
+
+comment on table click_event is '点击事件表';
+comment on column click_event.link_id is '链接id';
+comment on column click_event.click_time is '点击时间';
+comment on column click_event.referrer is '来源页面';
+comment on column click_event.user_agent is '浏览器标识';
+comment on column click_event.ip is '匿名化后的IP，截断或加盐哈希，不保存原始IP';
+comment on column click_event.accept_language is '浏览器语言';
+comment on column click_event.referrer_domain is '来源域名，从referrer解析';
+comment on column click_event.browser is '浏览器，从user_agent解析';
+comment on column click_event.os is '操作系统，从user_agent解析';
+comment on column click_event.device is '设备类型：Desktop、Mobile、Tablet、Other';
+comment on column click_event.is_bot is '是否为机器人或爬虫';
+
+-- 创建点击统计汇总表，由后台任务定时从click_event汇总，统计接口只查询汇总表
+create table if not exists click_stats_hourly
+(
+    link_id    bigint    not null,
+    bucket     timestamp not null,
+    clicks     bigint    not null default 0,
+    bot_clicks bigint    not null default 0,
+    primary key (link_id, bucket)
+);
+
+create table if not exists click_stats_daily
+(
+    link_id         bigint    not null,
+    bucket          timestamp not null,
+    clicks          bigint    not null default 0,
+    bot_clicks      bigint    not null default 0,
+    unique_visitors bigint    not null default 0,
+    primary key (link_id, bucket)
+);
+
+-- 机器人识别：存量表结构升级
+alter table click_stats_hourly add column if not exists bot_clicks bigint not null default 0;
+alter table click_stats_daily add column if not exists bot_clicks bigint not null default 0;
+
+-- 独立访客：存量表结构升级
+alter table click_stats_daily add column if not exists unique_visitors bigint not null default 0;
+
+comment on table click_stats_hourly is '按小时汇总的点击统计表';
+comment on table click_stats_daily is '按天汇总的点击统计表';
+comment on column click_stats_hourly.bucket is '时间桶起始时间';
+comment on column click_stats_daily.bucket is '时间桶起始时间';
+comment on column click_stats_hourly.clicks is '真人点击次数';
+comment on column click_stats_hourly.bot_clicks is '机器人点击次数';
+comment on column click_stats_daily.clicks is '真人点击次数';
+comment on column click_stats_daily.bot_clicks is '机器人点击次数';
+comment on column click_stats_daily.unique_visitors is '独立访客估算值，来自Redis HyperLogLog';
 
 -- 创建自动更新update_time的触发器函数
 create or replace function update_updated_at_column()
@@ -51,4 +259,213 @@ drop trigger if exists update_link_history_updated_at on link_history;
 create trigger update_link_history_updated_at
     before update on link_history
     for each row
-    execute function update_updated_at_column();
\ No newline at end of file
+    execute function update_updated_at_column();
+
+-- 创建Webhook订阅表
+create table if not exists webhook_subscription
+(
+    id           bigint                             not null primary key,
+    workspace_id bigint                             not null default 0,
+    url          varchar(2048)                      not null,
+    secret      varchar(128)                       not null,
+    events      text[]                             not null default '{}',
+    active      boolean                            not null default true,
+    create_time timestamp default CURRENT_TIMESTAMP null,
+    update_time timestamp default CURRENT_TIMESTAMP null
+);
+
+-- 多租户：存量表结构升级
+alter table webhook_subscription add column if not exists workspace_id bigint not null default 0;
+create index if not exists webhook_subscription_workspace_id_index on webhook_subscription (workspace_id);
+
+comment on table webhook_subscription is 'Webhook订阅表';
+comment on column webhook_subscription.workspace_id is '所属工作空间，只接收该工作空间内链接的事件';
+comment on column webhook_subscription.url is '接收事件的地址';
+comment on column webhook_subscription.secret is '签名密钥，用于计算HMAC-SHA256签名';
+comment on column webhook_subscription.events is '订阅的事件类型，为空表示订阅除link.clicked外的全部事件';
+
+-- 创建Webhook待投递表，投递成功后删除，多次失败后转入死信表
+create table if not exists webhook_delivery
+(
+    id              bigint                             not null primary key,
+    subscription_id bigint                             not null references webhook_subscription (id) on delete cascade,
+    event_id        bigint                             not null,
+    event_type      varchar(32)                        not null,
+    payload         text                               not null,
+    attempts        integer                            not null default 0,
+    next_attempt_at timestamp                          not null default CURRENT_TIMESTAMP,
+    last_error      text                               null,
+    create_time     timestamp default CURRENT_TIMESTAMP null
+);
+
+create index if not exists webhook_delivery_next_attempt_at_index on webhook_delivery (next_attempt_at);
+
+comment on table webhook_delivery is 'Webhook待投递表';
+comment on column webhook_delivery.event_id is '事件id，同一事件投递到不同订阅时相同';
+comment on column webhook_delivery.payload is '请求体，签名基于该内容计算';
+comment on column webhook_delivery.attempts is '已投递次数';
+comment on column webhook_delivery.next_attempt_at is '下次投递时间';
+comment on column webhook_delivery.last_error is '最近一次投递失败的原因';
+
+-- 创建Webhook死信表
+create table if not exists webhook_dead_letter
+(
+    id              bigint                             not null primary key,
+    subscription_id bigint                             not null references webhook_subscription (id) on delete cascade,
+    event_id        bigint                             not null,
+    event_type      varchar(32)                        not null,
+    payload         text                               not null,
+    attempts        integer                            not null,
+    last_error      text                               null,
+    create_time     timestamp                          null,
+    failed_time     timestamp default CURRENT_TIMESTAMP null
+);
+
+create index if not exists webhook_dead_letter_failed_time_index on webhook_dead_letter (failed_time DESC);
+
+comment on table webhook_dead_letter is 'Webhook死信表，超过最大投递次数的事件，可通过管理接口重新投递';
+comment on column webhook_dead_letter.failed_time is '转入死信表的时间';
+
+-- 创建API Key表，只保存Key的哈希值
+create table if not exists api_key
+(
+    id           bigint                             not null primary key,
+    workspace_id bigint                             not null default 0,
+    user_id      bigint                             null,
+    name         varchar(128)                       not null,
+    key_prefix   varchar(16)                        not null,
+    key_hash     varchar(64)                        not null,
+    scopes       text[]                             not null default '{}',
+    revoked      boolean                            not null default false,
+    last_used_at timestamp                          null,
+    create_time  timestamp default CURRENT_TIMESTAMP null,
+    update_time  timestamp default CURRENT_TIMESTAMP null
+);
+
+create unique index if not exists api_key_key_hash_uindex on api_key (key_hash);
+
+-- 多租户：存量表结构升级
+alter table api_key add column if not exists workspace_id bigint not null default 0;
+alter table api_key add column if not exists user_id bigint null;
+
+comment on table api_key is '管理接口API Key表';
+comment on column api_key.workspace_id is '所属工作空间，只能访问该工作空间的数据';
+comment on column api_key.user_id is '所属用户';
+comment on column api_key.name is '名称，作为操作人记录';
+comment on column api_key.key_prefix is 'Key的前几位，用于识别';
+comment on column api_key.key_hash is 'Key的SHA256哈希值';
+comment on column api_key.scopes is '权限范围：read、create、admin';
+comment on column api_key.revoked is '是否已吊销';
+comment on column api_key.last_used_at is '最近使用时间';
+
+-- 链接所有权：存量链接中未绑定用户的API Key创建的链接，创建人从owner_id移到owner_key_id
+update link_history
+set owner_key_id = owner_id,
+    owner_id = null
+where owner_key_id is null
+  and owner_id in (select id from api_key where user_id is null);
+
+-- 创建目标地址规则表，与规则文件中的规则合并生效
+create table if not exists url_rule
+(
+    id          bigint                             not null primary key,
+    kind        varchar(16)                        not null,
+    value       varchar(1024)                      not null,
+    note        varchar(256)                       null,
+    created_by  varchar(128)                       null,
+    create_time timestamp default CURRENT_TIMESTAMP null
+);
+
+create unique index if not exists url_rule_kind_value_uindex on url_rule (kind, value);
+
+comment on table url_rule is '目标地址规则表，对全部工作空间生效';
+comment on column url_rule.kind is '规则类型：block_domain（屏蔽域名）、block_pattern（屏蔽正则）、allow_domain（允许域名）';
+comment on column url_rule.value is '域名（包含子域名）或匹配完整地址的正则表达式';
+comment on column url_rule.note is '备注';
+comment on column url_rule.created_by is '操作人';
+
+create table if not exists abuse_report
+(
+    id            bigint                             not null primary key,
+    link_id       bigint                             not null,
+    workspace_id  bigint                             not null default 0,
+    reason        varchar(16)                        not null,
+    detail        varchar(1024)                      null,
+    reporter_hash varchar(64)                        not null,
+    status        varchar(16)                        not null default 'open',
+    handled_by    varchar(128)                       null,
+    handle_time   timestamp                          null,
+    create_time   timestamp default CURRENT_TIMESTAMP null
+);
+
+-- 同一举报人对同一链接只有一条待处理的举报
+create unique index if not exists abuse_report_link_reporter_open_uindex on abuse_report (link_id, reporter_hash) where status = 'open';
+create index if not exists abuse_report_workspace_create_time_desc_index on abuse_report (workspace_id, create_time DESC);
+-- 统计举报人待处理的举报数
+create index if not exists abuse_report_reporter_open_index on abuse_report (reporter_hash) where status = 'open';
+
+comment on table abuse_report is '滥用举报表';
+comment on column abuse_report.link_id is '被举报的链接';
+comment on column abuse_report.workspace_id is '链接所属工作空间';
+comment on column abuse_report.reason is '举报原因：phishing、malware、spam、other';
+comment on column abuse_report.detail is '举报说明';
+comment on column abuse_report.reporter_hash is '举报人IP的哈希值，不保存原始IP';
+comment on column abuse_report.status is '状态：open 待处理，confirmed 已确认下架，dismissed 已驳回';
+comment on column abuse_report.handled_by is '处理人';
+comment on column abuse_report.handle_time is '处理时间';
+
+create table if not exists audit_log
+(
+    id            bigint                             not null primary key,
+    workspace_id  bigint                             not null default 0,
+    actor         varchar(128)                       not null,
+    actor_key_id  bigint                             null,
+    actor_user_id bigint                             null,
+    action        varchar(32)                        not null,
+    target_type   varchar(16)                        not null,
+    target_id     varchar(64)                        not null,
+    before        jsonb                              null,
+    after         jsonb                              null,
+    request_id    varchar(128)                       null,
+    upstream_request_id varchar(128)                 null,
+    create_time   timestamp default CURRENT_TIMESTAMP null
+);
+
+-- 上游请求id：存量表结构升级，request_id只保存服务端生成的id
+alter table audit_log add column if not exists upstream_request_id varchar(128) null;
+
+create index if not exists audit_log_workspace_create_time_desc_index on audit_log (workspace_id, create_time DESC);
+create index if not exists audit_log_target_index on audit_log (target_type, target_id);
+
+comment on table audit_log is '审计日志表，只允许追加';
+comment on column audit_log.workspace_id is '操作所属工作空间';
+comment on column audit_log.actor is '操作人：API Key或用户的名称，后台任务为system，命令行为cli';
+comment on column audit_log.actor_key_id is '操作人的API Key，JWT认证时为空';
+comment on column audit_log.actor_user_id is '操作人绑定的用户';
+comment on column audit_log.action is '操作类型，如 link.create、link.update、link.purge';
+comment on column audit_log.target_type is '操作对象类型：link、webhook、apikey、user、url_rule、workspace';
+comment on column audit_log.target_id is '操作对象，链接为短码，其他为id';
+comment on column audit_log.before is '操作前的值';
+comment on column audit_log.after is '操作后的值';
+comment on column audit_log.request_id is '服务端生成的请求id，与访问日志和响应头X-Request-Id一致';
+comment on column audit_log.upstream_request_id is '可信代理传入的X-Request-Id，只用于与上游网关的日志关联';
+
+-- 审计日志只允许追加，禁止修改和删除
+create or replace function reject_audit_log_change()
+returns trigger as $$
+begin
+    raise exception 'audit_log is append-only';
+end;
+$$ language plpgsql;
+
+drop trigger if exists audit_log_append_only on audit_log;
+create trigger audit_log_append_only
+    before update or delete on audit_log
+    for each row
+    execute function reject_audit_log_change();
+
+drop trigger if exists audit_log_no_truncate on audit_log;
+create trigger audit_log_no_truncate
+    before truncate on audit_log
+    for each statement
+    execute function reject_audit_log_change();
@@ -0,0 +1,161 @@
+use crate::prepare;
+use crate::service::audit_service::Auditor;
+use crate::service::{api_key_service, workspace_service};
+use crate::types::enums::Role;
+
+const USAGE: &str = r#"用法:
+  short_link                                                启动服务
+  short_link workspace create <名称>                         创建工作空间
+  short_link workspace list                                 查询工作空间列表
+  short_link user create <工作空间id> <名称> <角色> [邮箱]    在工作空间内创建用户，角色：viewer、editor、admin
+  short_link user list <工作空间id>                          查询工作空间内的用户
+  short_link apikey create <名称> <权限范围> [工作空间id]      创建API Key，权限范围以逗号分隔：read,create,admin，默认工作空间为0
+  short_link apikey list [工作空间id]                        查询API Key列表，未指定工作空间时查询全部
+  short_link apikey revoke <id>                             吊销API Key"#;
+
+/// 执行命令行管理命令，失败时以非0状态码退出
+pub async fn run(args: &[String]) {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["workspace", "create", name] => create_workspace(name).await,
+        ["workspace", "list"] => list_workspaces().await,
+        ["user", "create", workspace_id, name, role] => {
+            create_user(workspace_id, name, role, None).await
+        }
+        ["user", "create", workspace_id, name, role, email] => {
+            create_user(workspace_id, name, role, Some(email)).await
+        }
+        ["user", "list", workspace_id] => list_users(workspace_id).await,
+        ["apikey", "create", name, scopes] => create_api_key(name, scopes, "0").await,
+        ["apikey", "create", name, scopes, workspace_id] => {
+            create_api_key(name, scopes, workspace_id).await
+        }
+        ["apikey", "list"] => list_api_keys(None).await,
+        ["apikey", "list", workspace_id] => list_api_keys(Some(workspace_id)).await,
+        ["apikey", "revoke", id] => revoke_api_key(id).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("执行失败: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn parse_id(value: &str) -> Result<i64, crate::AppError> {
+    Ok(value.parse().map_err(|_| anyhow::anyhow!("无效的id: {}", value))?)
+}
+
+async fn create_workspace(name: &str) -> Result<(), crate::AppError> {
+    let db_pool = prepare::create_cli_db_pool().await;
+    let workspace = workspace_service::create_workspace(&db_pool, &Auditor::cli(), name.to_string()).await?;
+    println!("id:   {}", workspace.id);
+    println!("name: {}", workspace.name);
+    Ok(())
+}
+
+async fn list_workspaces() -> Result<(), crate::AppError> {
+    let db_pool = prepare::create_cli_db_pool().await;
+    let workspaces = workspace_service::list_workspaces(&db_pool).await?;
+    println!("{:<20} name", "id");
+    for workspace in workspaces {
+        println!("{:<20} {}", workspace.id, workspace.name);
+    }
+    Ok(())
+}
+
+async fn create_user(
+    workspace_id: &str,
+    name: &str,
+    role: &str,
+    email: Option<&str>,
+) -> Result<(), crate::AppError> {
+    let workspace_id = parse_id(workspace_id)?;
+    let role = Role::parse(role).ok_or_else(|| anyhow::anyhow!("不支持的角色: {}", role))?;
+    let db_pool = prepare::create_cli_db_pool().await;
+    let user = workspace_service::create_user(
+        &db_pool,
+        &Auditor::cli(),
+        workspace_id,
+        name.to_string(),
+        email.map(str::to_string),
+        role,
+    )
+    .await?;
+    println!("id:        {}", user.id);
+    println!("workspace: {}", user.workspace_id);
+    println!("name:      {}", user.name);
+    println!("email:     {}", user.email.unwrap_or_default());
+    println!("role:      {}", user.role);
+    Ok(())
+}
+
+async fn list_users(workspace_id: &str) -> Result<(), crate::AppError> {
+    let workspace_id = parse_id(workspace_id)?;
+    let db_pool = prepare::create_cli_db_pool().await;
+    let users = workspace_service::list_users(&db_pool, workspace_id).await?;
+    println!("{:<20} {:<24} {:<8} email", "id", "name", "role");
+    for user in users {
+        let email = user.email.unwrap_or_default();
+        println!("{:<20} {:<24} {:<8} {}", user.id, user.name, user.role, email);
+    }
+    Ok(())
+}
+
+async fn create_api_key(name: &str, scopes: &str, workspace_id: &str) -> Result<(), crate::AppError> {
+    let workspace_id = parse_id(workspace_id)?;
+    let db_pool = prepare::create_cli_db_pool().await;
+    let scopes = scopes
+        .split(',')
+        .map(|scope| scope.trim().to_lowercase())
+        .filter(|scope| !scope.is_empty())
+        .collect();
+    let created = api_key_service::create_api_key(
+        &db_pool,
+        &Auditor::cli(),
+        workspace_id,
+        None,
+        name.to_string(),
+        scopes,
+    )
+    .await?;
+    println!("id:        {}", created.api_key.id);
+    println!("workspace: {}", created.api_key.workspace_id);
+    println!("name:      {}", created.api_key.name);
+    println!("scopes:    {}", created.api_key.scopes.join(","));
+    println!("key:       {}", created.key);
+    println!("请妥善保存Key，之后无法再次查看");
+    Ok(())
+}
+
+async fn list_api_keys(workspace_id: Option<&str>) -> Result<(), crate::AppError> {
+    let workspace_id = workspace_id.map(parse_id).transpose()?;
+    let db_pool = prepare::create_cli_db_pool().await;
+    let api_keys = api_key_service::list_api_keys(&db_pool, workspace_id).await?;
+    println!(
+        "{:<20} {:<20} {:<24} {:<14} {:<20} revoked",
+        "id", "workspace", "name", "prefix", "scopes"
+    );
+    for api_key in api_keys {
+        println!(
+            "{:<20} {:<20} {:<24} {:<14} {:<20} {}",
+            api_key.id,
+            api_key.workspace_id,
+            api_key.name,
+            api_key.key_prefix,
+            api_key.scopes.join(","),
+            api_key.revoked
+        );
+    }
+    Ok(())
+}
+
+async fn revoke_api_key(id: &str) -> Result<(), crate::AppError> {
+    let id = parse_id(id)?;
+    let db_pool = prepare::create_cli_db_pool().await;
+    api_key_service::revoke_api_key(&db_pool, &Auditor::cli(), None, id).await?;
+    println!("已吊销API Key {}", id);
+    Ok(())
+}
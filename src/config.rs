@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::types::enums::{IpMode, RateAlgorithm, RateGroup};
+use crate::utils::helper::IpNetwork;
+
 pub trait Driver {
     fn to_link(self) -> String;
 }
@@ -9,6 +12,16 @@ pub struct Config {
     pub datasource: Datasource,
     pub redis: Redis,
     pub cleanup: Option<Cleanup>,
+    pub link: Option<Link>,
+    pub access: Option<Access>,
+    pub analytics: Option<Analytics>,
+    pub webhook: Option<Webhook>,
+    pub auth: Option<Auth>,
+    pub rate_limit: Option<RateLimit>,
+    pub blocklist: Option<Blocklist>,
+    pub abuse: Option<Abuse>,
+    pub signed_link: Option<SignedLink>,
+    pub server: Option<Server>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,6 +36,179 @@ pub struct Cleanup {
     pub lock_timeout_secs: Option<u64>,
     /// 分布式锁key
     pub lock_key: Option<String>,
+    /// 失效链接保留天数，超过后物理删除，默认0（不删除）
+    pub retention_days: Option<u64>,
+    /// 物理删除前归档的JSONL文件路径，为空时不归档
+    pub archive_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Link {
+    /// 默认短链域名，创建时未指定域名则使用该域名
+    pub default_domain: Option<String>,
+    /// 允许使用的短链域名列表
+    pub domains: Option<Vec<String>>,
+    /// 链接最大有效期（秒，从创建时间起算），默认365天
+    pub max_lifetime_secs: Option<u64>,
+    /// 失效链接可重新激活的宽限期（秒），默认7天
+    pub reactivate_grace_secs: Option<u64>,
+    /// 目标地址允许的协议，默认http、https
+    pub allowed_schemes: Option<Vec<String>>,
+    /// 是否允许目标地址指向内网和本机地址，默认false
+    pub allow_private_destinations: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Access {
+    /// 访问记录从Redis刷新到数据库的间隔（秒），默认60秒
+    pub flush_interval_secs: Option<u64>,
+    /// 访问记录缓冲队列容量，队列满时丢弃访问记录，默认10000
+    pub channel_capacity: Option<usize>,
+    /// 访问记录批量写入Redis的数量，默认500
+    pub batch_size: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Analytics {
+    /// 点击事件缓冲队列容量，队列满时丢弃事件，默认10000
+    pub channel_capacity: Option<usize>,
+    /// 点击事件批量写入数量，默认500
+    pub batch_size: Option<usize>,
+    /// 点击事件最长写入间隔（毫秒），默认1000
+    pub flush_interval_ms: Option<u64>,
+    /// 点击统计汇总间隔（秒），默认300
+    pub rollup_interval_secs: Option<u64>,
+    /// 每次汇总重新计算最近多少小时的数据，默认2
+    pub rollup_lookback_hours: Option<u64>,
+    /// 识别机器人和爬虫的User-Agent关键字，不区分大小写
+    pub bot_patterns: Option<Vec<String>>,
+    /// 是否通过Redis发布订阅推送实时点击流，默认true
+    pub live_stream: Option<bool>,
+    /// 实时点击流的广播缓冲容量，订阅端处理过慢时会丢弃旧事件，默认1024
+    pub live_buffer: Option<usize>,
+    /// 点击事件中IP的保存方式：truncate（截断）、hash（加盐哈希），默认truncate
+    pub ip_mode: Option<IpMode>,
+    /// 哈希盐的轮换周期（小时），默认24
+    pub ip_salt_rotation_hours: Option<u64>,
+    /// 点击事件保留天数，超过后删除，默认0（不删除）
+    pub retention_days: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Auth {
+    /// 管理接口是否需要API Key，默认true
+    pub enabled: Option<bool>,
+    /// 校验JWT的JWKS文件路径，与jwks_url二选一，都为空时不接受JWT
+    pub jwks_path: Option<String>,
+    /// 校验JWT的JWKS地址
+    pub jwks_url: Option<String>,
+    /// JWKS重新加载间隔（秒），默认300
+    pub jwks_refresh_secs: Option<u64>,
+    /// 要求的签发方（iss），为空时不校验
+    pub jwt_issuer: Option<String>,
+    /// 要求的受众（aud），为空时不校验
+    pub jwt_audience: Option<String>,
+    /// 工作空间id所在的claim，默认workspace_id
+    pub jwt_workspace_claim: Option<String>,
+    /// 角色列表所在的claim，默认roles
+    pub jwt_roles_claim: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Blocklist {
+    /// 规则文件路径（YAML），与管理接口维护的规则合并，为空时只使用管理接口维护的规则
+    pub path: Option<String>,
+    /// 仅允许模式，目标地址必须属于允许的域名，默认false
+    pub allowlist_only: Option<bool>,
+    /// 规则重新加载间隔（秒），默认30
+    pub reload_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Abuse {
+    /// 待处理的举报达到该数量时自动隔离链接，0表示不自动隔离，默认3
+    pub report_threshold: Option<u64>,
+    /// 同一举报人（客户端IP）最多同时存在的待处理举报数，超出后返回429，限制单个举报人可以隔离的链接数，0表示不限制，默认10
+    pub max_open_reports_per_reporter: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Server {
+    /// 可信反向代理的地址或网段（CIDR），只有来自这些地址的请求才读取X-Forwarded-For和X-Real-IP，默认为空即只使用连接地址
+    pub trusted_proxies: Option<Vec<String>>,
+}
+
+impl Server {
+    /// 解析可信代理，忽略格式错误的配置
+    pub fn trusted_proxies(&self) -> Vec<IpNetwork> {
+        self.trusted_proxies
+            .iter()
+            .flatten()
+            .filter_map(|value| {
+                let network = IpNetwork::parse(value);
+                if network.is_none() {
+                    tracing::warn!("忽略无效的可信代理配置: {}", value);
+                }
+                network
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignedLink {
+    /// 签名密钥，可通过环境变量SIGNED_LINK_SECRET覆盖，为空时不能生成签名链接，带签名的请求一律拒绝
+    pub secret: Option<String>,
+    /// 签名链接默认有效期（秒），默认3600
+    pub default_ttl_secs: Option<u64>,
+    /// 签名链接最长有效期（秒），默认7天
+    pub max_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimit {
+    /// 是否启用限流，默认true
+    pub enabled: Option<bool>,
+    /// 短链跳转的限流策略，默认每个IP每60秒600次
+    pub redirect: Option<RatePolicy>,
+    /// 创建链接的限流策略，默认每个IP和每个API Key每60秒30次
+    pub create: Option<RatePolicy>,
+    /// 其他管理接口的限流策略，默认每个IP和每个API Key每60秒300次
+    pub admin: Option<RatePolicy>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RatePolicy {
+    /// 限流算法：sliding_window、token_bucket，创建链接默认token_bucket，其他分组默认sliding_window
+    pub algorithm: Option<RateAlgorithm>,
+    /// 窗口内允许的请求数，令牌桶时为桶容量，0表示不限流
+    pub limit: Option<u64>,
+    /// 窗口长度（秒）
+    pub window_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    /// 投递任务轮询间隔（毫秒），默认1000
+    pub poll_interval_ms: Option<u64>,
+    /// 每次轮询最多投递的数量，默认100
+    pub batch_size: Option<usize>,
+    /// 最大投递次数，超过后转入死信表，默认8
+    pub max_attempts: Option<i32>,
+    /// 首次重试间隔（秒），之后每次翻倍，默认10
+    pub backoff_base_secs: Option<u64>,
+    /// 最大重试间隔（秒），默认3600
+    pub backoff_max_secs: Option<u64>,
+    /// 单次请求超时时间（秒），默认10
+    pub timeout_secs: Option<u64>,
+    /// 订阅缓存刷新间隔（秒），默认30
+    pub refresh_interval_secs: Option<u64>,
+    /// 点击事件Webhook缓冲队列容量，队列满时丢弃事件，默认10000
+    pub click_channel_capacity: Option<usize>,
+    /// 点击事件Webhook批量写入投递记录的数量，默认500
+    pub click_batch_size: Option<usize>,
+    /// 点击事件Webhook最长写入间隔（毫秒），默认1000
+    pub click_flush_interval_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,6 +239,16 @@ impl Default for Config {
             datasource: Datasource::default(),
             redis: Redis::default(),
             cleanup: Some(Cleanup::default()),
+            link: Some(Link::default()),
+            access: Some(Access::default()),
+            analytics: Some(Analytics::default()),
+            webhook: Some(Webhook::default()),
+            auth: Some(Auth::default()),
+            rate_limit: Some(RateLimit::default()),
+            blocklist: Some(Blocklist::default()),
+            abuse: Some(Abuse::default()),
+            signed_link: Some(SignedLink::default()),
+            server: Some(Server::default()),
         }
     }
 }
@@ -65,10 +261,223 @@ impl Default for Cleanup {
             enable_distributed_lock: Some(false),
             lock_timeout_secs: Some(300),
             lock_key: Some("cleanup:lock".to_string()),
+            retention_days: Some(0),
+            archive_path: None,
+        }
+    }
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self {
+            default_domain: Some("localhost".to_string()),
+            domains: Some(vec!["localhost".to_string()]),
+            max_lifetime_secs: Some(31536000),
+            reactivate_grace_secs: Some(604800),
+            allowed_schemes: Some(vec!["http".to_string(), "https".to_string()]),
+            allow_private_destinations: Some(false),
+        }
+    }
+}
+
+impl Default for Access {
+    fn default() -> Self {
+        Self {
+            flush_interval_secs: Some(60),
+            channel_capacity: Some(10000),
+            batch_size: Some(500),
         }
     }
 }
 
+impl Default for Analytics {
+    fn default() -> Self {
+        Self {
+            channel_capacity: Some(10000),
+            batch_size: Some(500),
+            flush_interval_ms: Some(1000),
+            rollup_interval_secs: Some(300),
+            rollup_lookback_hours: Some(2),
+            bot_patterns: Some(default_bot_patterns()),
+            live_stream: Some(true),
+            live_buffer: Some(1024),
+            ip_mode: Some(IpMode::Truncate),
+            ip_salt_rotation_hours: Some(24),
+            retention_days: Some(0),
+        }
+    }
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Self {
+            enabled: Some(true),
+            jwks_path: None,
+            jwks_url: None,
+            jwks_refresh_secs: Some(300),
+            jwt_issuer: None,
+            jwt_audience: None,
+            jwt_workspace_claim: Some("workspace_id".to_string()),
+            jwt_roles_claim: Some("roles".to_string()),
+        }
+    }
+}
+
+impl Default for Blocklist {
+    fn default() -> Self {
+        Self {
+            path: None,
+            allowlist_only: Some(false),
+            reload_secs: Some(30),
+        }
+    }
+}
+
+impl Default for Abuse {
+    fn default() -> Self {
+        Self {
+            report_threshold: Some(3),
+            max_open_reports_per_reporter: Some(10),
+        }
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            trusted_proxies: Some(Vec::new()),
+        }
+    }
+}
+
+impl Default for SignedLink {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            default_ttl_secs: Some(3600),
+            max_ttl_secs: Some(604800),
+        }
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            enabled: Some(true),
+            redirect: Some(RatePolicy::default_for(RateGroup::Redirect)),
+            create: Some(RatePolicy::default_for(RateGroup::Create)),
+            admin: Some(RatePolicy::default_for(RateGroup::Admin)),
+        }
+    }
+}
+
+impl RateLimit {
+    /// 路由分组的限流策略，未配置的字段使用该分组的默认值
+    pub fn policy(&self, group: RateGroup) -> RatePolicy {
+        let configured = match group {
+            RateGroup::Redirect => self.redirect.as_ref(),
+            RateGroup::Create => self.create.as_ref(),
+            RateGroup::Admin => self.admin.as_ref(),
+        };
+        let default = RatePolicy::default_for(group);
+        match configured {
+            Some(policy) => RatePolicy {
+                algorithm: policy.algorithm.or(default.algorithm),
+                limit: policy.limit.or(default.limit),
+                window_secs: policy.window_secs.or(default.window_secs),
+            },
+            None => default,
+        }
+    }
+}
+
+impl RatePolicy {
+    fn default_for(group: RateGroup) -> Self {
+        // 创建链接允许短时突发，使用令牌桶
+        let (algorithm, limit) = match group {
+            RateGroup::Redirect => (RateAlgorithm::SlidingWindow, 600),
+            RateGroup::Create => (RateAlgorithm::TokenBucket, 30),
+            RateGroup::Admin => (RateAlgorithm::SlidingWindow, 300),
+        };
+        Self {
+            algorithm: Some(algorithm),
+            limit: Some(limit),
+            window_secs: Some(60),
+        }
+    }
+}
+
+impl Default for Webhook {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: Some(1000),
+            batch_size: Some(100),
+            max_attempts: Some(8),
+            backoff_base_secs: Some(10),
+            backoff_max_secs: Some(3600),
+            timeout_secs: Some(10),
+            refresh_interval_secs: Some(30),
+            click_channel_capacity: Some(10000),
+            click_batch_size: Some(500),
+            click_flush_interval_ms: Some(1000),
+        }
+    }
+}
+
+/// 默认的机器人User-Agent关键字：聊天应用的链接预览、搜索引擎爬虫、监控探测和命令行工具
+fn default_bot_patterns() -> Vec<String> {
+    [
+        "bot", "crawler", "spider", "slurp", "facebookexternalhit", "whatsapp",
+        "skypeuripreview", "embedly", "preview", "headless", "curl", "wget", "python-requests",
+        "go-http-client", "okhttp", "uptime", "pingdom", "monitor",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
+}
+
+impl Analytics {
+    /// 统一转为小写，未配置时使用默认关键字
+    pub fn normalize_bot_patterns(&mut self) {
+        let patterns = self.bot_patterns.take().unwrap_or_else(default_bot_patterns);
+        self.bot_patterns = Some(
+            patterns
+                .into_iter()
+                .map(|pattern| pattern.trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        );
+    }
+}
+
+impl Link {
+    /// 默认短链域名
+    pub fn default_domain(&self) -> String {
+        self.default_domain
+            .as_deref()
+            .unwrap_or("localhost")
+            .to_lowercase()
+    }
+
+    /// 目标地址的协议是否允许，未配置时只允许http和https
+    pub fn is_allowed_scheme(&self, scheme: &str) -> bool {
+        match &self.allowed_schemes {
+            Some(schemes) => schemes.iter().any(|allowed| allowed.eq_ignore_ascii_case(scheme)),
+            None => scheme == "http" || scheme == "https",
+        }
+    }
+
+    /// 域名是否在允许列表中（默认域名始终允许）
+    pub fn is_allowed_domain(&self, domain: &str) -> bool {
+        domain == self.default_domain()
+            || self
+                .domains
+                .iter()
+                .flatten()
+                .any(|allowed| allowed.eq_ignore_ascii_case(domain))
+    }
+}
+
 impl Default for Datasource {
     fn default() -> Self {
         Self {
@@ -1,31 +1,108 @@
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
+use axum::middleware;
+use axum::response::sse::{KeepAlive, Sse};
+use futures_util::Stream;
 use axum::routing::{get, post};
-use axum::{Json, Router};
+use axum::{Extension, Json, Router};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::handle::{auth, rate_limit};
 use crate::pojo::AppError;
 use crate::{
-    link_service,
-    pojo::{link_history::LinkListResponse, Message, Pagination},
-    types::{IState, MessageResult},
+    link_service::{self, LinkOptions},
+    pojo::{
+        abuse_report::AbuseReportListResponse,
+        api_key::{ApiActor, ApiKeyCreatedResponse, ApiKeyResponse},
+        audit_log::AuditLogListResponse,
+        click_stats::{
+            AnalyticsEraseResponse, ClickBreakdownResponse, ClickSeriesResponse,
+            StatsOverviewResponse,
+        },
+        link_history::{LinkHistoryResponse, LinkListResponse, SignedLinkResponse},
+        link_revision::LinkRevisionResponse,
+        url_rule::UrlRuleResponse,
+        webhook::{WebhookDeadLetterListResponse, WebhookSubscriptionResponse},
+        workspace::{AppUserResponse, WorkspaceResponse},
+        Message, Pagination,
+    },
+    service::{
+        abuse_service, api_key_service,
+        audit_service::{self, AuditFilter, Auditor},
+        live_service, privacy_service, signed_link_service, stats_service, url_rule_service,
+        webhook_service, workspace_service,
+    },
+    types::{
+        enums::{Granularity, Permission, RateGroup, ReportStatus, Role, UrlRuleKind},
+        HandlerResult, IState, MessageResult, RequestId,
+    },
 };
 
-pub fn router() -> Router<Arc<IState>> {
-    Router::new()
-        .route("/link/list", get(link_list))
+/// 管理接口，认证中间件校验API Key，各接口按调用方的角色校验所需权限
+///
+/// 限流在认证之前执行，创建链接和其他管理接口分别使用独立的限流策略
+pub fn router(state: Arc<IState>) -> Router<Arc<IState>> {
+    let create = Router::new()
         .route("/link/create", post(create_link))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authenticate))
+        .route_layer(middleware::from_fn_with_state(
+            (state.clone(), RateGroup::Create),
+            rate_limit::limit,
+        ));
+    let admin = Router::new()
+        .route("/link/list", get(link_list))
+        .route("/link/:code/update", post(update_link))
+        .route("/link/:code/revisions", get(link_revisions))
+        .route("/link/:code/rollback", post(rollback_link))
+        .route("/link/:code/extend", post(extend_link))
+        .route("/link/:code/sign", post(sign_link))
+        .route("/link/:code/stats", get(link_stats))
+        .route("/link/:code/stats/breakdown", get(link_stats_breakdown))
+        .route("/link/:code/analytics/erase", post(erase_link_analytics))
+        .route("/stats/overview", get(stats_overview))
+        .route("/stats/stream", get(stats_stream))
+        .route("/webhook/list", get(webhook_list))
+        .route("/webhook/create", post(create_webhook))
+        .route("/webhook/:id/delete", post(delete_webhook))
+        .route("/webhook/dead-letters", get(webhook_dead_letters))
+        .route("/webhook/dead-letters/:id/replay", post(replay_webhook_dead_letter))
+        .route("/apikey/list", get(api_key_list))
+        .route("/apikey/create", post(create_api_key))
+        .route("/apikey/:id/revoke", post(revoke_api_key))
+        .route("/workspace", get(current_workspace))
+        .route("/user/list", get(user_list))
+        .route("/user/create", post(create_user))
+        .route("/user/:id/role", post(update_user_role))
+        .route("/abuse/reports", get(abuse_reports))
+        .route("/link/:code/quarantine/confirm", post(confirm_takedown))
+        .route("/link/:code/quarantine/release", post(release_quarantine))
+        .route("/url-rule/list", get(url_rule_list))
+        .route("/url-rule/create", post(create_url_rule))
+        .route("/url-rule/:id/delete", post(delete_url_rule))
+        .route("/url-rule/reload", post(reload_url_rules))
+        .route("/audit", get(audit_logs))
         .route("/health/cleanup", get(cleanup_health))
+        .route("/health/analytics", get(analytics_health))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authenticate))
+        .route_layer(middleware::from_fn_with_state(
+            (state, RateGroup::Admin),
+            rate_limit::limit,
+        ));
+    create.merge(admin)
 }
 
 async fn link_list(
     State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
     pagination: Option<Query<Pagination>>,
 ) -> MessageResult<LinkListResponse> {
+    actor.require(Permission::LinkRead)?;
     let Query(pagination) = pagination.unwrap_or_default();
-    match link_service::get_link_list(pool, pagination).await {
+    match link_service::get_link_list(pool, actor.workspace_id, pagination).await {
         Ok(link_list_response) => Ok(Message::ok(link_list_response)),
         Err(e) => {
             tracing::error!("查询链接列表失败: {}", e);
@@ -39,18 +116,671 @@ async fn link_list(
 struct CreateLink {
     #[validate(url(message = "无效"), required(message = "不能为空"))]
     url: Option<String>,
+    /// 短链域名，需在配置的允许列表中，为空时使用默认域名
+    domain: Option<String>,
     duration: Option<u64>,
+    /// 空闲有效期（秒），超过该时间未被访问的链接会被清理
+    idle_ttl: Option<u64>,
+    /// 只能通过签名链接访问，默认false
+    require_signature: Option<bool>,
 }
 
 async fn create_link(
     State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
     Json(payload): Json<CreateLink>,
 ) -> MessageResult<String> {
+    actor.require(Permission::LinkCreate)?;
+    if let Err(e) = payload.validate() {
+        return Err(AppError::bad_request(e));
+    }
+    let (code, _) = link_service::create_link(
+        pool,
+        &actor,
+        &Auditor::new(&actor, &request_id),
+        payload.url.unwrap(),
+        payload.domain,
+        payload.duration,
+        LinkOptions {
+            idle_ttl: payload.idle_ttl,
+            require_signature: payload.require_signature.unwrap_or(false),
+        },
+    )
+    .await?;
+    Ok(Message::ok(code))
+}
+
+#[derive(Deserialize, Validate, Debug)]
+struct UpdateLink {
+    #[validate(url(message = "无效"), required(message = "不能为空"))]
+    url: Option<String>,
+}
+
+/// 修改短链的目标地址
+async fn update_link(
+    State(pool): State<Arc<IState>>,
+    Path(code): Path<String>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<UpdateLink>,
+) -> MessageResult<()> {
+    actor.require(Permission::LinkEdit)?;
+    if let Err(e) = payload.validate() {
+        return Err(AppError::bad_request(e));
+    }
+    let auditor = Auditor::new(&actor, &request_id);
+    link_service::update_link(pool, &actor, &auditor, code, payload.url.unwrap()).await?;
+    Ok(Message::ok(()))
+}
+
+/// 查询短链目标地址的修改历史
+async fn link_revisions(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Path(code): Path<String>,
+) -> MessageResult<Vec<LinkRevisionResponse>> {
+    actor.require(Permission::LinkRead)?;
+    let revisions = link_service::get_link_revisions(pool, actor.workspace_id, code).await?;
+    Ok(Message::ok(revisions))
+}
+
+#[derive(Deserialize, Debug)]
+struct RollbackLink {
+    revision_id: i64,
+}
+
+/// 将短链回滚到指定的修改记录
+async fn rollback_link(
+    State(pool): State<Arc<IState>>,
+    Path(code): Path<String>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<RollbackLink>,
+) -> MessageResult<()> {
+    actor.require(Permission::LinkEdit)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    link_service::rollback_link(pool, &actor, &auditor, code, payload.revision_id).await?;
+    Ok(Message::ok(()))
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct SignLink {
+    /// 签名链接的有效期（秒），为空时使用默认有效期
+    duration: Option<u64>,
+}
+
+/// 生成带过期时间的签名链接，签名过期后失效；链接随之开启签名校验，只能通过签名链接访问
+async fn sign_link(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(code): Path<String>,
+    payload: Option<Json<SignLink>>,
+) -> MessageResult<SignedLinkResponse> {
+    actor.require(Permission::LinkEdit)?;
+    let Json(payload) = payload.unwrap_or_default();
+    let auditor = Auditor::new(&actor, &request_id);
+    let signed =
+        signed_link_service::sign_link(&pool, &actor, &auditor, &code, payload.duration).await?;
+    Ok(Message::ok(signed))
+}
+
+#[derive(Deserialize, Debug)]
+struct ExtendLink {
+    /// 新的过期时间（毫秒时间戳）
+    expire_date: Option<i64>,
+    /// 在当前过期时间基础上顺延的秒数
+    duration: Option<u64>,
+}
+
+/// 延长短链有效期，宽限期内的失效链接会被重新激活
+async fn extend_link(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(code): Path<String>,
+    Json(payload): Json<ExtendLink>,
+) -> MessageResult<LinkHistoryResponse> {
+    actor.require(Permission::LinkEdit)?;
+    let link = link_service::extend_link(
+        pool,
+        &actor,
+        &Auditor::new(&actor, &request_id),
+        code,
+        payload.expire_date,
+        payload.duration,
+    )
+    .await?;
+    Ok(Message::ok(link))
+}
+
+#[derive(Deserialize, Debug)]
+struct StatsQuery {
+    /// 开始时间（毫秒时间戳）
+    from: Option<i64>,
+    /// 结束时间（毫秒时间戳）
+    to: Option<i64>,
+    /// 时间粒度：hour、day，默认day
+    granularity: Option<Granularity>,
+    /// 是否包含机器人的点击，默认false
+    include_bots: Option<bool>,
+}
+
+/// 查询短链按时间分桶的点击统计
+async fn link_stats(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Path(code): Path<String>,
+    Query(query): Query<StatsQuery>,
+) -> MessageResult<ClickSeriesResponse> {
+    actor.require(Permission::StatsRead)?;
+    let granularity = query.granularity.unwrap_or(Granularity::Day);
+    let include_bots = query.include_bots.unwrap_or(false);
+    let series = stats_service::get_click_series(
+        pool,
+        actor.workspace_id,
+        code,
+        query.from,
+        query.to,
+        granularity,
+        include_bots,
+    )
+    .await?;
+    Ok(Message::ok(series))
+}
+
+#[derive(Deserialize, Debug)]
+struct BreakdownQuery {
+    /// 开始时间（毫秒时间戳）
+    from: Option<i64>,
+    /// 结束时间（毫秒时间戳）
+    to: Option<i64>,
+    /// 每个维度返回的条数，默认10
+    limit: Option<usize>,
+    /// 是否包含机器人的点击，默认false
+    include_bots: Option<bool>,
+}
+
+/// 查询短链的来源域名、浏览器、操作系统、设备类型分布
+async fn link_stats_breakdown(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Path(code): Path<String>,
+    Query(query): Query<BreakdownQuery>,
+) -> MessageResult<ClickBreakdownResponse> {
+    actor.require(Permission::StatsRead)?;
+    let include_bots = query.include_bots.unwrap_or(false);
+    let breakdown = stats_service::get_click_breakdown(
+        pool,
+        actor.workspace_id,
+        code,
+        query.from,
+        query.to,
+        query.limit,
+        include_bots,
+    )
+    .await?;
+    Ok(Message::ok(breakdown))
+}
+
+/// 清除短链的全部统计数据，短链本身不受影响
+async fn erase_link_analytics(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(code): Path<String>,
+) -> MessageResult<AnalyticsEraseResponse> {
+    actor.require(Permission::AnalyticsPurge)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    let erased =
+        privacy_service::erase_link_analytics(pool, &auditor, actor.workspace_id, code).await?;
+    Ok(Message::ok(erased))
+}
+
+#[derive(Deserialize, Debug)]
+struct OverviewQuery {
+    /// 开始时间（毫秒时间戳）
+    from: Option<i64>,
+    /// 结束时间（毫秒时间戳）
+    to: Option<i64>,
+    /// 点击排行返回的条数，默认10
+    limit: Option<usize>,
+    /// 是否包含机器人的点击，默认false
+    include_bots: Option<bool>,
+}
+
+/// 工作空间概览：链接状态分布、每天新建链接数、每天点击数和点击排行
+async fn stats_overview(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Query(query): Query<OverviewQuery>,
+) -> MessageResult<StatsOverviewResponse> {
+    actor.require(Permission::StatsRead)?;
+    let include_bots = query.include_bots.unwrap_or(false);
+    let overview = stats_service::get_overview(
+        pool,
+        actor.workspace_id,
+        query.from,
+        query.to,
+        query.limit,
+        include_bots,
+    )
+    .await?;
+    Ok(Message::ok(overview))
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamQuery {
+    /// 只推送该短码的点击，为空时推送全部
+    code: Option<String>,
+    /// 是否包含机器人的点击，默认false
+    include_bots: Option<bool>,
+}
+
+/// 以SSE推送实时点击流
+async fn stats_stream(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Query(query): Query<StreamQuery>,
+) -> HandlerResult<Sse<impl Stream<Item = Result<axum::response::sse::Event, axum::Error>>>> {
+    actor.require(Permission::StatsRead)?;
+    if !pool.analytics_config.live_stream.unwrap_or(true) {
+        return Err(AppError::from(anyhow::anyhow!("实时点击流未启用")));
+    }
+    if let Some(code) = &query.code {
+        link_service::query_link_by_code(&pool, actor.workspace_id, code).await?;
+    }
+    let include_bots = query.include_bots.unwrap_or(false);
+    let stream = live_service::click_stream(&pool, actor.workspace_id, query.code, include_bots);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Deserialize, Validate, Debug)]
+struct CreateWebhook {
+    #[validate(url(message = "无效"), required(message = "不能为空"))]
+    url: Option<String>,
+    /// 订阅的事件类型，为空表示订阅除link.clicked外的全部事件
+    #[serde(default)]
+    events: Vec<String>,
+    /// 签名密钥，为空时随机生成
+    secret: Option<String>,
+}
+
+/// 创建Webhook订阅，返回的密钥用于校验请求签名
+async fn create_webhook(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateWebhook>,
+) -> MessageResult<WebhookSubscriptionResponse> {
+    actor.require(Permission::WebhookManage)?;
     if let Err(e) = payload.validate() {
-        return Err(AppError::from(e));
+        return Err(AppError::bad_request(e));
     }
-    let res = link_service::create_link(pool, payload.url.unwrap(), payload.duration).await?;
-    Ok(Message::ok(res))
+    let subscription = webhook_service::create_subscription(
+        pool,
+        &Auditor::new(&actor, &request_id),
+        actor.workspace_id,
+        payload.url.unwrap(),
+        payload.events,
+        payload.secret,
+    )
+    .await?;
+    Ok(Message::ok(subscription))
+}
+
+/// 查询Webhook订阅列表
+async fn webhook_list(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<Vec<WebhookSubscriptionResponse>> {
+    actor.require(Permission::WebhookManage)?;
+    let subscriptions = webhook_service::list_subscriptions(pool, actor.workspace_id).await?;
+    Ok(Message::ok(subscriptions))
+}
+
+/// 删除Webhook订阅
+async fn delete_webhook(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<i64>,
+) -> MessageResult<()> {
+    actor.require(Permission::WebhookManage)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    webhook_service::delete_subscription(pool, &auditor, actor.workspace_id, id).await?;
+    Ok(Message::ok(()))
+}
+
+/// 分页查询投递失败的Webhook
+async fn webhook_dead_letters(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    pagination: Option<Query<Pagination>>,
+) -> MessageResult<WebhookDeadLetterListResponse> {
+    actor.require(Permission::WebhookManage)?;
+    let Query(pagination) = pagination.unwrap_or_default();
+    let dead_letters = webhook_service::list_dead_letters(pool, actor.workspace_id, pagination).await?;
+    Ok(Message::ok(dead_letters))
+}
+
+/// 重新投递失败的Webhook
+async fn replay_webhook_dead_letter(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<i64>,
+) -> MessageResult<()> {
+    actor.require(Permission::WebhookManage)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    webhook_service::replay_dead_letter(pool, &auditor, actor.workspace_id, id).await?;
+    Ok(Message::ok(()))
+}
+
+#[derive(Deserialize, Debug)]
+struct CreateApiKey {
+    /// 名称，作为操作人记录
+    name: String,
+    /// 权限范围：read、create、admin
+    scopes: Vec<String>,
+    /// 所属用户，需属于当前工作空间
+    user_id: Option<i64>,
+}
+
+/// 在当前工作空间创建API Key，明文Key只在响应中返回一次
+async fn create_api_key(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateApiKey>,
+) -> MessageResult<ApiKeyCreatedResponse> {
+    actor.require(Permission::ApiKeyManage)?;
+    let created = api_key_service::create_api_key(
+        &pool.db_pool,
+        &Auditor::new(&actor, &request_id),
+        actor.workspace_id,
+        payload.user_id,
+        payload.name,
+        payload.scopes,
+    )
+    .await?;
+    Ok(Message::ok(created))
+}
+
+/// 查询API Key列表
+async fn api_key_list(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<Vec<ApiKeyResponse>> {
+    actor.require(Permission::ApiKeyManage)?;
+    let api_keys = api_key_service::list_api_keys(&pool.db_pool, Some(actor.workspace_id)).await?;
+    Ok(Message::ok(api_keys))
+}
+
+/// 吊销API Key
+async fn revoke_api_key(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<i64>,
+) -> MessageResult<()> {
+    actor.require(Permission::ApiKeyManage)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    api_key_service::revoke_api_key(&pool.db_pool, &auditor, Some(actor.workspace_id), id).await?;
+    Ok(Message::ok(()))
+}
+
+/// 查询当前调用方所属的工作空间
+async fn current_workspace(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<WorkspaceResponse> {
+    actor.require(Permission::LinkRead)?;
+    let workspace = workspace_service::get_workspace(&pool.db_pool, actor.workspace_id).await?;
+    Ok(Message::ok(workspace))
+}
+
+#[derive(Deserialize, Validate, Debug)]
+struct CreateUser {
+    name: String,
+    #[validate(email(message = "无效"))]
+    email: Option<String>,
+    /// 角色：viewer、editor、admin，默认viewer
+    role: Option<Role>,
+}
+
+/// 在当前工作空间创建用户
+async fn create_user(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateUser>,
+) -> MessageResult<AppUserResponse> {
+    actor.require(Permission::UserManage)?;
+    if let Err(e) = payload.validate() {
+        return Err(AppError::bad_request(e));
+    }
+    let user = workspace_service::create_user(
+        &pool.db_pool,
+        &Auditor::new(&actor, &request_id),
+        actor.workspace_id,
+        payload.name,
+        payload.email,
+        payload.role.unwrap_or(Role::Viewer),
+    )
+    .await?;
+    Ok(Message::ok(user))
+}
+
+#[derive(Deserialize, Debug)]
+struct UpdateUserRole {
+    role: Role,
+}
+
+/// 修改当前工作空间内用户的角色，用户的API Key随之生效
+async fn update_user_role(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<i64>,
+    Json(payload): Json<UpdateUserRole>,
+) -> MessageResult<AppUserResponse> {
+    actor.require(Permission::UserManage)?;
+    let user = workspace_service::update_user_role(
+        &pool.db_pool,
+        &Auditor::new(&actor, &request_id),
+        actor.workspace_id,
+        id,
+        payload.role,
+    )
+    .await?;
+    Ok(Message::ok(user))
+}
+
+#[derive(Deserialize, Debug)]
+struct ReportQuery {
+    /// 举报状态：open、confirmed、dismissed，为空时查询全部
+    status: Option<ReportStatus>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+}
+
+/// 查询当前工作空间的滥用举报
+async fn abuse_reports(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Query(query): Query<ReportQuery>,
+) -> MessageResult<AbuseReportListResponse> {
+    actor.require(Permission::AbuseManage)?;
+    let pagination = Pagination {
+        page: query.page.unwrap_or(1),
+        page_size: query.page_size.unwrap_or(10),
+        ..Pagination::default()
+    };
+    let reports =
+        abuse_service::list_reports(&pool, actor.workspace_id, query.status, pagination).await?;
+    Ok(Message::ok(reports))
+}
+
+/// 确认举报并下架短链
+async fn confirm_takedown(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(code): Path<String>,
+) -> MessageResult<()> {
+    actor.require(Permission::AbuseManage)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    abuse_service::confirm_takedown(&pool, &actor, &auditor, &code).await?;
+    Ok(Message::ok(()))
+}
+
+/// 驳回举报并解除短链的隔离
+async fn release_quarantine(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(code): Path<String>,
+) -> MessageResult<()> {
+    actor.require(Permission::AbuseManage)?;
+    let auditor = Auditor::new(&actor, &request_id);
+    abuse_service::release(&pool, &actor, &auditor, &code).await?;
+    Ok(Message::ok(()))
+}
+
+/// 目标地址规则对全部工作空间生效，只允许默认工作空间的管理员维护
+fn require_url_rule_manage(actor: &ApiActor) -> HandlerResult<()> {
+    actor.require(Permission::UrlRuleManage)?;
+    if actor.workspace_id != 0 {
+        return Err(AppError::forbidden(anyhow::anyhow!("只有默认工作空间可以管理目标地址规则")));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Validate, Debug)]
+struct CreateUrlRule {
+    kind: UrlRuleKind,
+    /// 域名或正则表达式
+    #[validate(length(min = 1, max = 1024, message = "长度需在1到1024之间"))]
+    value: String,
+    #[validate(length(max = 256, message = "长度不能超过256"))]
+    note: Option<String>,
+}
+
+/// 新增目标地址规则，屏蔽规则对存量链接的跳转同样生效
+async fn create_url_rule(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<CreateUrlRule>,
+) -> MessageResult<UrlRuleResponse> {
+    require_url_rule_manage(&actor)?;
+    if let Err(e) = payload.validate() {
+        return Err(AppError::bad_request(e));
+    }
+    let rule = url_rule_service::create_rule(
+        &pool,
+        &Auditor::new(&actor, &request_id),
+        payload.kind,
+        payload.value,
+        payload.note,
+    )
+    .await?;
+    Ok(Message::ok(rule))
+}
+
+/// 查询管理接口维护的目标地址规则
+async fn url_rule_list(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<Vec<UrlRuleResponse>> {
+    require_url_rule_manage(&actor)?;
+    let rules = url_rule_service::list_rules(&pool).await?;
+    Ok(Message::ok(rules))
+}
+
+/// 删除目标地址规则
+async fn delete_url_rule(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Extension(request_id): Extension<RequestId>,
+    Path(id): Path<i64>,
+) -> MessageResult<()> {
+    require_url_rule_manage(&actor)?;
+    url_rule_service::delete_rule(&pool, &Auditor::new(&actor, &request_id), id).await?;
+    Ok(Message::ok(()))
+}
+
+/// 立即重新加载规则文件和数据库中的规则，返回生效的规则数量
+async fn reload_url_rules(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<usize> {
+    require_url_rule_manage(&actor)?;
+    let count = url_rule_service::refresh_rules(&pool).await?;
+    Ok(Message::ok(count))
+}
+
+/// 查询当前工作空间的用户列表
+async fn user_list(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<Vec<AppUserResponse>> {
+    actor.require(Permission::UserManage)?;
+    let users = workspace_service::list_users(&pool.db_pool, actor.workspace_id).await?;
+    Ok(Message::ok(users))
+}
+
+#[derive(Deserialize, Debug)]
+struct AuditQuery {
+    /// 操作类型，如 link.update
+    action: Option<String>,
+    /// 操作对象类型：link、webhook、apikey、user、url_rule、workspace
+    target_type: Option<String>,
+    /// 操作对象，链接为短码，其他为id
+    target_id: Option<String>,
+    /// 操作人
+    actor: Option<String>,
+    /// 开始时间（毫秒时间戳）
+    from: Option<i64>,
+    /// 结束时间（毫秒时间戳，不包含）
+    to: Option<i64>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+}
+
+/// 查询当前工作空间的审计日志
+async fn audit_logs(
+    State(pool): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+    Query(query): Query<AuditQuery>,
+) -> MessageResult<AuditLogListResponse> {
+    actor.require(Permission::AuditRead)?;
+    let from_millis = |millis: Option<i64>| -> HandlerResult<Option<NaiveDateTime>> {
+        millis
+            .map(|millis| {
+                chrono::DateTime::from_timestamp_millis(millis)
+                    .map(|dt| dt.naive_utc())
+                    .ok_or_else(|| AppError::bad_request(anyhow::anyhow!("无效的时间戳: {}", millis)))
+            })
+            .transpose()
+    };
+    let filter = AuditFilter {
+        action: query.action,
+        target_type: query.target_type,
+        target_id: query.target_id,
+        actor: query.actor,
+        from: from_millis(query.from)?,
+        to: from_millis(query.to)?,
+    };
+    let pagination = Pagination {
+        page: query.page.unwrap_or(1),
+        page_size: query.page_size.unwrap_or(10),
+        ..Pagination::default()
+    };
+    let logs = audit_service::list_logs(&pool, actor.workspace_id, filter, pagination).await?;
+    Ok(Message::ok(logs))
 }
 
 /// 清理任务健康检查响应
@@ -68,10 +798,18 @@ struct CleanupHealthResponse {
     total_cleanup_runs: u64,
     /// 总清理数量
     total_cleaned: u64,
+    /// 最后一次物理删除数量
+    last_purge_count: usize,
+    /// 总物理删除数量
+    total_purged: u64,
 }
 
 /// 清理任务健康检查端点
-async fn cleanup_health(State(state): State<Arc<IState>>) -> MessageResult<CleanupHealthResponse> {
+async fn cleanup_health(
+    State(state): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<CleanupHealthResponse> {
+    actor.require(Permission::StatsRead)?;
     let stats = state.cleanup_stats.read().await;
     
     let status = if stats.last_cleanup_time.is_some() {
@@ -93,7 +831,42 @@ async fn cleanup_health(State(state): State<Arc<IState>>) -> MessageResult<Clean
         last_cleanup_count: stats.last_cleanup_count,
         total_cleanup_runs: stats.total_cleanup_runs,
         total_cleaned: stats.total_cleaned,
+        last_purge_count: stats.last_purge_count,
+        total_purged: stats.total_purged,
     };
     
     Ok(Message::ok(response))
 }
+
+/// 点击事件写入健康检查响应
+#[derive(Serialize, Debug)]
+struct AnalyticsHealthResponse {
+    /// 队列容量
+    channel_capacity: usize,
+    /// 队列中等待写入的事件数量
+    queued: usize,
+    /// 已写入的事件总数
+    total_written: u64,
+    /// 因队列已满或写入失败而丢弃的事件总数
+    total_dropped: u64,
+    /// 因队列已满或写入失败而丢弃的点击事件Webhook总数
+    webhook_click_dropped: u64,
+}
+
+/// 点击事件写入健康检查端点
+async fn analytics_health(
+    State(state): State<Arc<IState>>,
+    Extension(actor): Extension<ApiActor>,
+) -> MessageResult<AnalyticsHealthResponse> {
+    actor.require(Permission::StatsRead)?;
+    let channel_capacity = state.click_tx.max_capacity();
+    let response = AnalyticsHealthResponse {
+        channel_capacity,
+        queued: channel_capacity - state.click_tx.capacity(),
+        total_written: state.click_stats.written.load(Ordering::Relaxed),
+        total_dropped: state.click_stats.dropped.load(Ordering::Relaxed),
+        webhook_click_dropped: state.webhook_click_dropped.load(Ordering::Relaxed),
+    };
+
+    Ok(Message::ok(response))
+}
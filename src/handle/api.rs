@@ -1,18 +1,207 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
-use axum::response::Redirect;
-use axum::routing::get;
-use axum::Router;
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use chrono::Utc;
+use serde::Deserialize;
+use validator::Validate;
 
-use crate::types::IState;
-use crate::{service::link_service, RedirectResult};
+use crate::pojo::click_event::{ClickEvent, LiveClick};
+use crate::pojo::{AppError, Message};
+use crate::handle::rate_limit;
+use crate::types::enums::{AbuseReason, RateGroup, WebhookEvent};
+use crate::types::{HandlerResult, IState, MessageResult, RequestId};
+use crate::utils::helper::{calculate_sha256, client_ip, decode_base62, escape_html};
+use crate::utils::user_agent::is_bot;
+use crate::{
+    service::{
+        abuse_service, access_service, click_service, link_service, live_service,
+        privacy_service, signed_link_service, url_rule_service, webhook_service,
+    },
+};
 
-pub fn router() -> Router<Arc<IState>> {
-    Router::new().route("/s/:hash", get(redirect))
+/// 被隔离链接的风险提示页，`{url}` 为转义后的目标地址，`{continue}` 为继续访问的链接
+const QUARANTINE_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex, nofollow">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>安全提示</title>
+</head>
+<body style="font-family: sans-serif; max-width: 640px; margin: 80px auto; padding: 0 16px; line-height: 1.6">
+<h2>⚠️ 该链接可能存在风险</h2>
+<p>该短链已被多名用户举报，正在等待审核，目标网站可能是钓鱼网站或包含恶意软件。</p>
+<p>目标地址：<code style="word-break: break-all">{url}</code></p>
+<p>请勿在该网站输入账号、密码或支付信息。</p>
+<p>{continue}</p>
+</body>
+</html>
+"#;
+
+/// 公开接口，短链跳转和举报按客户端IP限流
+pub fn router(state: Arc<IState>) -> Router<Arc<IState>> {
+    Router::new()
+        .route("/s/:hash", get(redirect))
+        .route("/s/:hash/report", post(report_link))
+        .route_layer(middleware::from_fn_with_state(
+            (state, RateGroup::Redirect),
+            rate_limit::limit,
+        ))
+}
+
+/// 签名链接的参数，两者同时出现时校验签名
+#[derive(Deserialize, Debug)]
+struct SignedQuery {
+    /// 过期时间（Unix时间戳，秒）
+    exp: Option<i64>,
+    sig: Option<String>,
+}
+
+async fn redirect(
+    State(pool): State<Arc<IState>>,
+    Path(hash): Path<String>,
+    Query(signed): Query<SignedQuery>,
+    ConnectInfo(remote): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> HandlerResult<Response> {
+    // 签名在查询数据库和缓存之前校验，伪造或过期的签名链接不会产生任何查询
+    let is_signed =
+        signed_link_service::check_request(&pool, &hash, signed.exp, signed.sig.as_deref())?;
+    let link_id = decode_base62(&hash).map_err(AppError::not_found)? as i64;
+    let host = header_value(&headers, header::HOST);
+    let target = link_service::query_origin_url(pool.clone(), host, hash).await?;
+    if target.require_signature && !is_signed {
+        return Err(AppError::forbidden(anyhow::anyhow!("该短链只能通过签名链接访问")));
+    }
+    let url = target.url;
+    let workspace_id = target.workspace_id;
+    if url_rule_service::is_blocked(&pool, &url) {
+        return Err(AppError::forbidden(anyhow::anyhow!("链接已被禁用")));
+    }
+    // 被隔离的链接不直接跳转，也不计入点击
+    if target.quarantined {
+        return Ok(quarantine_page(&url));
+    }
+
+    let ip = client_ip(&headers, remote.ip(), &pool.trusted_proxies);
+    let user_agent = header_value(&headers, header::USER_AGENT);
+    let accept_language = header_value(&headers, header::ACCEPT_LANGUAGE);
+    let bot_patterns = pool.analytics_config.bot_patterns.as_deref().unwrap_or_default();
+    let is_bot = is_bot(user_agent.as_deref(), bot_patterns);
+
+    // 机器人照常重定向，但不计入点击次数、独立访客，也不顺延空闲过期时间
+    if !is_bot {
+        // 访客指纹只以哈希形式写入HyperLogLog，不保存原始IP
+        let visitor = calculate_sha256(&format!(
+            "{}|{}|{}",
+            ip,
+            user_agent.as_deref().unwrap_or_default(),
+            accept_language.as_deref().unwrap_or_default()
+        ));
+        access_service::record_access(&pool, link_id, visitor);
+    }
+
+    let event = ClickEvent {
+        link_id,
+        click_time: Utc::now().naive_utc(),
+        referrer: header_value(&headers, header::REFERER),
+        user_agent,
+        ip: Some(privacy_service::storage_ip(&pool, ip).await),
+        accept_language,
+        is_bot,
+    };
+    notify_click(&pool, &event, workspace_id);
+    click_service::emit(&pool, event);
+
+    // 使用临时重定向且禁止缓存：浏览器缓存的301会绕过修改目标地址、隔离、屏蔽规则和签名过期，也不再产生点击
+    let headers = [(header::CACHE_CONTROL, "no-store")];
+    Ok((headers, Redirect::temporary(&url)).into_response())
+}
+
+fn quarantine_page(url: &str) -> Response {
+    let page = QUARANTINE_PAGE
+        .replace("{continue}", &continue_link(url))
+        .replace("{url}", &escape_html(url));
+    let headers = [(header::CACHE_CONTROL, "no-store")];
+    (headers, Html(page)).into_response()
 }
 
-async fn redirect(State(pool): State<Arc<IState>>, Path(hash): Path<String>) -> RedirectResult {
-    let url = link_service::query_origin_url(pool, hash).await?;
-    Ok(Redirect::permanent(&url))
+/// 继续访问的链接，只有http和https地址生成链接，存量数据中的javascript:等地址只显示文字
+fn continue_link(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => format!(
+            r#"<a href="{}" rel="noopener noreferrer nofollow">我了解风险，继续访问</a>"#,
+            escape_html(parsed.as_str())
+        ),
+        _ => "目标地址的协议不受支持，无法继续访问".to_string(),
+    }
+}
+
+#[derive(Deserialize, Validate, Debug)]
+struct ReportLink {
+    reason: AbuseReason,
+    #[validate(length(max = 1000, message = "长度不能超过1000"))]
+    detail: Option<String>,
+}
+
+/// 举报短链，同一IP对同一链接的重复举报只记录一次
+async fn report_link(
+    State(pool): State<Arc<IState>>,
+    Path(hash): Path<String>,
+    ConnectInfo(remote): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(request_id): Extension<RequestId>,
+    Json(payload): Json<ReportLink>,
+) -> MessageResult<()> {
+    if let Err(e) = payload.validate() {
+        return Err(AppError::bad_request(e));
+    }
+    let host = header_value(&headers, header::HOST);
+    let ip = client_ip(&headers, remote.ip(), &pool.trusted_proxies);
+    abuse_service::report(&pool, host, &hash, ip, payload.reason, payload.detail, &request_id)
+        .await?;
+    Ok(Message::ok(()))
+}
+
+/// 推送实时点击流并派发点击事件，两者都按链接所属的工作空间分发
+fn notify_click(state: &Arc<IState>, event: &ClickEvent, workspace_id: i64) {
+    let live = state.analytics_config.live_stream.unwrap_or(true);
+    let webhook = webhook_service::is_subscribed(state, WebhookEvent::LinkClicked);
+    if !live && !webhook {
+        return;
+    }
+    let click = LiveClick::new(event, workspace_id);
+    if webhook {
+        webhook_service::emit_click(state, click.clone());
+    }
+    if live {
+        live_service::publish(state, click);
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continue_link_only_for_http() {
+        let link = continue_link("https://93.184.216.34/a?b=1&c=\"2\"");
+        assert!(link.starts_with(r#"<a href="https://93.184.216.34/a?b=1&amp;c=%222%22""#));
+        assert!(!continue_link("javascript:alert(1)").contains("<a"));
+        assert!(!continue_link(" JavaScript:alert(1)").contains("href"));
+        assert!(!continue_link("data:text/html,<script>alert(1)</script>").contains("href"));
+    }
 }
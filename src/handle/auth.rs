@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::pojo::api_key::ApiActor;
+use crate::pojo::AppError;
+use crate::service::{api_key_service, jwt_service, workspace_service};
+use crate::types::enums::Role;
+use crate::types::IState;
+
+/// 管理接口的认证中间件，校验API Key或JWT后把调用方写入请求扩展，由各接口按角色校验权限
+///
+/// 未启用认证时不校验，调用方为admin，操作人取自请求头 `X-Operator`，工作空间取自请求头 `X-Workspace`，
+/// 工作空间不存在时返回404
+pub async fn authenticate(
+    State(state): State<Arc<IState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if !state.auth_config.enabled.unwrap_or(true) {
+        let Some(workspace_id) = workspace(req.headers()) else {
+            return AppError::bad_request(anyhow::anyhow!("无效的工作空间")).into_response();
+        };
+        if let Err(e) = workspace_service::get_workspace(&state.db_pool, workspace_id).await {
+            return e.into_response();
+        }
+        let actor = ApiActor {
+            key_id: None,
+            name: operator(req.headers()),
+            role: Role::Admin,
+            workspace_id,
+            user_id: None,
+        };
+        req.extensions_mut().insert(actor);
+        return next.run(req).await;
+    }
+
+    let Some(key) = api_key(req.headers()) else {
+        return AppError::unauthorized(anyhow::anyhow!("缺少API Key")).into_response();
+    };
+    // API Key以固定前缀开头，其他三段式的凭证按SSO签发的JWT校验
+    if jwt_service::is_enabled(&state.auth_config) && jwt_service::is_jwt(&key) {
+        return match jwt_service::authenticate(&state, &key).await {
+            Ok(actor) => {
+                req.extensions_mut().insert(actor);
+                next.run(req).await
+            }
+            Err(e) => e.into_response(),
+        };
+    }
+    let api_key = match api_key_service::authenticate(&state, &key).await {
+        Ok(Some(api_key)) => api_key,
+        Ok(None) => return AppError::unauthorized(anyhow::anyhow!("无效的API Key")).into_response(),
+        Err(e) => {
+            tracing::error!("校验API Key失败: {}", e);
+            return e.into_response();
+        }
+    };
+    let Some(role) = api_key.role() else {
+        return AppError::forbidden(anyhow::anyhow!("API Key没有有效的角色")).into_response();
+    };
+
+    let actor = ApiActor {
+        key_id: Some(api_key.id),
+        name: api_key.name,
+        role,
+        workspace_id: api_key.workspace_id,
+        user_id: api_key.user_id,
+    };
+    req.extensions_mut().insert(actor);
+    next.run(req).await
+}
+
+/// 从 `Authorization: Bearer <key>` 或 `X-API-Key` 请求头读取API Key或JWT
+pub(crate) fn api_key(headers: &HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| headers.get("x-api-key").and_then(|value| value.to_str().ok()))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 操作人，取自请求头 `X-Operator`
+fn operator(headers: &HeaderMap) -> String {
+    headers
+        .get("x-operator")
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or("anonymous")
+        .to_string()
+}
+
+/// 工作空间，取自请求头 `X-Workspace`，未指定时为默认工作空间
+fn workspace(headers: &HeaderMap) -> Option<i64> {
+    match headers.get("x-workspace") {
+        None => Some(0),
+        Some(value) => value.to_str().ok().and_then(|value| value.trim().parse().ok()),
+    }
+}
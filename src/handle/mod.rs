@@ -1,2 +1,4 @@
 pub mod admin;
 pub mod api;
+pub mod auth;
+pub mod rate_limit;
@@ -0,0 +1,61 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::handle::auth;
+use crate::pojo::AppError;
+use crate::service::rate_limit_service;
+use crate::types::enums::RateGroup;
+use crate::types::IState;
+use crate::utils::helper::{calculate_sha256, client_ip};
+
+/// 限流中间件，计数保存在Redis中，多实例共享同一份限额
+///
+/// 每个请求按客户端IP计数，携带API Key或JWT时再按凭证计数，任一超出限制即返回429和 `Retry-After`；
+/// 凭证只保存哈希值。Redis不可用时放行，避免限流故障导致服务不可用
+pub async fn limit(
+    State((state, group)): State<(Arc<IState>, RateGroup)>,
+    ConnectInfo(remote): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let config = &state.rate_limit_config;
+    if !config.enabled.unwrap_or(true) {
+        return next.run(req).await;
+    }
+    let policy = config.policy(group);
+
+    let mut subjects = vec![format!("ip:{}", client_ip(req.headers(), remote.ip(), &state.trusted_proxies))];
+    if group != RateGroup::Redirect
+        && let Some(key) = auth::api_key(req.headers())
+    {
+        subjects.push(format!("key:{}", calculate_sha256(&key)));
+    }
+
+    for subject in subjects {
+        match rate_limit_service::check(&state, group, &policy, &subject).await {
+            Ok(None) => {}
+            Ok(Some(retry_after)) => {
+                tracing::debug!("{} 超出 {} 分组的限流", subject, group.as_str());
+                let mut response = AppError::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    anyhow::anyhow!("请求过于频繁，请 {} 秒后重试", retry_after),
+                )
+                .into_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+                return response;
+            }
+            Err(e) => {
+                tracing::warn!("限流计数失败，放行请求: {}", e);
+                break;
+            }
+        }
+    }
+    next.run(req).await
+}
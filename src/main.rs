@@ -1,9 +1,9 @@
 use std::io;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
 
-use axum::http::{Method, Request};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request};
 use axum::Router;
 use axum::{
     body::{Body, Bytes},
@@ -26,40 +26,70 @@ use idgen::{IdGeneratorOptions, YitIdHelper};
 use crate::{
     pojo::AppError,
     pojo::Message,
-    service::{link_base_service, link_service, cleanup_service},
-    types::{IState, RedirectResult},
+    service::{
+        access_service, click_service, cleanup_service, link_base_service, link_service,
+        jwt_service, live_service, privacy_service, stats_service, url_rule_service,
+        webhook_service,
+    },
+    types::{IState, RequestId},
 };
 
-static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(0);
+const REQUEST_ID_HEADER: &str = "x-request-id";
 
+/// 请求id记录到审计日志中，使用全局唯一的id，重启后不重复
 fn generate_request_id() -> String {
-    let counter = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
-    format!("req_{:x}", counter)
+    format!("req_{:x}", YitIdHelper::next_id())
 }
 
+/// 上游传入的X-Request-Id，只接受不超过128个字符的可见ASCII字符
+///
+/// 客户端可以任意伪造该请求头，只有来自可信代理的请求才保留，且单独记录，不作为本服务的请求id
+fn incoming_request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .filter(|value| value.chars().all(|c| c.is_ascii_graphic()))
+        .map(str::to_string)
+}
+
+mod cli;
 mod config;
 mod handle;
 mod idgen;
 mod pojo;
 mod prepare;
 mod service;
+#[cfg(test)]
+mod test_support;
 mod types;
 mod utils;
 
 #[tokio::main]
 async fn main() {
+    YitIdHelper::set_id_generator(IdGeneratorOptions::default());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        cli::run(&args).await;
+        return;
+    }
+
     print_banner();
     init_log();
-    YitIdHelper::set_id_generator(IdGeneratorOptions::default());
-    let state = prepare::create_state().await;
-    if let Err(err) = run_server(state).await {
+    let (state, receivers) = prepare::create_state().await;
+    if let Err(err) = run_server(state, receivers).await {
         tracing::error!("Server error: {}", err);
     }
 }
 
-async fn run_server(state: Arc<IState>) -> Result<(), axum::Error> {
-    let app = api_router()
-        .layer(middleware::from_fn(print_request_response))
+async fn run_server(
+    state: Arc<IState>,
+    receivers: types::EventReceivers,
+) -> Result<(), axum::Error> {
+    let types::EventReceivers { click_rx, access_rx, live_publish_rx, webhook_click_rx } = receivers;
+    let app = api_router(state.clone())
+        .layer(middleware::from_fn_with_state(state.clone(), print_request_response))
         .layer(CorsLayer::new().allow_origin(Any).allow_methods([
             Method::GET,
             Method::POST,
@@ -72,6 +102,83 @@ async fn run_server(state: Arc<IState>) -> Result<(), axum::Error> {
     // 创建shutdown channel
     let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
 
+    // 启动访问记录写入任务
+    let access_writer_state = state.clone();
+    let access_writer_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        access_service::access_writer_task(access_writer_state, access_rx, access_writer_shutdown_rx).await;
+    });
+
+    // 启动访问记录刷新任务
+    let access_state = state.clone();
+    let access_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        access_service::access_flush_task(access_state, access_shutdown_rx).await;
+    });
+
+    // 启动点击事件批量写入任务
+    let click_state = state.clone();
+    let click_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        click_service::click_writer_task(click_state, click_rx, click_shutdown_rx).await;
+    });
+
+    // 启动点击统计汇总任务
+    let rollup_state = state.clone();
+    let rollup_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        stats_service::rollup_clicks_task(rollup_state, rollup_shutdown_rx).await;
+    });
+
+    // 启动过期点击事件清理任务
+    let retention_state = state.clone();
+    let retention_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        privacy_service::click_retention_task(retention_state, retention_shutdown_rx).await;
+    });
+
+    // 启动实时点击流发布任务
+    let live_publish_state = state.clone();
+    let live_publish_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        live_service::live_publish_task(live_publish_state, live_publish_rx, live_publish_shutdown_rx).await;
+    });
+
+    // 启动实时点击流订阅任务
+    let live_state = state.clone();
+    let live_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        live_service::live_subscribe_task(live_state, live_shutdown_rx).await;
+    });
+
+    // 启动Webhook投递任务
+    let webhook_state = state.clone();
+    let webhook_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        webhook_service::webhook_delivery_task(webhook_state, webhook_shutdown_rx).await;
+    });
+
+    // 启动点击事件Webhook写入任务
+    let webhook_click_state = state.clone();
+    let webhook_click_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        webhook_service::click_dispatch_task(webhook_click_state, webhook_click_rx, webhook_click_shutdown_rx).await;
+    });
+
+    // 启动JWKS加载任务
+    let jwks_state = state.clone();
+    let jwks_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        jwt_service::jwks_refresh_task(jwks_state, jwks_shutdown_rx).await;
+    });
+
+    // 启动目标地址规则加载任务
+    let url_rule_state = state.clone();
+    let url_rule_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        url_rule_service::url_rule_refresh_task(url_rule_state, url_rule_shutdown_rx).await;
+    });
+
     // 启动定时清理过期链接任务
     let cleanup_state = state.clone();
     tokio::spawn(async move {
@@ -83,8 +190,13 @@ async fn run_server(state: Arc<IState>) -> Result<(), axum::Error> {
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     
     // 运行服务器并等待shutdown信号
-    let serve_future = serve(listener, app)
-        .with_graceful_shutdown(prepare::shutdown_signal());
+    // 收到关闭信号时先结束SSE长连接，否则优雅关闭会一直等待
+    let stream_shutdown = state.stream_shutdown.clone();
+    let serve_future = serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            prepare::shutdown_signal().await;
+            let _ = stream_shutdown.send(());
+        });
     
     serve_future.await.unwrap();
     
@@ -98,10 +210,10 @@ async fn run_server(state: Arc<IState>) -> Result<(), axum::Error> {
     Ok(())
 }
 
-fn api_router() -> Router<Arc<IState>> {
+fn api_router(state: Arc<IState>) -> Router<Arc<IState>> {
     Router::new()
-        .merge(handle::api::router())
-        .merge(handle::admin::router())
+        .merge(handle::api::router(state.clone()))
+        .merge(handle::admin::router(state))
 }
 
 struct LocalTimer;
@@ -148,12 +260,24 @@ fn init_log() {
 }
 
 async fn print_request_response(
+    State(state): State<Arc<IState>>,
     req: Request<Body>,
     next: Next,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let start_time = std::time::Instant::now();
-    let (parts, body) = req.into_parts();
+    let (mut parts, body) = req.into_parts();
     let uid = generate_request_id();
+    let from_trusted_proxy = parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .is_some_and(|ConnectInfo(remote)| {
+            state.trusted_proxies.iter().any(|network| network.contains(remote.ip()))
+        });
+    let upstream = from_trusted_proxy.then(|| incoming_request_id(&parts.headers)).flatten();
+    if let Some(upstream) = &upstream {
+        tracing::info!("request[{}] upstream request id: {}", uid, upstream);
+    }
+    parts.extensions.insert(RequestId { id: uid.clone(), upstream });
     let method = parts.method.clone();
     let uri = parts.uri.clone();
 
@@ -176,11 +300,24 @@ async fn print_request_response(
     let res = next.run(req).await;
 
     let duration = start_time.elapsed();
-    let (response_parts, body) = res.into_parts();
+    let (mut response_parts, body) = res.into_parts();
     let status = response_parts.status;
+    if let Ok(value) = HeaderValue::from_str(&uid) {
+        response_parts.headers.insert(REQUEST_ID_HEADER, value);
+    }
 
     tracing::info!("response[{}] - {} - {:?}", uid, status, duration);
 
+    // SSE响应是持续输出的流，不能缓冲
+    let is_event_stream = response_parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if is_event_stream {
+        return Ok(Response::from_parts(response_parts, body));
+    }
+
     let bytes = if should_log_body {
         buffer_and_print(&format!("response[{}]", uid), body).await?
     } else {
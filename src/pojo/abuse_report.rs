@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+use crate::utils::helper::encode_base62;
+
+/// 滥用举报，附带被举报链接的信息
+#[derive(sqlx::FromRow, Debug)]
+pub struct AbuseReport {
+    pub id: i64,
+    pub link_id: i64,
+    pub reason: String,
+    pub detail: Option<String>,
+    pub status: String,
+    pub handled_by: Option<String>,
+    pub handle_time: Option<chrono::NaiveDateTime>,
+    pub create_time: Option<chrono::NaiveDateTime>,
+    /// 来自link_history表
+    pub domain: String,
+    pub origin_url: String,
+    pub quarantined: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AbuseReportResponse {
+    pub id: i64,
+    pub link_code: String,
+    pub domain: String,
+    pub origin_url: String,
+    pub quarantined: bool,
+    pub reason: String,
+    pub detail: Option<String>,
+    pub status: String,
+    pub handled_by: Option<String>,
+    pub handle_time: Option<i64>,
+    pub create_time: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AbuseReportListResponse {
+    pub data: Vec<AbuseReportResponse>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: i64,
+    pub last_page: bool,
+}
+
+impl AbuseReport {
+    pub fn to_response(&self) -> AbuseReportResponse {
+        AbuseReportResponse {
+            id: self.id,
+            link_code: encode_base62(self.link_id as usize),
+            domain: self.domain.clone(),
+            origin_url: self.origin_url.clone(),
+            quarantined: self.quarantined,
+            reason: self.reason.clone(),
+            detail: self.detail.clone(),
+            status: self.status.clone(),
+            handled_by: self.handled_by.clone(),
+            handle_time: self.handle_time.map(|dt| dt.and_utc().timestamp_millis()),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+/// 一次非机器人的短链访问，由重定向处理器产生，批量写入Redis
+#[derive(Debug, Clone)]
+pub struct AccessEvent {
+    pub link_id: i64,
+    /// 访问时间（Unix时间戳）
+    pub timestamp: i64,
+    /// 访客指纹的哈希值，用于估算每日独立访客数
+    pub visitor: String,
+}
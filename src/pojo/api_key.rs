@@ -0,0 +1,171 @@
+use serde::Serialize;
+
+use crate::pojo::link_history::LinkOwner;
+use crate::pojo::AppError;
+use crate::types::enums::{ApiScope, Permission, Role};
+
+/// 管理接口的API Key，只保存哈希值
+#[derive(sqlx::FromRow, Debug)]
+pub struct ApiKey {
+    pub id: i64,
+    /// 所属工作空间
+    pub workspace_id: i64,
+    /// 所属用户，为空表示工作空间级别的Key
+    pub user_id: Option<i64>,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub revoked: bool,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    pub create_time: Option<chrono::NaiveDateTime>,
+    /// 所属用户的角色，来自app_user表
+    #[sqlx(default)]
+    pub user_role: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApiKeyResponse {
+    pub id: i64,
+    pub workspace_id: i64,
+    pub user_id: Option<i64>,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub revoked: bool,
+    pub last_used_at: Option<i64>,
+    pub create_time: Option<i64>,
+}
+
+/// 新建的API Key，明文只在创建时返回一次
+#[derive(Serialize, Debug)]
+pub struct ApiKeyCreatedResponse {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
+
+impl ApiKey {
+    pub fn to_response(&self) -> ApiKeyResponse {
+        ApiKeyResponse {
+            id: self.id,
+            workspace_id: self.workspace_id,
+            user_id: self.user_id,
+            name: self.name.clone(),
+            key_prefix: self.key_prefix.clone(),
+            scopes: self.scopes.clone(),
+            revoked: self.revoked,
+            last_used_at: self.last_used_at.map(|dt| dt.and_utc().timestamp_millis()),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+impl ApiKey {
+    /// Key的角色：权限范围对应的最高角色，绑定用户时不超过用户的角色
+    pub fn role(&self) -> Option<Role> {
+        let role = self
+            .scopes
+            .iter()
+            .filter_map(|scope| ApiScope::parse(scope))
+            .map(|scope| scope.role())
+            .max()?;
+        match &self.user_role {
+            None => Some(role),
+            Some(user_role) => Role::parse(user_role).map(|user_role| role.min(user_role)),
+        }
+    }
+}
+
+/// 当前请求的调用方，由认证中间件写入请求扩展
+#[derive(Debug, Clone)]
+pub struct ApiActor {
+    /// 使用的API Key，未启用认证时为空
+    pub key_id: Option<i64>,
+    /// 操作人，记录到修改历史中
+    pub name: String,
+    pub role: Role,
+    /// 所属工作空间，只能访问该工作空间的数据
+    pub workspace_id: i64,
+    /// 所属用户
+    pub user_id: Option<i64>,
+}
+
+impl ApiActor {
+    /// 校验调用方的角色是否拥有该权限，没有时返回403
+    pub fn require(&self, permission: Permission) -> Result<(), AppError> {
+        if self.role.allows(permission) {
+            Ok(())
+        } else {
+            Err(AppError::forbidden(anyhow::anyhow!(
+                "角色 {} 没有 {} 权限",
+                self.role.as_str(),
+                permission.as_str()
+            )))
+        }
+    }
+
+    /// 作为链接创建人记录的用户和API Key
+    pub fn owner(&self) -> LinkOwner {
+        LinkOwner {
+            user_id: self.user_id,
+            key_id: self.key_id,
+        }
+    }
+
+    /// 是否可以修改该链接，editor只能修改自己创建的链接
+    ///
+    /// 绑定用户的调用方按用户判断，可以修改同一用户通过其他Key创建的链接；未绑定用户的Key只能修改自己创建的链接
+    pub fn require_editable(&self, owner: LinkOwner) -> Result<(), AppError> {
+        if self.role.allows(Permission::LinkEditAny) {
+            return Ok(());
+        }
+        self.require(Permission::LinkEdit)?;
+        let by_user = self.user_id.is_some() && owner.user_id == self.user_id;
+        let by_key = self.user_id.is_none() && self.key_id.is_some() && owner.key_id == self.key_id;
+        if by_user || by_key {
+            Ok(())
+        } else {
+            Err(AppError::forbidden(anyhow::anyhow!("只能修改自己创建的链接")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+
+    fn actor(role: Role, user_id: Option<i64>, key_id: Option<i64>) -> ApiActor {
+        ApiActor {
+            key_id,
+            name: "test".to_string(),
+            role,
+            workspace_id: 0,
+            user_id,
+        }
+    }
+
+    #[test]
+    fn editable_by_owner_only() {
+        let owner = LinkOwner { user_id: Some(1), key_id: Some(10) };
+        let unowned = LinkOwner::default();
+
+        // admin可以修改任何链接，viewer不能修改
+        assert!(actor(Role::Admin, None, None).require_editable(unowned).is_ok());
+        let err = actor(Role::Viewer, Some(1), Some(10)).require_editable(owner).unwrap_err();
+        assert_eq!(err.status(), StatusCode::FORBIDDEN);
+
+        // 同一用户通过其他Key也可以修改
+        assert!(actor(Role::Editor, Some(1), Some(11)).require_editable(owner).is_ok());
+        assert!(actor(Role::Editor, Some(2), Some(10)).require_editable(owner).is_err());
+        assert!(actor(Role::Editor, Some(1), None).require_editable(unowned).is_err());
+
+        // 未绑定用户的Key只能修改自己创建的链接，Key的id不会被当作用户id匹配
+        let by_key = LinkOwner { user_id: None, key_id: Some(20) };
+        assert!(actor(Role::Editor, None, Some(20)).require_editable(by_key).is_ok());
+        assert!(actor(Role::Editor, None, Some(21)).require_editable(by_key).is_err());
+        assert!(actor(Role::Editor, Some(20), None).require_editable(by_key).is_err());
+        assert!(actor(Role::Editor, None, Some(1)).require_editable(owner).is_err());
+    }
+}
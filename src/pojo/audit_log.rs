@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+/// 审计日志，只允许追加
+#[derive(sqlx::FromRow, Debug)]
+pub struct AuditLog {
+    pub id: i64,
+    pub workspace_id: i64,
+    pub actor: String,
+    pub actor_key_id: Option<i64>,
+    pub actor_user_id: Option<i64>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    /// 可信代理传入的上游请求id
+    pub upstream_request_id: Option<String>,
+    pub create_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuditLogResponse {
+    pub id: i64,
+    pub workspace_id: i64,
+    pub actor: String,
+    pub actor_key_id: Option<i64>,
+    pub actor_user_id: Option<i64>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    pub upstream_request_id: Option<String>,
+    pub create_time: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuditLogListResponse {
+    pub data: Vec<AuditLogResponse>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: i64,
+    pub last_page: bool,
+}
+
+impl AuditLog {
+    pub fn to_response(&self) -> AuditLogResponse {
+        AuditLogResponse {
+            id: self.id,
+            workspace_id: self.workspace_id,
+            actor: self.actor.clone(),
+            actor_key_id: self.actor_key_id,
+            actor_user_id: self.actor_user_id,
+            action: self.action.clone(),
+            target_type: self.target_type.clone(),
+            target_id: self.target_id.clone(),
+            before: self.before.clone(),
+            after: self.after.clone(),
+            request_id: self.request_id.clone(),
+            upstream_request_id: self.upstream_request_id.clone(),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
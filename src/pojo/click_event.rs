@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::helper::{encode_base62, referrer_domain};
+use crate::utils::user_agent::parse_user_agent;
+
+/// 一次短链点击事件，由重定向处理器产生，批量写入click_event表
+#[derive(Serialize, Debug, Clone)]
+pub struct ClickEvent {
+    pub link_id: i64,
+    pub click_time: chrono::NaiveDateTime,
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    /// 匿名化后的IP
+    pub ip: Option<String>,
+    pub accept_language: Option<String>,
+    /// 是否为机器人或爬虫
+    pub is_bot: bool,
+}
+
+/// 实时点击流推送的事件，只包含解析后的维度，不包含IP等访客信息
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiveClick {
+    /// 短链所属工作空间
+    pub workspace_id: i64,
+    pub link_code: String,
+    /// 点击时间（毫秒时间戳）
+    pub click_time: i64,
+    pub referrer_domain: Option<String>,
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub device: Option<String>,
+    pub is_bot: bool,
+}
+
+impl LiveClick {
+    pub fn new(event: &ClickEvent, workspace_id: i64) -> Self {
+        let ua_info = event.user_agent.as_deref().map(parse_user_agent);
+        LiveClick {
+            workspace_id,
+            link_code: encode_base62(event.link_id as usize),
+            click_time: event.click_time.and_utc().timestamp_millis(),
+            referrer_domain: event.referrer.as_deref().and_then(referrer_domain),
+            browser: ua_info.as_ref().map(|info| info.browser.to_string()),
+            os: ua_info.as_ref().map(|info| info.os.to_string()),
+            device: ua_info.as_ref().map(|info| info.device.to_string()),
+            is_bot: event.is_bot,
+        }
+    }
+}
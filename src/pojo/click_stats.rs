@@ -0,0 +1,95 @@
+use serde::Serialize;
+
+/// 时间序列中的一个统计点
+#[derive(Serialize, Debug)]
+pub struct ClickPoint {
+    /// 时间桶起始时间（毫秒时间戳）
+    pub time: i64,
+    pub clicks: i64,
+    /// 独立访客估算值（HyperLogLog），仅按天统计时有值
+    pub unique_visitors: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClickSeriesResponse {
+    pub link_code: String,
+    pub granularity: String,
+    pub from: i64,
+    pub to: i64,
+    pub total: i64,
+    pub points: Vec<ClickPoint>,
+}
+
+/// 来源分析中的一项
+#[derive(Serialize, Debug)]
+pub struct BreakdownItem {
+    pub name: String,
+    pub clicks: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClickBreakdownResponse {
+    pub link_code: String,
+    pub from: i64,
+    pub to: i64,
+    /// 来源域名，没有Referer的记为 (direct)
+    pub referrers: Vec<BreakdownItem>,
+    /// 浏览器
+    pub browsers: Vec<BreakdownItem>,
+    /// 操作系统
+    pub os: Vec<BreakdownItem>,
+    /// 设备类型：Desktop、Mobile、Tablet、Other
+    pub devices: Vec<BreakdownItem>,
+}
+
+/// 按天统计的一个数据点
+#[derive(Serialize, Debug)]
+pub struct DailyCount {
+    /// 当天起始时间（毫秒时间戳）
+    pub time: i64,
+    pub count: i64,
+}
+
+/// 点击排行中的一条短链
+#[derive(Serialize, Debug)]
+pub struct TopLink {
+    pub link_code: String,
+    pub domain: String,
+    pub origin_url: String,
+    pub active: bool,
+    pub clicks: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StatsOverviewResponse {
+    pub from: i64,
+    pub to: i64,
+    /// 链接总数
+    pub total_links: i64,
+    /// 有效且未过期的链接数
+    pub active_links: i64,
+    /// 已失效的链接数
+    pub inactive_links: i64,
+    /// 已过期但尚未被清理任务标记失效的链接数
+    pub expired_links: i64,
+    /// 时间范围内的点击总数
+    pub total_clicks: i64,
+    /// 每天新建的链接数
+    pub links_created: Vec<DailyCount>,
+    /// 每天的点击数
+    pub clicks: Vec<DailyCount>,
+    /// 时间范围内点击数最多的短链
+    pub top_links: Vec<TopLink>,
+}
+
+/// 清除短链统计数据的结果
+#[derive(Serialize, Debug)]
+pub struct AnalyticsEraseResponse {
+    pub link_code: String,
+    /// 删除的点击事件数
+    pub click_events: u64,
+    /// 删除的按小时统计记录数
+    pub hourly_buckets: u64,
+    /// 删除的按天统计记录数
+    pub daily_buckets: u64,
+}
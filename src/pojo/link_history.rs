@@ -3,28 +3,64 @@ use serde::{Deserialize, Serialize};
 use crate::types::enums::LinkType;
 use crate::utils::helper::encode_base62;
 
-#[derive(sqlx::FromRow, Deserialize, Debug)]
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug)]
 pub struct LinkHistory {
     pub id: i64,
+    /// 所属工作空间
+    pub workspace_id: i64,
+    /// 创建人的用户id
+    pub owner_id: Option<i64>,
+    /// 创建时使用的API Key
+    pub owner_key_id: Option<i64>,
+    pub domain: String,
     pub origin_url: String,
     pub link_type: Option<i32>,
     pub expire_date: Option<chrono::NaiveDateTime>,
     pub active: bool,
     pub link_hash: String,
+    pub idle_ttl: Option<i64>,
+    pub idle_deadline: Option<chrono::NaiveDateTime>,
+    /// 是否被隔离，隔离后跳转显示风险提示页
+    pub quarantined: bool,
+    pub quarantine_time: Option<chrono::NaiveDateTime>,
+    /// 是否只能通过签名链接访问，未携带签名的跳转请求返回403
+    pub require_signature: bool,
+    /// 失效原因，见 `DeactivateReason`
+    pub deactivated_reason: Option<String>,
+    pub deactivated_at: Option<chrono::NaiveDateTime>,
     pub create_time: Option<chrono::NaiveDateTime>,
     pub update_time: Option<chrono::NaiveDateTime>,
+    /// 点击次数，来自link_stats表
+    #[sqlx(default)]
+    pub click_count: i64,
+    /// 最近点击时间，来自link_stats表
+    #[sqlx(default)]
+    pub last_clicked_at: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Serialize, Debug)]
 pub struct LinkHistoryResponse {
     pub id: i64,
+    pub workspace_id: i64,
+    pub owner_id: Option<i64>,
+    pub owner_key_id: Option<i64>,
+    pub domain: String,
     pub origin_url: String,
     pub link_type: Option<i32>,
     pub expire_date: Option<i64>,
     pub active: bool,
     pub link_hash: String,
+    pub idle_ttl: Option<i64>,
+    pub idle_deadline: Option<i64>,
+    pub quarantined: bool,
+    pub quarantine_time: Option<i64>,
+    pub require_signature: bool,
+    pub deactivated_reason: Option<String>,
+    pub deactivated_at: Option<i64>,
     pub create_time: Option<i64>,
     pub update_time: Option<i64>,
+    pub click_count: i64,
+    pub last_clicked_at: Option<i64>,
     pub link_code: String,
 }
 
@@ -37,30 +73,90 @@ pub struct LinkListResponse {
     pub last_page: bool,
 }
 
+/// 链接创建人，用户和API Key分别记录
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkOwner {
+    pub user_id: Option<i64>,
+    pub key_id: Option<i64>,
+}
+
+/// 签名链接，过期后签名失效，链接本身不受影响
+#[derive(Serialize, Debug)]
+pub struct SignedLinkResponse {
+    pub link_code: String,
+    pub domain: String,
+    /// 过期时间（Unix时间戳，秒）
+    pub exp: i64,
+    pub sig: String,
+    /// 带签名的访问路径：/s/{code}?exp=...&sig=...
+    pub path: String,
+}
+
 impl LinkHistory {
-    pub fn from_url(id: i64, origin_url: &str, link_hash: String) -> Self {
+    pub fn from_url(
+        id: i64,
+        workspace_id: i64,
+        owner: LinkOwner,
+        domain: &str,
+        origin_url: &str,
+        link_hash: String,
+        idle_ttl: Option<i64>,
+    ) -> Self {
         Self {
             id,
+            workspace_id,
+            owner_id: owner.user_id,
+            owner_key_id: owner.key_id,
+            domain: domain.to_string(),
             origin_url: origin_url.to_string(),
             link_type: Some(LinkType::INTERIM.to_value()),
             expire_date: None,
             active: true,
             link_hash,
+            idle_ttl,
+            idle_deadline: None,
+            quarantined: false,
+            quarantine_time: None,
+            require_signature: false,
+            deactivated_reason: None,
+            deactivated_at: None,
             create_time: None,
             update_time: None,
+            click_count: 0,
+            last_clicked_at: None,
+        }
+    }
+
+    pub fn owner(&self) -> LinkOwner {
+        LinkOwner {
+            user_id: self.owner_id,
+            key_id: self.owner_key_id,
         }
     }
 
     pub fn to_response(&self) -> LinkHistoryResponse {
         LinkHistoryResponse {
             id: self.id,
+            workspace_id: self.workspace_id,
+            owner_id: self.owner_id,
+            owner_key_id: self.owner_key_id,
+            domain: self.domain.clone(),
             origin_url: self.origin_url.clone(),
             link_type: self.link_type,
             expire_date: self.expire_date.map(|dt| dt.and_utc().timestamp_millis()),
             active: self.active,
             link_hash: self.link_hash.clone(),
+            idle_ttl: self.idle_ttl,
+            idle_deadline: self.idle_deadline.map(|dt| dt.and_utc().timestamp_millis()),
+            quarantined: self.quarantined,
+            quarantine_time: self.quarantine_time.map(|dt| dt.and_utc().timestamp_millis()),
+            require_signature: self.require_signature,
+            deactivated_reason: self.deactivated_reason.clone(),
+            deactivated_at: self.deactivated_at.map(|dt| dt.and_utc().timestamp_millis()),
             create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
             update_time: self.update_time.map(|dt| dt.and_utc().timestamp_millis()),
+            click_count: self.click_count,
+            last_clicked_at: self.last_clicked_at.map(|dt| dt.and_utc().timestamp_millis()),
             link_code: encode_base62(self.id as usize),
         }
     }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// 链接目标地址的修改记录，保存修改前的原始地址
+#[derive(sqlx::FromRow, Deserialize, Debug)]
+pub struct LinkRevision {
+    pub id: i64,
+    pub link_id: i64,
+    pub origin_url: String,
+    pub actor: String,
+    pub create_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LinkRevisionResponse {
+    pub id: i64,
+    pub link_id: i64,
+    pub origin_url: String,
+    pub actor: String,
+    pub create_time: Option<i64>,
+}
+
+impl LinkRevision {
+    pub fn to_response(&self) -> LinkRevisionResponse {
+        LinkRevisionResponse {
+            id: self.id,
+            link_id: self.link_id,
+            origin_url: self.origin_url.clone(),
+            actor: self.actor.clone(),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
@@ -3,7 +3,17 @@ use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 
+pub mod abuse_report;
+pub mod access_event;
+pub mod api_key;
+pub mod audit_log;
+pub mod click_event;
+pub mod click_stats;
 pub mod link_history;
+pub mod link_revision;
+pub mod url_rule;
+pub mod webhook;
+pub mod workspace;
 
 #[allow(dead_code)]
 #[derive(Deserialize, Debug)]
@@ -64,21 +74,56 @@ where
     }
 }
 
-pub struct AppError(anyhow::Error);
+/// 接口错误，默认为500，参数错误、未认证、无权限和资源不存在使用对应的状态码
+#[derive(Debug)]
+pub struct AppError {
+    status: StatusCode,
+    error: anyhow::Error,
+}
+
+impl AppError {
+    pub fn new(status: StatusCode, err: impl Into<anyhow::Error>) -> Self {
+        Self {
+            status,
+            error: err.into(),
+        }
+    }
+
+    /// 400 请求参数错误
+    pub fn bad_request(err: impl Into<anyhow::Error>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, err)
+    }
+
+    /// 401 未认证
+    pub fn unauthorized(err: impl Into<anyhow::Error>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, err)
+    }
+
+    /// 403 无权限
+    pub fn forbidden(err: impl Into<anyhow::Error>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, err)
+    }
+
+    /// 404 资源不存在
+    pub fn not_found(err: impl Into<anyhow::Error>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, err)
+    }
+
+    #[allow(dead_code)]
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.error)
     }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Message::<String>::failed(&self.0.to_string()),
-        )
-            .into_response()
+        (self.status, Message::<String>::failed(&self.error.to_string())).into_response()
     }
 }
 
@@ -87,6 +132,6 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self(err.into())
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, err)
     }
 }
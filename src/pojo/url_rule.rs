@@ -0,0 +1,150 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::types::enums::UrlRuleKind;
+
+/// 目标地址规则，通过管理接口维护
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct UrlRule {
+    pub id: i64,
+    pub kind: String,
+    pub value: String,
+    pub note: Option<String>,
+    pub created_by: Option<String>,
+    pub create_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UrlRuleResponse {
+    pub id: i64,
+    pub kind: String,
+    pub value: String,
+    pub note: Option<String>,
+    pub created_by: Option<String>,
+    pub create_time: Option<i64>,
+}
+
+impl UrlRule {
+    pub fn to_response(&self) -> UrlRuleResponse {
+        UrlRuleResponse {
+            id: self.id,
+            kind: self.kind.clone(),
+            value: self.value.clone(),
+            note: self.note.clone(),
+            created_by: self.created_by.clone(),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+/// 规则文件的格式（YAML）
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct UrlRuleFile {
+    #[serde(default)]
+    pub block_domains: Vec<String>,
+    #[serde(default)]
+    pub block_patterns: Vec<String>,
+    #[serde(default)]
+    pub allow_domains: Vec<String>,
+}
+
+/// 编译后的规则集合，由加载任务定时刷新，创建和跳转时同步读取
+#[derive(Debug, Default)]
+pub struct UrlRules {
+    block_domains: Vec<String>,
+    block_patterns: Vec<Regex>,
+    allow_domains: Vec<String>,
+}
+
+impl UrlRules {
+    /// 添加一条规则，域名或正则无效时返回错误信息
+    pub fn add(&mut self, kind: UrlRuleKind, value: &str) -> Result<(), String> {
+        match kind {
+            UrlRuleKind::BlockPattern => {
+                let pattern = Regex::new(value).map_err(|e| format!("无效的正则表达式: {}", e))?;
+                self.block_patterns.push(pattern);
+            }
+            UrlRuleKind::BlockDomain | UrlRuleKind::AllowDomain => {
+                let domain = normalize_domain(value).ok_or_else(|| format!("无效的域名: {}", value))?;
+                match kind {
+                    UrlRuleKind::BlockDomain => self.block_domains.push(domain),
+                    _ => self.allow_domains.push(domain),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.block_domains.len() + self.block_patterns.len() + self.allow_domains.len()
+    }
+
+    /// 校验目标地址，屏蔽规则优先于允许规则，不允许时返回原因
+    ///
+    /// * `allowlist_only`: 仅允许模式，目标地址必须属于允许的域名
+    pub fn check(&self, url: &str, allowlist_only: bool) -> Result<(), String> {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.trim_end_matches('.').to_lowercase()));
+        if let Some(host) = &host
+            && let Some(domain) = self.block_domains.iter().find(|domain| matches_domain(host, domain))
+        {
+            return Err(format!("目标域名已被屏蔽: {}", domain));
+        }
+        if self.block_patterns.iter().any(|pattern| pattern.is_match(url)) {
+            return Err("目标地址已被屏蔽".to_string());
+        }
+        if allowlist_only {
+            let allowed = host
+                .as_deref()
+                .is_some_and(|host| self.allow_domains.iter().any(|domain| matches_domain(host, domain)));
+            if !allowed {
+                return Err("目标域名不在允许列表中".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 规范化域名规则：小写，去掉 `*.` 前缀和首尾的 `.`
+pub fn normalize_domain(value: &str) -> Option<String> {
+    let domain = value.trim().to_lowercase();
+    let domain = domain.strip_prefix("*.").unwrap_or(&domain).trim_matches('.');
+    let valid = !domain.is_empty()
+        && domain.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    valid.then(|| domain.to_string())
+}
+
+/// 域名本身及其子域名都匹配
+fn matches_domain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_rules_check() {
+        let mut rules = UrlRules::default();
+        rules.add(UrlRuleKind::BlockDomain, "*.Phish.example").unwrap();
+        rules.add(UrlRuleKind::BlockPattern, r"(?i)\.exe$").unwrap();
+        rules.add(UrlRuleKind::AllowDomain, "example.com").unwrap();
+        assert!(rules.add(UrlRuleKind::BlockPattern, "(").is_err());
+        assert!(rules.add(UrlRuleKind::BlockDomain, "a/b").is_err());
+        assert_eq!(rules.len(), 3);
+
+        assert!(rules.check("https://phish.example/login", false).is_err());
+        assert!(rules.check("https://login.PHISH.example./", false).is_err());
+        assert!(rules.check("https://notphish.example/", false).is_ok());
+        assert!(rules.check("https://cdn.example.org/setup.EXE", false).is_err());
+        assert!(rules.check("https://example.org/", false).is_ok());
+
+        assert!(rules.check("https://example.org/", true).is_err());
+        assert!(rules.check("https://www.example.com/a", true).is_ok());
+        assert!(rules.check("not a url", true).is_err());
+    }
+}
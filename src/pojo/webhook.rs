@@ -0,0 +1,134 @@
+use serde::Serialize;
+
+use crate::pojo::link_history::LinkHistoryResponse;
+use crate::types::enums::WebhookEvent;
+
+/// Webhook订阅
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct WebhookSubscription {
+    pub id: i64,
+    /// 所属工作空间
+    pub workspace_id: i64,
+    pub url: String,
+    pub secret: String,
+    /// 订阅的事件类型，为空表示订阅除link.clicked外的全部事件
+    pub events: Vec<String>,
+    pub active: bool,
+    pub create_time: Option<chrono::NaiveDateTime>,
+    pub update_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WebhookSubscriptionResponse {
+    pub id: i64,
+    pub url: String,
+    /// 签名密钥，只在创建时返回
+    pub secret: Option<String>,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub create_time: Option<i64>,
+    pub update_time: Option<i64>,
+}
+
+impl WebhookSubscription {
+    /// 订阅是否接收该事件，点击事件量大，需显式订阅
+    pub fn accepts(&self, event_type: &str) -> bool {
+        if !self.active {
+            return false;
+        }
+        if self.events.is_empty() {
+            return event_type != WebhookEvent::LinkClicked.as_str();
+        }
+        self.events.iter().any(|e| e == event_type)
+    }
+
+    /// 订阅是否接收该工作空间内的事件
+    pub fn accepts_in(&self, workspace_id: i64, event_type: &str) -> bool {
+        self.workspace_id == workspace_id && self.accepts(event_type)
+    }
+
+    pub fn to_response(&self, with_secret: bool) -> WebhookSubscriptionResponse {
+        WebhookSubscriptionResponse {
+            id: self.id,
+            url: self.url.clone(),
+            secret: with_secret.then(|| self.secret.clone()),
+            events: self.events.clone(),
+            active: self.active,
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+            update_time: self.update_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+/// 待投递的Webhook，附带订阅的地址和密钥
+#[derive(sqlx::FromRow, Debug)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
+
+/// 超过最大投递次数的Webhook
+#[derive(sqlx::FromRow, Debug)]
+pub struct WebhookDeadLetter {
+    pub id: i64,
+    pub subscription_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub create_time: Option<chrono::NaiveDateTime>,
+    pub failed_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WebhookDeadLetterResponse {
+    pub id: i64,
+    pub subscription_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub create_time: Option<i64>,
+    pub failed_time: Option<i64>,
+}
+
+impl WebhookDeadLetter {
+    pub fn to_response(&self) -> WebhookDeadLetterResponse {
+        WebhookDeadLetterResponse {
+            id: self.id,
+            subscription_id: self.subscription_id,
+            event_id: self.event_id,
+            event_type: self.event_type.clone(),
+            payload: self.payload.clone(),
+            attempts: self.attempts,
+            last_error: self.last_error.clone(),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+            failed_time: self.failed_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct WebhookDeadLetterListResponse {
+    pub data: Vec<WebhookDeadLetterResponse>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: i64,
+    pub last_page: bool,
+}
+
+/// link.updated 事件的数据
+#[derive(Serialize, Debug)]
+pub struct LinkUpdatedPayload {
+    pub link: LinkHistoryResponse,
+    /// 修改前的目标地址，只有修改目标地址时有值
+    pub previous_url: Option<String>,
+    pub actor: Option<String>,
+}
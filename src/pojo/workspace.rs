@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+/// 工作空间，链接、API Key和Webhook订阅都归属于一个工作空间
+#[derive(sqlx::FromRow, Debug)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    pub create_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WorkspaceResponse {
+    pub id: i64,
+    pub name: String,
+    pub create_time: Option<i64>,
+}
+
+impl Workspace {
+    pub fn to_response(&self) -> WorkspaceResponse {
+        WorkspaceResponse {
+            id: self.id,
+            name: self.name.clone(),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+/// 工作空间内的用户
+#[derive(sqlx::FromRow, Debug)]
+pub struct AppUser {
+    pub id: i64,
+    pub workspace_id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    /// 角色：viewer、editor、admin
+    pub role: String,
+    pub create_time: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AppUserResponse {
+    pub id: i64,
+    pub workspace_id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub role: String,
+    pub create_time: Option<i64>,
+}
+
+impl AppUser {
+    pub fn to_response(&self) -> AppUserResponse {
+        AppUserResponse {
+            id: self.id,
+            workspace_id: self.workspace_id,
+            name: self.name.clone(),
+            email: self.email.clone(),
+            role: self.role.clone(),
+            create_time: self.create_time.map(|dt| dt.and_utc().timestamp_millis()),
+        }
+    }
+}
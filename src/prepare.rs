@@ -1,5 +1,6 @@
 use std::env;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 use std::time::Duration;
 
 use axum::{http::StatusCode, response::IntoResponse};
@@ -7,25 +8,96 @@ use bb8_redis::RedisConnectionManager;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use tokio::signal;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, RwLock};
 
 use crate::config::{Config, Datasource, Driver, Redis};
-use crate::types::{IState, CleanupStats};
+use crate::pojo::url_rule::{UrlRuleFile, UrlRules};
+use crate::service::{link_base_service, url_rule_service};
+use crate::types::{IState, CleanupStats, ClickEventStats, EventReceivers};
 
-pub async fn create_state() -> Arc<IState> {
+/// 创建全局状态，同时返回点击事件和访问记录队列的接收端，由后台写入任务消费
+pub async fn create_state() -> (Arc<IState>, EventReceivers) {
     let cfg = load_config("application.local.yaml", "application.yaml").unwrap_or_default();
     let redis_db = cfg.redis.database;
     let cleanup_config = cfg.cleanup.unwrap_or_default();
+    let link_config = cfg.link.unwrap_or_default();
+    let access_config = cfg.access.unwrap_or_default();
+    let webhook_config = cfg.webhook.unwrap_or_default();
+    let auth_config = cfg.auth.unwrap_or_default();
+    let rate_limit_config = cfg.rate_limit.unwrap_or_default();
+    let blocklist_config = cfg.blocklist.unwrap_or_default();
+    let abuse_config = cfg.abuse.unwrap_or_default();
+    let trusted_proxies = cfg.server.unwrap_or_default().trusted_proxies();
+    let mut signed_link_config = cfg.signed_link.unwrap_or_default();
+    if let Ok(secret) = env::var("SIGNED_LINK_SECRET") {
+        signed_link_config.secret = Some(secret);
+    }
+    let mut analytics_config = cfg.analytics.unwrap_or_default();
+    analytics_config.normalize_bot_patterns();
+    let (click_tx, click_rx) = mpsc::channel(analytics_config.channel_capacity.unwrap_or(10000));
+    let (access_tx, access_rx) = mpsc::channel(access_config.channel_capacity.unwrap_or(10000).max(1));
+    let (live_publish_tx, live_publish_rx) =
+        mpsc::channel(analytics_config.channel_capacity.unwrap_or(10000).max(1));
+    let (webhook_click_tx, webhook_click_rx) =
+        mpsc::channel(webhook_config.click_channel_capacity.unwrap_or(10000).max(1));
     let db_pool = create_db_pool(cfg.datasource).await;
-    let redis_pool = create_redis_pool(cfg.redis).await;
+    // 跳转按请求的域名查询链接，没有域名的存量链接需要归到默认域名下才能继续访问
+    match link_base_service::assign_default_domain(&db_pool, &link_config.default_domain()).await {
+        Ok((0, 0)) => {}
+        Ok((updated, remaining)) => {
+            tracing::info!("已将 {} 条存量链接的域名设置为默认域名", updated);
+            if remaining > 0 {
+                tracing::warn!("{} 条存量链接与默认域名下的短链重复，未设置域名，无法访问", remaining);
+            }
+        }
+        Err(e) => tracing::error!("设置存量链接的默认域名失败: {}", e),
+    }
+    let (redis_pool, redis_client) = create_redis_pool(cfg.redis).await;
+    let (live_tx, _) = broadcast::channel(analytics_config.live_buffer.unwrap_or(1024).max(1));
 
-    Arc::new(IState {
+    let state = Arc::new(IState {
         db_pool,
         redis_pool,
+        redis_client,
         redis_db,
         cleanup_config,
+        link_config,
+        access_config,
+        analytics_config,
+        webhook_config,
+        auth_config,
+        rate_limit_config,
+        blocklist_config,
+        abuse_config,
+        signed_link_config,
+        trusted_proxies,
+        click_tx,
+        click_stats: Arc::new(ClickEventStats::default()),
+        access_tx,
+        live_publish_tx,
+        live_tx,
+        stream_shutdown: broadcast::channel(1).0,
         cleanup_stats: Arc::new(RwLock::new(CleanupStats::default())),
-    })
+        webhook_click_tx,
+        webhook_click_dropped: Arc::new(AtomicU64::new(0)),
+        webhook_subscriptions: Arc::new(std::sync::RwLock::new(Vec::new())),
+        ip_salt: Arc::new(std::sync::RwLock::new(None)),
+        jwks: Arc::new(std::sync::RwLock::new(None)),
+        url_rules: Arc::new(std::sync::RwLock::new(UrlRules::default())),
+        url_rule_file: Arc::new(std::sync::RwLock::new(UrlRuleFile::default())),
+    });
+    // 启动时同步加载目标地址规则，避免加载任务首次刷新前的请求绕过规则
+    match url_rule_service::refresh_rules(&state).await {
+        Ok(count) => tracing::info!("已加载 {} 条目标地址规则", count),
+        Err(e) => tracing::error!("加载目标地址规则失败: {}", e),
+    }
+    (state, EventReceivers { click_rx, access_rx, live_publish_rx, webhook_click_rx })
+}
+
+/// 命令行工具只需要数据库连接
+pub async fn create_cli_db_pool() -> PgPool {
+    let cfg = load_config("application.local.yaml", "application.yaml").unwrap_or_default();
+    create_db_pool(cfg.datasource).await
 }
 
 pub async fn handler_404() -> impl IntoResponse {
@@ -93,10 +165,14 @@ async fn create_db_pool(datasource: Datasource) -> PgPool {
     db_pool
 }
 
-async fn create_redis_pool(redis_cfg: Redis) -> bb8::Pool<RedisConnectionManager> {
+async fn create_redis_pool(
+    redis_cfg: Redis,
+) -> (bb8::Pool<RedisConnectionManager>, bb8_redis::redis::Client) {
     let max_size = redis_cfg.max_size.unwrap_or(10);
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| redis_cfg.to_link());
     tracing::info!("redis_url: {}", redis_url);
+    let redis_client = bb8_redis::redis::Client::open(redis_url.as_str())
+        .expect("Redis客户端创建失败，请检查Redis URL格式");
     let redis_manager = RedisConnectionManager::new(redis_url)
         .expect("Redis连接管理器创建失败，请检查Redis URL格式");
     let redis_pool = bb8::Pool::builder()
@@ -106,7 +182,7 @@ async fn create_redis_pool(redis_cfg: Redis) -> bb8::Pool<RedisConnectionManager
         .await
         .expect("Redis连接池创建失败，请检查Redis服务状态和网络连接");
 
-    redis_pool
+    (redis_pool, redis_client)
 }
 
 fn load_config(path: &str, default_path: &str) -> Option<Config> {
@@ -0,0 +1,359 @@
+use std::net::IpAddr;
+
+use axum::http::StatusCode;
+use bb8_redis::redis::{cmd, AsyncCommands};
+
+use crate::idgen::YitIdHelper;
+use crate::link_base_service::lock_by_id;
+use crate::link_service::{evict_cache, query_active_link, query_link_by_code};
+use crate::pojo::abuse_report::{AbuseReport, AbuseReportListResponse};
+use crate::pojo::api_key::ApiActor;
+use crate::pojo::link_history::LinkHistory;
+use crate::pojo::{AppError, Pagination};
+use crate::service::audit_service::{self, Auditor};
+use crate::types::enums::{AbuseReason, AuditAction, DeactivateReason, ReportStatus};
+use crate::types::{HandlerResult, IState, RequestId};
+use crate::utils::helper::calculate_sha256;
+
+/// 被隔离的链接id集合，跳转时与URL缓存一起读取
+pub const QUARANTINED_LINKS_KEY: &str = "link:quarantined";
+
+/// 记录一条举报，待处理的举报达到阈值时自动隔离链接
+///
+/// 举报人以IP的哈希值区分，同一IP对同一链接只有一条待处理的举报，重复举报不报错；
+/// 同一IP待处理的举报超过上限时返回429，单个举报人不能批量隔离链接；
+/// 自动隔离以system的名义记录审计日志，关联触发隔离的举报请求
+pub async fn report(
+    state: &IState,
+    host: Option<String>,
+    link_code: &str,
+    ip: IpAddr,
+    reason: AbuseReason,
+    detail: Option<String>,
+    request_id: &RequestId,
+) -> HandlerResult<()> {
+    let link = query_active_link(state, host, link_code).await?;
+    let reporter_hash = calculate_sha256(&format!("report|{}", ip));
+    let max_open_reports = state.abuse_config.max_open_reports_per_reporter.unwrap_or(10);
+    if max_open_reports > 0 {
+        let reporter_open: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM abuse_report WHERE reporter_hash = $1 AND status = 'open' AND link_id <> $2",
+        )
+        .bind(&reporter_hash)
+        .bind(link.id)
+        .fetch_one(&state.db_pool)
+        .await?;
+        if reporter_open as u64 >= max_open_reports {
+            return Err(AppError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                anyhow::anyhow!("待处理的举报过多，请稍后再试"),
+            ));
+        }
+    }
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO abuse_report (id, link_id, workspace_id, reason, detail, reporter_hash)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (link_id, reporter_hash) WHERE status = 'open' DO NOTHING
+        "#,
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(link.id)
+    .bind(link.workspace_id)
+    .bind(reason.as_str())
+    .bind(&detail)
+    .bind(&reporter_hash)
+    .execute(&state.db_pool)
+    .await?
+    .rows_affected()
+        > 0;
+
+    let threshold = state.abuse_config.report_threshold.unwrap_or(3);
+    if !inserted || threshold == 0 || link.quarantined {
+        return Ok(());
+    }
+    let open_reports: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM abuse_report WHERE link_id = $1 AND status = 'open'",
+    )
+    .bind(link.id)
+    .fetch_one(&state.db_pool)
+    .await?;
+    if (open_reports as u64) < threshold {
+        return Ok(());
+    }
+
+    // 隔离与审计日志在同一事务中写入，操作前的值取事务中锁定的行
+    let mut tx = state.db_pool.begin().await?;
+    let before = lock_by_id(&mut tx, link.id).await?;
+    let quarantined = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        UPDATE link_history SET quarantined = true, quarantine_time = NOW(), update_time = NOW()
+        WHERE id = $1 AND quarantined = false
+        RETURNING *
+        "#,
+    )
+    .bind(link.id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(quarantined) = quarantined else {
+        tx.rollback().await?;
+        return Ok(());
+    };
+    let auditor = Auditor {
+        request_id: Some(request_id.id.clone()),
+        upstream_request_id: request_id.upstream.clone(),
+        ..Auditor::system()
+    };
+    audit_service::record_link_change(
+        &mut tx,
+        &auditor,
+        AuditAction::LinkQuarantine,
+        before.as_ref(),
+        Some(&quarantined),
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::warn!("短链 {} 收到 {} 条举报，已自动隔离", link_code, open_reports);
+    mark_quarantined(state, link.id, true).await
+}
+
+/// 分页查询工作空间内的举报，按举报时间倒序，可按状态过滤
+pub async fn list_reports(
+    state: &IState,
+    workspace_id: i64,
+    status: Option<ReportStatus>,
+    pagination: Pagination,
+) -> HandlerResult<AbuseReportListResponse> {
+    let db_pool = &state.db_pool;
+    let page = pagination.page.max(1);
+    let page_size = pagination.page_size.clamp(1, 100);
+    let status = status.map(|status| status.as_str());
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM abuse_report r JOIN link_history h ON h.id = r.link_id
+        WHERE r.workspace_id = $1 AND ($2::varchar IS NULL OR r.status = $2)
+        "#,
+    )
+    .bind(workspace_id)
+    .bind(status)
+    .fetch_one(db_pool)
+    .await?;
+    let reports = sqlx::query_as::<_, AbuseReport>(
+        r#"
+        SELECT r.id, r.link_id, r.reason, r.detail, r.status, r.handled_by, r.handle_time,
+               r.create_time, h.domain, h.origin_url, h.quarantined
+        FROM abuse_report r JOIN link_history h ON h.id = r.link_id
+        WHERE r.workspace_id = $1 AND ($2::varchar IS NULL OR r.status = $2)
+        ORDER BY r.create_time DESC LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(workspace_id)
+    .bind(status)
+    .bind(page_size as i64)
+    .bind(((page - 1) * page_size) as i64)
+    .fetch_all(db_pool)
+    .await?;
+
+    let total_pages = ((total as f64) / (page_size as f64)).ceil() as usize;
+    Ok(AbuseReportListResponse {
+        data: reports.iter().map(|report| report.to_response()).collect(),
+        page,
+        page_size,
+        total,
+        last_page: page >= total_pages,
+    })
+}
+
+/// 确认举报并下架链接：链接失效且保持隔离状态，待处理的举报标记为已确认
+pub async fn confirm_takedown(
+    state: &IState,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    link_code: &str,
+) -> HandlerResult<()> {
+    let link = query_link_by_code(state, actor.workspace_id, link_code).await?;
+    let mut tx = state.db_pool.begin().await?;
+    let before = lock_by_id(&mut tx, link.id).await?;
+    let after = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        UPDATE link_history
+        SET active = false, quarantined = true, quarantine_time = COALESCE(quarantine_time, NOW()),
+            deactivated_reason = $2, deactivated_at = COALESCE(deactivated_at, NOW()), update_time = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(link.id)
+    .bind(DeactivateReason::Abuse.as_str())
+    .fetch_one(&mut *tx)
+    .await?;
+    audit_service::record_link_change(&mut tx, auditor, AuditAction::LinkTakedown, before.as_ref(), Some(&after))
+        .await?;
+    resolve_reports(&mut tx, link.id, ReportStatus::Confirmed, &actor.name).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} 确认举报并下架短链 {}", actor.name, link_code);
+    if let Err(e) = evict_cache(state, &link).await {
+        tracing::error!("清除缓存失败: {}", e);
+    }
+    mark_quarantined(state, link.id, false).await
+}
+
+/// 驳回举报并解除隔离，待处理的举报标记为已驳回
+pub async fn release(
+    state: &IState,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    link_code: &str,
+) -> HandlerResult<()> {
+    let link = query_link_by_code(state, actor.workspace_id, link_code).await?;
+    let mut tx = state.db_pool.begin().await?;
+    let before = match lock_by_id(&mut tx, link.id).await? {
+        Some(before) if before.active => before,
+        _ => return Err(AppError::bad_request(anyhow::anyhow!("短链已失效"))),
+    };
+    let after = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        UPDATE link_history SET quarantined = false, quarantine_time = NULL, update_time = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(link.id)
+    .fetch_one(&mut *tx)
+    .await?;
+    audit_service::record_link_change(&mut tx, auditor, AuditAction::LinkRelease, Some(&before), Some(&after))
+        .await?;
+    resolve_reports(&mut tx, link.id, ReportStatus::Dismissed, &actor.name).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} 驳回举报并解除隔离短链 {}", actor.name, link_code);
+    mark_quarantined(state, link.id, false).await
+}
+
+async fn resolve_reports(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    link_id: i64,
+    status: ReportStatus,
+    operator: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE abuse_report SET status = $2, handled_by = $3, handle_time = NOW()
+        WHERE link_id = $1 AND status = 'open'
+        "#,
+    )
+    .bind(link_id)
+    .bind(status.as_str())
+    .bind(operator)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// 同步Redis中的隔离集合，跳转时据此显示风险提示页
+pub async fn mark_quarantined(state: &IState, id: i64, quarantined: bool) -> Result<(), AppError> {
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+    if quarantined {
+        let _: () = r_con.sadd(QUARANTINED_LINKS_KEY, id).await?;
+    } else {
+        let _: () = r_con.srem(QUARANTINED_LINKS_KEY, id).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Abuse, Config};
+    use crate::test_support;
+    use crate::types::enums::Role;
+    use crate::utils::helper::encode_base62;
+
+    fn request_id() -> RequestId {
+        RequestId {
+            id: "test".to_string(),
+            upstream: None,
+        }
+    }
+
+    /// 每次生成独立的举报人，避免与其他测试和历史数据中的举报互相影响
+    fn reporter() -> IpAddr {
+        IpAddr::from(std::net::Ipv6Addr::from((0x2001_0db8u128 << 96) | YitIdHelper::next_id() as u128))
+    }
+
+    async fn reload(state: &IState, link: &LinkHistory) -> LinkHistory {
+        query_link_by_code(state, link.workspace_id, &encode_base62(link.id as usize)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn reporter_open_reports_limited() {
+        let cfg = Config {
+            abuse: Some(Abuse {
+                report_threshold: Some(0),
+                max_open_reports_per_reporter: Some(2),
+            }),
+            ..Config::default()
+        };
+        let Some((state, _receivers)) = test_support::state_with_config(cfg).await else { return };
+        let ws = test_support::workspace_id();
+        let ip = reporter();
+        let mut codes = Vec::new();
+        for n in 0..3 {
+            let link = test_support::insert_link(&state, ws, &format!("https://93.184.216.34/limit/{}", n)).await;
+            codes.push(encode_base62(link.id as usize));
+        }
+
+        for code in &codes[..2] {
+            report(&state, None, code, ip, AbuseReason::Spam, None, &request_id()).await.unwrap();
+        }
+        // 重复举报同一链接不受上限影响
+        report(&state, None, &codes[0], ip, AbuseReason::Spam, None, &request_id()).await.unwrap();
+        let err = report(&state, None, &codes[2], ip, AbuseReason::Spam, None, &request_id()).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::TOO_MANY_REQUESTS);
+        // 其他举报人不受影响
+        report(&state, None, &codes[2], reporter(), AbuseReason::Spam, None, &request_id()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn threshold_quarantine_then_confirm_or_release() {
+        let Some(state) = test_support::redis_state().await else { return };
+        let ws = test_support::workspace_id();
+        let admin = test_support::actor(ws, Role::Admin);
+        let confirmed = test_support::insert_link(&state, ws, "https://93.184.216.34/confirm").await;
+        let released = test_support::insert_link(&state, ws, "https://93.184.216.34/release").await;
+
+        for link in [&confirmed, &released] {
+            let code = encode_base62(link.id as usize);
+            for _ in 0..3 {
+                assert!(!reload(&state, link).await.quarantined);
+                report(&state, None, &code, reporter(), AbuseReason::Phishing, None, &request_id())
+                    .await
+                    .unwrap();
+            }
+            assert!(reload(&state, link).await.quarantined);
+        }
+
+        confirm_takedown(&state, &admin, &Auditor::system(), &encode_base62(confirmed.id as usize)).await.unwrap();
+        let link = reload(&state, &confirmed).await;
+        assert!(!link.active && link.quarantined);
+        assert_eq!(link.deactivated_reason.as_deref(), Some(DeactivateReason::Abuse.as_str()));
+
+        release(&state, &admin, &Auditor::system(), &encode_base62(released.id as usize)).await.unwrap();
+        let link = reload(&state, &released).await;
+        assert!(link.active && !link.quarantined);
+
+        let reports = list_reports(&state, ws, Some(ReportStatus::Open), Pagination { page: 1, per_page_last: 0, page_size: 10 })
+            .await
+            .unwrap();
+        assert_eq!(reports.total, 0);
+        let reports = list_reports(&state, ws, Some(ReportStatus::Confirmed), Pagination { page: 1, per_page_last: 0, page_size: 10 })
+            .await
+            .unwrap();
+        assert_eq!(reports.total, 3);
+    }
+}
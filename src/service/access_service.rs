@@ -0,0 +1,470 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use bb8_redis::redis::{self, cmd, pipe, AsyncCommands, Script};
+use chrono::{DateTime, NaiveDate, Utc};
+use tokio::select;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::idgen::YitIdHelper;
+use crate::link_base_service::save_access_records;
+use crate::pojo::access_event::AccessEvent;
+use crate::types::IState;
+
+/// 最近访问时间，member为链接id，score为访问时间（Unix时间戳）
+const ACCESS_TOUCH_KEY: &str = "link:access:touch";
+/// 累计点击次数，field为链接id，value为点击次数
+const ACCESS_CLICKS_KEY: &str = "link:access:clicks";
+
+/// 每日独立访客的HyperLogLog，key为 link:uv:{id}:{yyyymmdd}
+const UNIQUE_VISITOR_KEY: &str = "link:uv:";
+/// 独立访客数据保留3天，保证跨天后汇总任务仍能读取前一天的数据
+const UNIQUE_VISITOR_TTL_SECONDS: i64 = 259200;
+/// 刷新专用的key超过该时间（秒）仍存在，说明刷新过程中进程已退出
+const STALE_FLUSHING_SECONDS: i64 = 600;
+
+/// 待刷新的数据存在时，移到本次刷新专用的key
+///
+/// 访问记录被全部清除时点击次数的key可能不存在，单独判断，避免只移走一半
+static TAKE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r#"
+        if redis.call('EXISTS', KEYS[1]) == 0 then
+            return 0
+        end
+        redis.call('RENAME', KEYS[1], KEYS[3])
+        if redis.call('EXISTS', KEYS[2]) == 1 then
+            redis.call('RENAME', KEYS[2], KEYS[4])
+        end
+        return 1
+        "#,
+    )
+});
+
+/// 将刷新专用key中的数据合并回待刷新的key：最近访问时间取较大值，点击次数累加
+static RESTORE_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r#"
+        redis.call('ZUNIONSTORE', KEYS[1], 2, KEYS[1], KEYS[3], 'AGGREGATE', 'MAX')
+        local clicks = redis.call('HGETALL', KEYS[4])
+        for i = 1, #clicks, 2 do
+            redis.call('HINCRBY', KEYS[2], clicks[i], clicks[i + 1])
+        end
+        redis.call('DEL', KEYS[3], KEYS[4])
+        return 1
+        "#,
+    )
+});
+
+/// 访问记录最长写入间隔（毫秒）
+const WRITE_INTERVAL_MS: u64 = 1000;
+
+/// 独立访客key：link:uv:{id}:{yyyymmdd}（UTC日期）
+pub fn unique_visitor_key(id: i64, date: NaiveDate) -> String {
+    format!("{}{}:{}", UNIQUE_VISITOR_KEY, id, date.format("%Y%m%d"))
+}
+
+/// 记录一次短链访问，放入队列由写入任务批量写入Redis，队列已满时直接丢弃，不阻塞重定向
+///
+/// * `visitor`: 访客指纹的哈希值，用于估算每日独立访客数
+pub fn record_access(state: &IState, id: i64, visitor: String) {
+    let event = AccessEvent {
+        link_id: id,
+        timestamp: Utc::now().timestamp(),
+        visitor,
+    };
+    if let Err(TrySendError::Full(event)) = state.access_tx.try_send(event) {
+        tracing::debug!("访问记录队列已满，丢弃链接 {} 的访问", event.link_id);
+    }
+}
+
+/// 访问记录批量写入任务，攒满一批或到达写入间隔时通过一个Pipeline写入Redis
+pub async fn access_writer_task(
+    state: Arc<IState>,
+    mut access_rx: mpsc::Receiver<AccessEvent>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) {
+    let batch_size = state.access_config.batch_size.unwrap_or(500).max(1);
+    let mut interval = tokio::time::interval(Duration::from_millis(WRITE_INTERVAL_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut buffer: Vec<AccessEvent> = Vec::with_capacity(batch_size);
+
+    tracing::info!("访问记录写入任务已启动，批量大小: {}", batch_size);
+
+    loop {
+        // 缓冲区满时已经写入，剩余容量至少为1
+        let limit = batch_size - buffer.len();
+        select! {
+            received = access_rx.recv_many(&mut buffer, limit) => {
+                if received == 0 {
+                    break;
+                }
+                if buffer.len() >= batch_size {
+                    write_batch(&state, &mut buffer).await;
+                }
+            }
+            _ = interval.tick() => {
+                if !buffer.is_empty() {
+                    write_batch(&state, &mut buffer).await;
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                // 写入队列中剩余的访问记录
+                while let Ok(event) = access_rx.try_recv() {
+                    buffer.push(event);
+                    if buffer.len() >= batch_size {
+                        write_batch(&state, &mut buffer).await;
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    if !buffer.is_empty() {
+        write_batch(&state, &mut buffer).await;
+    }
+    tracing::info!("访问记录写入任务已停止");
+}
+
+async fn write_batch(state: &IState, buffer: &mut Vec<AccessEvent>) {
+    if let Err(e) = touch(state, buffer).await {
+        tracing::warn!("写入 {} 条访问记录失败: {}", buffer.len(), e);
+    }
+    buffer.clear();
+}
+
+/// 一批访问记录合并后的结果
+#[derive(Debug, Default, PartialEq)]
+struct AccessBatch {
+    /// 链接id -> (最近访问时间, 访问次数)
+    touches: BTreeMap<i64, (i64, i64)>,
+    /// 独立访客key -> 访客指纹
+    visitors: BTreeMap<String, Vec<String>>,
+}
+
+/// 合并同一批次内同一链接的访问，减少写入Redis的命令数
+fn aggregate(events: &[AccessEvent]) -> AccessBatch {
+    let mut batch = AccessBatch::default();
+    for event in events {
+        let touch = batch.touches.entry(event.link_id).or_insert((event.timestamp, 0));
+        touch.0 = touch.0.max(event.timestamp);
+        touch.1 += 1;
+        let date = DateTime::from_timestamp(event.timestamp, 0)
+            .unwrap_or_else(Utc::now)
+            .date_naive();
+        batch
+            .visitors
+            .entry(unique_visitor_key(event.link_id, date))
+            .or_default()
+            .push(event.visitor.clone());
+    }
+    batch
+}
+
+async fn touch(state: &IState, events: &[AccessEvent]) -> Result<(), crate::AppError> {
+    let batch = aggregate(events);
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    let mut pipeline = pipe();
+    pipeline.atomic();
+    for (id, (timestamp, count)) in &batch.touches {
+        // GT保证乱序写入时不会把最近访问时间改小
+        pipeline
+            .cmd("ZADD")
+            .arg(ACCESS_TOUCH_KEY)
+            .arg("GT")
+            .arg(timestamp)
+            .arg(id)
+            .ignore()
+            .hincr(ACCESS_CLICKS_KEY, id, *count)
+            .ignore();
+    }
+    for (uv_key, visitors) in &batch.visitors {
+        pipeline
+            .pfadd(uv_key, visitors)
+            .ignore()
+            .expire(uv_key, UNIQUE_VISITOR_TTL_SECONDS)
+            .ignore();
+    }
+    let _: () = pipeline.query_async(&mut *r_con).await?;
+    Ok(())
+}
+
+/// 清除链接在Redis中尚未刷新的点击次数和独立访客数据
+pub async fn erase_access(state: &IState, id: i64) -> Result<(), crate::AppError> {
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    let today = Utc::now().date_naive();
+    let uv_keys: Vec<String> = (0..=UNIQUE_VISITOR_TTL_SECONDS / 86400)
+        .map(|days| unique_visitor_key(id, today - chrono::Duration::days(days)))
+        .collect();
+    let _: () = pipe()
+        .atomic()
+        .hdel(ACCESS_CLICKS_KEY, id)
+        .ignore()
+        .del(&uv_keys)
+        .ignore()
+        .query_async(&mut *r_con)
+        .await?;
+    Ok(())
+}
+
+/// 定时将Redis中的访问记录刷新到数据库的任务
+pub async fn access_flush_task(state: Arc<IState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let interval_secs = state.access_config.flush_interval_secs.unwrap_or(60);
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    tracing::info!("访问记录刷新任务已启动，刷新间隔: {} 秒", interval_secs);
+
+    // 上次刷新过程中进程退出时，已取走的数据还留在刷新专用的key中
+    match recover_flushing(&state).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("已合并 {} 组未完成刷新的访问记录", count),
+        Err(e) => tracing::error!("合并未完成刷新的访问记录失败: {}", e),
+    }
+
+    loop {
+        select! {
+            _ = interval.tick() => {
+                if let Err(e) = flush_access(&state).await {
+                    tracing::error!("刷新访问记录失败: {}", e);
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                if let Err(e) = flush_access(&state).await {
+                    tracing::error!("关闭前刷新访问记录失败: {}", e);
+                }
+                break;
+            }
+        }
+    }
+
+    tracing::info!("访问记录刷新任务已停止");
+}
+
+/// 刷新专用的key：{key}:flushing:{Unix时间戳}:{id}
+fn flushing_keys(timestamp: i64, id: i64) -> (String, String) {
+    (
+        format!("{}:flushing:{}:{}", ACCESS_TOUCH_KEY, timestamp, id),
+        format!("{}:flushing:{}:{}", ACCESS_CLICKS_KEY, timestamp, id),
+    )
+}
+
+/// 将Redis中累积的访问记录刷新到数据库，返回刷新的链接数量
+pub async fn flush_access(state: &IState) -> Result<usize, crate::AppError> {
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    // 通过RENAME原子地取走待刷新的数据，刷新期间的新访问写入新的key
+    let (flushing_touch_key, flushing_clicks_key) =
+        flushing_keys(Utc::now().timestamp(), YitIdHelper::next_id());
+    let taken: i64 = TAKE_SCRIPT
+        .key(ACCESS_TOUCH_KEY)
+        .key(ACCESS_CLICKS_KEY)
+        .key(&flushing_touch_key)
+        .key(&flushing_clicks_key)
+        .invoke_async(&mut *r_con)
+        .await?;
+    if taken == 0 {
+        // 没有新的访问，或其他实例已经取走了数据
+        return Ok(0);
+    }
+
+    let touches: Vec<(String, f64)> = r_con.zrange_withscores(&flushing_touch_key, 0, -1).await?;
+    let clicks: HashMap<String, i64> = r_con.hgetall(&flushing_clicks_key).await?;
+
+    let mut ids = Vec::with_capacity(touches.len());
+    let mut timestamps = Vec::with_capacity(touches.len());
+    let mut counts = Vec::with_capacity(touches.len());
+    for (member, score) in touches {
+        if let Ok(id) = member.parse::<i64>() {
+            counts.push(clicks.get(&member).copied().unwrap_or(0));
+            ids.push(id);
+            timestamps.push(score);
+        }
+    }
+
+    match save_access_records(&state.db_pool, &ids, &timestamps, &counts).await {
+        Ok(_) => {
+            let _: () = r_con.del(&[&flushing_touch_key, &flushing_clicks_key]).await?;
+            Ok(ids.len())
+        }
+        Err(e) => {
+            // 写库失败时把数据合并回去，等待下次刷新
+            restore(&mut r_con, &flushing_touch_key, &flushing_clicks_key).await?;
+            Err(e)
+        }
+    }
+}
+
+/// 将刷新专用key中的数据合并回待刷新的key
+async fn restore(
+    r_con: &mut redis::aio::MultiplexedConnection,
+    flushing_touch_key: &str,
+    flushing_clicks_key: &str,
+) -> Result<(), crate::AppError> {
+    let _: i64 = RESTORE_SCRIPT
+        .key(ACCESS_TOUCH_KEY)
+        .key(ACCESS_CLICKS_KEY)
+        .key(flushing_touch_key)
+        .key(flushing_clicks_key)
+        .invoke_async(r_con)
+        .await?;
+    Ok(())
+}
+
+/// 合并超过 `STALE_FLUSHING_SECONDS` 仍未删除的刷新专用key，返回合并的组数
+///
+/// 刷新中的key不会存在这么久，不会与其他实例正在进行的刷新冲突
+async fn recover_flushing(state: &IState) -> Result<usize, crate::AppError> {
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    let prefix = format!("{}:flushing:", ACCESS_TOUCH_KEY);
+    let keys: Vec<String> = {
+        let mut iter = r_con.scan_match::<_, String>(format!("{}*", prefix)).await?;
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        keys
+    };
+
+    let deadline = Utc::now().timestamp() - STALE_FLUSHING_SECONDS;
+    let mut recovered = 0;
+    for key in keys {
+        let Some((timestamp, id)) = key
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.split_once(':'))
+            .and_then(|(timestamp, id)| Some((timestamp.parse::<i64>().ok()?, id.parse::<i64>().ok()?)))
+        else {
+            continue;
+        };
+        if timestamp > deadline {
+            continue;
+        }
+        let (flushing_touch_key, flushing_clicks_key) = flushing_keys(timestamp, id);
+        restore(&mut r_con, &flushing_touch_key, &flushing_clicks_key).await?;
+        recovered += 1;
+    }
+    Ok(recovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link_base_service::{query_by_id, save};
+    use crate::pojo::link_history::{LinkHistory, LinkOwner};
+    use crate::service::audit_service::Auditor;
+    use crate::test_support;
+    use crate::utils::helper::calculate_sha256;
+
+    fn event(link_id: i64, timestamp: i64, visitor: &str) -> AccessEvent {
+        AccessEvent { link_id, timestamp, visitor: visitor.to_string() }
+    }
+
+    #[test]
+    fn aggregate_batch() {
+        // 2023-11-14 22:13:20 UTC 与次日
+        let day1 = 1_700_000_000;
+        let day2 = day1 + 86400;
+        let batch = aggregate(&[
+            event(1, day1 + 10, "a"),
+            event(1, day1, "b"),
+            event(2, day1, "a"),
+            event(1, day2, "a"),
+        ]);
+        assert_eq!(batch.touches[&1], (day2, 3));
+        assert_eq!(batch.touches[&2], (day1, 1));
+        let date1 = DateTime::from_timestamp(day1, 0).unwrap().date_naive();
+        let date2 = DateTime::from_timestamp(day2, 0).unwrap().date_naive();
+        assert_eq!(batch.visitors[&unique_visitor_key(1, date1)], vec!["a", "b"]);
+        assert_eq!(batch.visitors[&unique_visitor_key(1, date2)], vec!["a"]);
+        assert_eq!(batch.visitors.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn access_extends_idle_deadline() {
+        let Some(state) = test_support::state().await else { return };
+        let ws = test_support::workspace_id();
+        let domain = state.link_config.default_domain();
+        let url = "https://93.184.216.34/idle";
+        let id = YitIdHelper::next_id();
+        let link = LinkHistory::from_url(id, ws, LinkOwner::default(), &domain, url, calculate_sha256(url), Some(3600));
+        assert!(save(&state.db_pool, &link, &Auditor::system()).await.unwrap().is_some());
+        let plain = test_support::insert_link(&state, ws, "https://93.184.216.34/plain").await;
+
+        let accessed_at = Utc::now().timestamp() - 60;
+        save_access_records(
+            &state.db_pool,
+            &[id, plain.id],
+            &[accessed_at as f64, accessed_at as f64],
+            &[2, 1],
+        )
+        .await
+        .unwrap();
+
+        let link = query_by_id(&state.db_pool, ws, id).await.unwrap().unwrap();
+        let deadline = link.idle_deadline.unwrap().and_utc().timestamp();
+        assert_eq!(deadline, accessed_at + 3600);
+        let plain = query_by_id(&state.db_pool, ws, plain.id).await.unwrap().unwrap();
+        assert!(plain.idle_deadline.is_none());
+
+        let clicks: i64 = sqlx::query_scalar("SELECT click_count FROM link_stats WHERE link_id = $1")
+            .bind(id)
+            .fetch_one(&state.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(clicks, 2);
+    }
+
+    #[tokio::test]
+    async fn flush_and_recover() {
+        let Some(state) = test_support::redis_state().await else { return };
+        let ws = test_support::workspace_id();
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/flush").await;
+        let now = Utc::now().timestamp();
+        touch(&state, &[event(link.id, now, "a"), event(link.id, now, "b")]).await.unwrap();
+        flush_access(&state).await.unwrap();
+
+        let click_count = |state: Arc<IState>| async move {
+            sqlx::query_scalar::<_, i64>("SELECT click_count FROM link_stats WHERE link_id = $1")
+                .bind(link.id)
+                .fetch_one(&state.db_pool)
+                .await
+                .unwrap()
+        };
+        assert_eq!(click_count(state.clone()).await, 2);
+
+        // 模拟刷新过程中进程退出留下的key：只有最近访问时间，没有点击次数
+        let mut r_con = state.redis_pool.get().await.unwrap();
+        let (touch_key, clicks_key) = flushing_keys(now - STALE_FLUSHING_SECONDS - 1, YitIdHelper::next_id());
+        let _: () = r_con.zadd(&touch_key, link.id, now).await.unwrap();
+        let _: () = r_con.hset(&clicks_key, link.id, 3).await.unwrap();
+        let (recent_key, _) = flushing_keys(now, YitIdHelper::next_id());
+        let _: () = r_con.zadd(&recent_key, link.id, now).await.unwrap();
+
+        assert!(recover_flushing(&state).await.unwrap() >= 1);
+        let exists: bool = r_con.exists(&touch_key).await.unwrap();
+        assert!(!exists);
+        // 其他实例可能正在刷新的key不处理
+        let exists: bool = r_con.exists(&recent_key).await.unwrap();
+        assert!(exists);
+        let _: () = r_con.del(&recent_key).await.unwrap();
+        drop(r_con);
+
+        flush_access(&state).await.unwrap();
+        assert_eq!(click_count(state.clone()).await, 5);
+    }
+}
+
@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+
+use crate::idgen::YitIdHelper;
+use crate::pojo::api_key::{ApiKey, ApiKeyCreatedResponse, ApiKeyResponse};
+use crate::pojo::AppError;
+use crate::service::audit_service::{self, Auditor};
+use crate::service::workspace_service;
+use crate::types::enums::{ApiScope, AuditAction};
+use crate::types::{HandlerResult, IState};
+use crate::utils::helper::calculate_sha256;
+
+/// API Key的固定前缀，便于在日志和代码仓库中识别泄露的Key
+const API_KEY_PREFIX: &str = "slk_";
+/// 保存用于识别的Key前缀长度
+const KEY_PREFIX_LEN: usize = 12;
+
+/// 在工作空间内创建API Key，可绑定到工作空间内的用户，返回的明文Key只出现这一次
+pub async fn create_api_key(
+    m_conn: &sqlx::PgPool,
+    auditor: &Auditor,
+    workspace_id: i64,
+    user_id: Option<i64>,
+    name: String,
+    scopes: Vec<String>,
+) -> Result<ApiKeyCreatedResponse, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::bad_request(anyhow::anyhow!("名称不能为空")));
+    }
+    if scopes.is_empty() {
+        return Err(AppError::bad_request(anyhow::anyhow!("至少指定一个权限范围")));
+    }
+    if let Some(unknown) = scopes.iter().find(|scope| ApiScope::parse(scope).is_none()) {
+        return Err(AppError::bad_request(anyhow::anyhow!("不支持的权限范围: {}", unknown)));
+    }
+    workspace_service::get_workspace(m_conn, workspace_id).await?;
+    if let Some(user_id) = user_id
+        && !workspace_service::user_in_workspace(m_conn, workspace_id, user_id).await?
+    {
+        return Err(AppError::not_found(anyhow::anyhow!("用户不存在: {}", user_id)));
+    }
+
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let key = format!("{}{}", API_KEY_PREFIX, hex::encode(bytes));
+
+    let mut tx = m_conn.begin().await?;
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        r#"
+        INSERT INTO api_key (id, workspace_id, user_id, name, key_prefix, key_hash, scopes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#,
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(workspace_id)
+    .bind(user_id)
+    .bind(&name)
+    .bind(&key[..KEY_PREFIX_LEN])
+    .bind(calculate_sha256(&key))
+    .bind(&scopes)
+    .fetch_one(&mut *tx)
+    .await?
+    .to_response();
+    // 明文Key不写入审计日志
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace_id,
+        AuditAction::ApiKeyCreate,
+        api_key.id,
+        None,
+        serde_json::to_value(&api_key).ok(),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(ApiKeyCreatedResponse { key, api_key })
+}
+
+/// 查询API Key，不包含明文和哈希值，未指定工作空间时查询全部
+pub async fn list_api_keys(
+    m_conn: &sqlx::PgPool,
+    workspace_id: Option<i64>,
+) -> Result<Vec<ApiKeyResponse>, AppError> {
+    let api_keys = sqlx::query_as::<_, ApiKey>(
+        r#"
+        SELECT * FROM api_key
+        WHERE $1::bigint IS NULL OR workspace_id = $1
+        ORDER BY create_time DESC
+        "#,
+    )
+    .bind(workspace_id)
+    .fetch_all(m_conn)
+    .await?;
+    Ok(api_keys.iter().map(|api_key| api_key.to_response()).collect())
+}
+
+/// 吊销API Key，吊销后立即失效，指定工作空间时只能吊销该工作空间的Key
+pub async fn revoke_api_key(
+    m_conn: &sqlx::PgPool,
+    auditor: &Auditor,
+    workspace_id: Option<i64>,
+    id: i64,
+) -> Result<ApiKeyResponse, AppError> {
+    let mut tx = m_conn.begin().await?;
+    let before = sqlx::query_as::<_, ApiKey>(
+        r#"
+        SELECT * FROM api_key
+        WHERE id = $1 AND revoked = false AND ($2::bigint IS NULL OR workspace_id = $2)
+        FOR UPDATE
+        "#,
+    )
+    .bind(id)
+    .bind(workspace_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found(anyhow::anyhow!("API Key不存在或已吊销")))?
+    .to_response();
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        "UPDATE api_key SET revoked = true, update_time = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?
+    .to_response();
+    audit_service::record(
+        &mut tx,
+        auditor,
+        api_key.workspace_id,
+        AuditAction::ApiKeyRevoke,
+        id,
+        serde_json::to_value(&before).ok(),
+        serde_json::to_value(&api_key).ok(),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(api_key)
+}
+
+/// 校验明文Key，返回未吊销的API Key
+pub async fn authenticate(state: &Arc<IState>, key: &str) -> HandlerResult<Option<ApiKey>> {
+    if !key.starts_with(API_KEY_PREFIX) {
+        return Ok(None);
+    }
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        r#"
+        SELECT k.*, u.role AS user_role
+        FROM api_key k LEFT JOIN app_user u ON u.id = k.user_id
+        WHERE k.key_hash = $1 AND k.revoked = false
+        "#,
+    )
+    .bind(calculate_sha256(key))
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    if let Some(api_key) = &api_key {
+        touch(state.clone(), api_key.id);
+    }
+    Ok(api_key)
+}
+
+/// 异步更新最近使用时间，每分钟最多更新一次
+fn touch(state: Arc<IState>, id: i64) {
+    tokio::spawn(async move {
+        let result = sqlx::query(
+            r#"
+            UPDATE api_key SET last_used_at = NOW()
+            WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < NOW() - interval '1 minute')
+            "#,
+        )
+        .bind(id)
+        .execute(&state.db_pool)
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("更新API Key {} 的使用时间失败: {}", id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+    use crate::test_support;
+    use crate::types::enums::Role;
+
+    fn scopes(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn key_hashing_and_scopes() {
+        let Some(state) = test_support::state().await else { return };
+        let db_pool = &state.db_pool;
+        let ws = workspace_service::create_workspace(db_pool, &Auditor::system(), "keys".to_string()).await.unwrap().id;
+        let viewer = workspace_service::create_user(db_pool, &Auditor::system(), ws, "viewer".to_string(), None, Role::Viewer)
+            .await
+            .unwrap();
+
+        let err = create_api_key(db_pool, &Auditor::system(), ws, None, "bad".to_string(), scopes(&["write"])).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        let admin = create_api_key(db_pool, &Auditor::system(), ws, None, "admin".to_string(), scopes(&["read", "admin"]))
+            .await
+            .unwrap();
+        assert!(admin.key.starts_with(API_KEY_PREFIX));
+        assert_eq!(admin.api_key.key_prefix, admin.key[..KEY_PREFIX_LEN]);
+        // 只保存哈希值，不保存明文
+        let stored: String = sqlx::query_scalar("SELECT key_hash FROM api_key WHERE id = $1")
+            .bind(admin.api_key.id)
+            .fetch_one(db_pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, calculate_sha256(&admin.key));
+
+        let found = authenticate(&state, &admin.key).await.unwrap().unwrap();
+        assert_eq!(found.id, admin.api_key.id);
+        assert_eq!(found.role(), Some(Role::Admin));
+        assert!(authenticate(&state, &format!("{}0", admin.key)).await.unwrap().is_none());
+        assert!(authenticate(&state, "not-a-key").await.unwrap().is_none());
+
+        // 绑定用户的Key不超过用户的角色
+        let bound = create_api_key(db_pool, &Auditor::system(), ws, Some(viewer.id), "bound".to_string(), scopes(&["create"]))
+            .await
+            .unwrap();
+        let found = authenticate(&state, &bound.key).await.unwrap().unwrap();
+        assert_eq!(found.role(), Some(Role::Viewer));
+
+        revoke_api_key(db_pool, &Auditor::system(), Some(ws), admin.api_key.id).await.unwrap();
+        assert!(authenticate(&state, &admin.key).await.unwrap().is_none());
+        let err = revoke_api_key(db_pool, &Auditor::system(), Some(ws + 1), bound.api_key.id).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,279 @@
+use chrono::NaiveDateTime;
+use serde_json::{json, Value};
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+
+use crate::idgen::YitIdHelper;
+use crate::pojo::api_key::ApiActor;
+use crate::pojo::audit_log::{AuditLog, AuditLogListResponse};
+use crate::pojo::link_history::LinkHistory;
+use crate::pojo::{AppError, Pagination};
+use crate::types::enums::AuditAction;
+use crate::types::{HandlerResult, IState, RequestId};
+use crate::utils::helper::encode_base62;
+
+/// 批量写入时每条语句的最大行数
+const MAX_INSERT_ROWS: usize = 1000;
+
+/// 审计日志的操作人
+#[derive(Debug, Clone)]
+pub struct Auditor {
+    pub actor: String,
+    pub key_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub request_id: Option<String>,
+    /// 可信代理传入的上游请求id
+    pub upstream_request_id: Option<String>,
+}
+
+impl Auditor {
+    /// 管理接口的调用方
+    pub fn new(actor: &ApiActor, request_id: &RequestId) -> Self {
+        Self {
+            actor: actor.name.clone(),
+            key_id: actor.key_id,
+            user_id: actor.user_id,
+            request_id: Some(request_id.id.clone()),
+            upstream_request_id: request_id.upstream.clone(),
+        }
+    }
+
+    /// 清理任务、自动隔离等后台操作
+    pub fn system() -> Self {
+        Self::named("system")
+    }
+
+    /// 命令行操作
+    pub fn cli() -> Self {
+        Self::named("cli")
+    }
+
+    fn named(name: &str) -> Self {
+        Self {
+            actor: name.to_string(),
+            key_id: None,
+            user_id: None,
+            request_id: None,
+            upstream_request_id: None,
+        }
+    }
+}
+
+/// 一条待写入的审计记录：(工作空间, 操作对象, 操作前, 操作后)
+pub type AuditEntry = (i64, String, Option<Value>, Option<Value>);
+
+/// 在操作所在的事务中写入一条审计日志，写入失败时操作一同回滚
+pub async fn record(
+    conn: &mut PgConnection,
+    auditor: &Auditor,
+    workspace_id: i64,
+    action: AuditAction,
+    target_id: impl ToString,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), AppError> {
+    let entry = (workspace_id, target_id.to_string(), before, after);
+    record_batch(conn, auditor, action, &[entry]).await
+}
+
+/// 在操作所在的事务中批量写入同一操作的审计日志，用于清理任务
+pub async fn record_batch(
+    conn: &mut PgConnection,
+    auditor: &Auditor,
+    action: AuditAction,
+    entries: &[AuditEntry],
+) -> Result<(), AppError> {
+    for chunk in entries.chunks(MAX_INSERT_ROWS) {
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            r#"INSERT INTO audit_log (id, workspace_id, actor, actor_key_id, actor_user_id, action,
+            target_type, target_id, before, after, request_id, upstream_request_id) "#,
+        );
+        builder.push_values(chunk, |mut row, (workspace_id, target_id, before, after)| {
+            row.push_bind(YitIdHelper::next_id())
+                .push_bind(workspace_id)
+                .push_bind(&auditor.actor)
+                .push_bind(auditor.key_id)
+                .push_bind(auditor.user_id)
+                .push_bind(action.as_str())
+                .push_bind(action.target_type())
+                .push_bind(target_id)
+                .push_bind(before)
+                .push_bind(after)
+                .push_bind(&auditor.request_id)
+                .push_bind(&auditor.upstream_request_id);
+        });
+        builder.build().execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+/// 在修改链接的事务中记录审计日志，操作前后的值分别来自事务中锁定读取的行和修改返回的行，相同时不记录
+pub async fn record_link_change(
+    conn: &mut PgConnection,
+    auditor: &Auditor,
+    action: AuditAction,
+    before: Option<&LinkHistory>,
+    after: Option<&LinkHistory>,
+) -> Result<(), AppError> {
+    match link_entry(before, after) {
+        Some(entry) => record_batch(conn, auditor, action, &[entry]).await,
+        None => Ok(()),
+    }
+}
+
+/// 链接的审计记录，操作前后的值相同时为空
+pub fn link_entry(before: Option<&LinkHistory>, after: Option<&LinkHistory>) -> Option<AuditEntry> {
+    let link = after.or(before)?;
+    let before = before.map(link_snapshot);
+    let after = after.map(link_snapshot);
+    if before == after {
+        return None;
+    }
+    Some((link.workspace_id, encode_base62(link.id as usize), before, after))
+}
+
+/// 链接中需要审计的字段，时间为毫秒时间戳
+pub fn link_snapshot(link: &LinkHistory) -> Value {
+    let millis = |dt: Option<NaiveDateTime>| dt.map(|dt| dt.and_utc().timestamp_millis());
+    json!({
+        "link_code": encode_base62(link.id as usize),
+        "domain": link.domain,
+        "origin_url": link.origin_url,
+        "active": link.active,
+        "expire_date": millis(link.expire_date),
+        "idle_ttl": link.idle_ttl,
+        "quarantined": link.quarantined,
+        "require_signature": link.require_signature,
+        "owner_id": link.owner_id,
+        "owner_key_id": link.owner_key_id,
+    })
+}
+
+/// 审计日志的查询条件，均为可选
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub actor: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+/// 分页查询工作空间内的审计日志，按时间倒序
+pub async fn list_logs(
+    state: &IState,
+    workspace_id: i64,
+    filter: AuditFilter,
+    pagination: Pagination,
+) -> HandlerResult<AuditLogListResponse> {
+    let db_pool = &state.db_pool;
+    let page = pagination.page.max(1);
+    let page_size = pagination.page_size.clamp(1, 100);
+    let condition = r#"
+        workspace_id = $1
+        AND ($2::varchar IS NULL OR action = $2)
+        AND ($3::varchar IS NULL OR target_type = $3)
+        AND ($4::varchar IS NULL OR target_id = $4)
+        AND ($5::varchar IS NULL OR actor = $5)
+        AND ($6::timestamp IS NULL OR create_time >= $6)
+        AND ($7::timestamp IS NULL OR create_time < $7)
+    "#;
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM audit_log WHERE {}", condition))
+        .bind(workspace_id)
+        .bind(&filter.action)
+        .bind(&filter.target_type)
+        .bind(&filter.target_id)
+        .bind(&filter.actor)
+        .bind(filter.from)
+        .bind(filter.to)
+        .fetch_one(db_pool)
+        .await?;
+    let logs = sqlx::query_as::<_, AuditLog>(&format!(
+        "SELECT * FROM audit_log WHERE {} ORDER BY create_time DESC, id DESC LIMIT $8 OFFSET $9",
+        condition
+    ))
+    .bind(workspace_id)
+    .bind(&filter.action)
+    .bind(&filter.target_type)
+    .bind(&filter.target_id)
+    .bind(&filter.actor)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(page_size as i64)
+    .bind(((page - 1) * page_size) as i64)
+    .fetch_all(db_pool)
+    .await?;
+
+    let total_pages = ((total as f64) / (page_size as f64)).ceil() as usize;
+    Ok(AuditLogListResponse {
+        data: logs.iter().map(|log| log.to_response()).collect(),
+        page,
+        page_size,
+        total,
+        last_page: page >= total_pages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link_base_service::{mark_links_as_inactive, update_origin_url};
+    use crate::pojo::link_history::LinkOwner;
+    use crate::test_support;
+    use crate::utils::helper::calculate_sha256;
+
+    #[test]
+    fn snapshot_fields() {
+        let owner = LinkOwner { user_id: Some(7), key_id: Some(9) };
+        let mut link = LinkHistory::from_url(62, 1, owner, "s.example.com", "https://example.com/", String::new(), None);
+        link.expire_date = chrono::DateTime::from_timestamp_millis(1_700_000_000_000).map(|dt| dt.naive_utc());
+        let snapshot = link_snapshot(&link);
+        assert_eq!(snapshot["link_code"], "10");
+        assert_eq!(snapshot["origin_url"], "https://example.com/");
+        assert_eq!(snapshot["expire_date"], 1_700_000_000_000i64);
+        assert_eq!(snapshot["owner_id"], 7);
+        assert_eq!(snapshot["owner_key_id"], 9);
+        assert!(snapshot.get("link_hash").is_none());
+    }
+
+    async fn link_logs(state: &IState, link: &LinkHistory) -> Vec<AuditLog> {
+        sqlx::query_as::<_, AuditLog>("SELECT * FROM audit_log WHERE target_id = $1 ORDER BY id")
+            .bind(encode_base62(link.id as usize))
+            .fetch_all(&state.db_pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn link_changes_logged_with_mutation() {
+        let Some(state) = test_support::state().await else { return };
+        let ws = test_support::workspace_id();
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/audit/v1").await;
+        let auditor = Auditor {
+            request_id: Some("req-1".to_string()),
+            upstream_request_id: Some("upstream-1".to_string()),
+            ..Auditor::system()
+        };
+
+        let url = "https://93.184.216.34/audit/v2";
+        let updated = update_origin_url(&state.db_pool, &link, url, &calculate_sha256(url), &auditor, AuditAction::LinkUpdate)
+            .await
+            .unwrap();
+        assert!(updated);
+        assert_eq!(mark_links_as_inactive(&state.db_pool, &[link.id], &Auditor::system()).await.unwrap(), 1);
+        // 已失效的链接不再记录
+        assert_eq!(mark_links_as_inactive(&state.db_pool, &[link.id], &Auditor::system()).await.unwrap(), 0);
+
+        let logs = link_logs(&state, &link).await;
+        let actions: Vec<&str> = logs.iter().map(|log| log.action.as_str()).collect();
+        assert_eq!(actions, ["link.create", "link.update", "link.deactivate"]);
+        assert!(logs[0].before.is_none());
+        assert_eq!(logs[1].before.as_ref().unwrap()["origin_url"], "https://93.184.216.34/audit/v1");
+        assert_eq!(logs[1].after.as_ref().unwrap()["origin_url"], url);
+        assert_eq!(logs[1].request_id.as_deref(), Some("req-1"));
+        assert_eq!(logs[1].upstream_request_id.as_deref(), Some("upstream-1"));
+        assert_eq!(logs[2].before.as_ref().unwrap()["active"], true);
+        assert_eq!(logs[2].after.as_ref().unwrap()["active"], false);
+    }
+}
@@ -1,18 +1,25 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 use bb8_redis::redis::{cmd, AsyncCommands, Pipeline};
 use chrono::Utc;
+use tokio::io::AsyncWriteExt;
 use tokio::select;
 use tokio::sync::broadcast;
 
-use crate::link_base_service::{query_expired_links, mark_links_as_inactive};
+use crate::access_service::flush_access;
+use crate::link_base_service::{
+    delete_inactive_links, mark_links_as_inactive, query_expired_links, query_purgeable_links,
+};
+use crate::pojo::link_history::{LinkHistory, LinkHistoryResponse};
+use crate::link_service::{hash_cache_key, origin_cache_key};
+use crate::service::audit_service::Auditor;
+use crate::service::webhook_service;
+use crate::types::enums::WebhookEvent;
 use crate::types::IState;
 use crate::utils::helper::calculate_sha256;
 
-const LINK_HASH_KEY: &str = "link:hash:";
-const LINK_ID_KEY: &str = "link:origin:uri:";
-
 /// 定时清理过期链接的任务
 pub async fn cleanup_expired_links_task(
     state: Arc<IState>,
@@ -83,6 +90,13 @@ async fn cleanup_expired_links(state: Arc<IState>) -> Result<usize, crate::AppEr
     };
     
     let result = cleanup_expired_links_internal(state.clone()).await;
+
+    // 第二阶段：物理删除超过保留期的失效链接
+    match purge_inactive_links_internal(state.clone()).await {
+        Ok(count) if count > 0 => tracing::info!("物理删除完成，共删除 {} 条失效链接", count),
+        Ok(_) => {}
+        Err(e) => tracing::error!("物理删除失效链接失败: {}", e),
+    }
     
     // 释放分布式锁
     if lock_acquired {
@@ -98,6 +112,11 @@ async fn cleanup_expired_links(state: Arc<IState>) -> Result<usize, crate::AppEr
 async fn cleanup_expired_links_internal(state: Arc<IState>) -> Result<usize, crate::AppError> {
     let db_pool = &state.db_pool;
     let batch_size = state.cleanup_config.batch_size.unwrap_or(1000);
+
+    // 先刷新尚未落库的访问记录，避免误清理刚被访问过的空闲链接
+    if let Err(e) = flush_access(&state).await {
+        tracing::warn!("清理前刷新访问记录失败: {}", e);
+    }
     
     let expired_links = query_expired_links(db_pool).await?;
     if expired_links.is_empty() {
@@ -112,8 +131,23 @@ async fn cleanup_expired_links_internal(state: Arc<IState>) -> Result<usize, cra
     for chunk in expired_links.chunks(batch_size) {
         let ids: Vec<i64> = chunk.iter().map(|link| link.id).collect();
         
-        let affected = mark_links_as_inactive(db_pool, &ids).await?;
+        // 审计日志与更新在同一事务中写入，写入失败时本批次回滚
+        let affected = mark_links_as_inactive(db_pool, &ids, &Auditor::system()).await?;
         tracing::debug!("数据库更新完成，本批次影响 {} 行", affected);
+
+        if webhook_service::is_subscribed(&state, WebhookEvent::LinkExpired) {
+            // 事件只投递给链接所属工作空间的订阅
+            let mut expired: BTreeMap<i64, Vec<LinkHistoryResponse>> = BTreeMap::new();
+            for link in chunk {
+                expired.entry(link.workspace_id).or_default().push(LinkHistoryResponse {
+                    active: false,
+                    ..link.to_response()
+                });
+            }
+            for (workspace_id, links) in &expired {
+                webhook_service::dispatch(&state, *workspace_id, WebhookEvent::LinkExpired, links);
+            }
+        }
         
         let cache_cleaned = cleanup_redis_cache(state.clone(), chunk).await;
         match cache_cleaned {
@@ -141,10 +175,74 @@ async fn cleanup_expired_links_internal(state: Arc<IState>) -> Result<usize, cra
     Ok(total_cleaned)
 }
 
+/// 物理删除失效超过保留天数的链接，删除前可选归档到JSONL文件
+async fn purge_inactive_links_internal(state: Arc<IState>) -> Result<usize, crate::AppError> {
+    let retention_days = state.cleanup_config.retention_days.unwrap_or(0);
+    if retention_days == 0 {
+        return Ok(0);
+    }
+
+    let db_pool = &state.db_pool;
+    let batch_size = state.cleanup_config.batch_size.unwrap_or(1000);
+    let archive_path = state.cleanup_config.archive_path.as_deref();
+
+    let mut total_purged = 0;
+    loop {
+        let links = query_purgeable_links(db_pool, retention_days, batch_size).await?;
+        if links.is_empty() {
+            break;
+        }
+
+        // 归档失败时不删除，避免数据丢失
+        if let Some(path) = archive_path {
+            archive_links(path, &links).await?;
+        }
+
+        let ids: Vec<i64> = links.iter().map(|link| link.id).collect();
+        let affected = delete_inactive_links(db_pool, &ids, &Auditor::system()).await?;
+        tracing::debug!("物理删除完成，本批次影响 {} 行", affected);
+        total_purged += affected as usize;
+
+        if affected == 0 || links.len() < batch_size {
+            break;
+        }
+    }
+
+    {
+        let mut stats = state.cleanup_stats.write().await;
+        stats.last_purge_count = total_purged;
+        stats.total_purged += total_purged as u64;
+    }
+
+    Ok(total_purged)
+}
+
+/// 将链接以JSONL格式追加写入归档文件
+async fn archive_links(path: &str, links: &[LinkHistory]) -> Result<(), crate::AppError> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut content = String::new();
+    for link in links {
+        content.push_str(&serde_json::to_string(link)?);
+        content.push('\n');
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
 /// 清理Redis缓存（使用Pipeline批量删除）
 async fn cleanup_redis_cache(
     state: Arc<IState>,
-    expired_links: &[crate::pojo::link_history::LinkHistory],
+    expired_links: &[LinkHistory],
 ) -> Result<usize, crate::AppError> {
     let redis_pool = &state.redis_pool;
     let redis_db = state.redis_db.unwrap_or(0);
@@ -158,8 +256,8 @@ async fn cleanup_redis_cache(
     
     for link in expired_links {
         let link_hash = calculate_sha256(&link.origin_url);
-        let hash_key = format!("{}{}", LINK_HASH_KEY, link_hash);
-        let id_key = format!("{}{}", LINK_ID_KEY, link.id);
+        let hash_key = hash_cache_key(link.workspace_id, &link.domain, &link_hash);
+        let id_key = origin_cache_key(&link.domain, link.id);
         
         pipe.del(&hash_key);
         pipe.del(&id_key);
@@ -227,3 +325,58 @@ async fn release_lock(state: Arc<IState>) -> Result<(), crate::AppError> {
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cleanup, Config};
+    use crate::link_base_service::query_by_id;
+    use crate::test_support;
+
+    /// 将链接标记为指定天数之前失效
+    async fn deactivate_days_ago(state: &IState, id: i64, days: i32) {
+        sqlx::query(
+            r#"
+            UPDATE link_history
+            SET active = false, deactivated_reason = 'expired', deactivated_at = NOW() - make_interval(days => $2)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(days)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn purge_respects_retention() {
+        let archive = std::env::temp_dir().join(format!("purge-{}.jsonl", test_support::workspace_id()));
+        let cfg = Config {
+            cleanup: Some(Cleanup {
+                retention_days: Some(7),
+                archive_path: Some(archive.to_string_lossy().into_owned()),
+                ..Cleanup::default()
+            }),
+            ..Config::default()
+        };
+        let Some((state, _)) = test_support::state_with_config(cfg).await else { return };
+        let ws = test_support::workspace_id();
+        let old = test_support::insert_link(&state, ws, "https://93.184.216.34/old").await;
+        let recent = test_support::insert_link(&state, ws, "https://93.184.216.34/recent").await;
+        let active = test_support::insert_link(&state, ws, "https://93.184.216.34/active").await;
+        deactivate_days_ago(&state, old.id, 8).await;
+        deactivate_days_ago(&state, recent.id, 6).await;
+
+        let purged = purge_inactive_links_internal(state.clone()).await.unwrap();
+        assert!(purged >= 1);
+        assert!(query_by_id(&state.db_pool, ws, old.id).await.unwrap().is_none());
+        assert!(query_by_id(&state.db_pool, ws, recent.id).await.unwrap().is_some());
+        assert!(query_by_id(&state.db_pool, ws, active.id).await.unwrap().is_some());
+
+        let archived = tokio::fs::read_to_string(&archive).await.unwrap();
+        let _ = tokio::fs::remove_file(&archive).await;
+        assert!(archived.contains("https://93.184.216.34/old"));
+        assert!(!archived.contains("https://93.184.216.34/recent"));
+    }
+}
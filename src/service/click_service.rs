@@ -0,0 +1,134 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::{Postgres, QueryBuilder};
+use tokio::select;
+use tokio::sync::{broadcast, mpsc};
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::pojo::click_event::ClickEvent;
+use crate::types::IState;
+use crate::utils::helper::referrer_domain;
+use crate::utils::user_agent::parse_user_agent;
+
+// PostgreSQL单条语句最多65535个参数，每个事件占用11个
+const MAX_BATCH_SIZE: usize = 5000;
+
+/// 投递点击事件，队列已满时直接丢弃并计数，不阻塞重定向
+pub fn emit(state: &IState, event: ClickEvent) {
+    match state.click_tx.try_send(event) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => {
+            state.click_stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 点击事件批量写入任务，攒满一批或到达写入间隔时写入数据库
+pub async fn click_writer_task(
+    state: Arc<IState>,
+    mut click_rx: mpsc::Receiver<ClickEvent>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) {
+    let analytics_config = &state.analytics_config;
+    let batch_size = analytics_config.batch_size.unwrap_or(500).clamp(1, MAX_BATCH_SIZE);
+    let flush_interval_ms = analytics_config.flush_interval_ms.unwrap_or(1000);
+
+    let mut interval = tokio::time::interval(Duration::from_millis(flush_interval_ms));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut buffer: Vec<ClickEvent> = Vec::with_capacity(batch_size);
+
+    tracing::info!("点击事件写入任务已启动，批量大小: {}，写入间隔: {} 毫秒", batch_size, flush_interval_ms);
+
+    loop {
+        // 缓冲区满时已经写入，剩余容量至少为1
+        let limit = batch_size - buffer.len();
+        select! {
+            received = click_rx.recv_many(&mut buffer, limit) => {
+                if received == 0 {
+                    break;
+                }
+                if buffer.len() >= batch_size {
+                    write_batch(&state, &mut buffer).await;
+                }
+            }
+            _ = interval.tick() => {
+                if !buffer.is_empty() {
+                    write_batch(&state, &mut buffer).await;
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                // 写入队列中剩余的事件
+                while let Ok(event) = click_rx.try_recv() {
+                    buffer.push(event);
+                    if buffer.len() >= batch_size {
+                        write_batch(&state, &mut buffer).await;
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    if !buffer.is_empty() {
+        write_batch(&state, &mut buffer).await;
+    }
+    tracing::info!("点击事件写入任务已停止");
+}
+
+async fn write_batch(state: &IState, buffer: &mut Vec<ClickEvent>) {
+    let count = buffer.len() as u64;
+    match insert_click_events(&state.db_pool, buffer).await {
+        Ok(_) => {
+            state.click_stats.written.fetch_add(count, Ordering::Relaxed);
+        }
+        Err(e) => {
+            tracing::error!("写入 {} 条点击事件失败: {}", count, e);
+            state.click_stats.dropped.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+    buffer.clear();
+}
+
+/// 多行插入点击事件，同时写入从User-Agent和Referer解析出的维度，供来源分析使用
+async fn insert_click_events(
+    m_conn: &sqlx::PgPool,
+    events: &[ClickEvent],
+) -> Result<u64, crate::AppError> {
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+        r#"
+        INSERT INTO click_event (link_id, click_time, referrer, user_agent, ip, accept_language,
+                                 referrer_domain, browser, os, device, is_bot)
+        "#,
+    );
+    builder.push_values(events, |mut row, event| {
+        let ua_info = event.user_agent.as_deref().map(parse_user_agent);
+        let domain = event.referrer.as_deref().and_then(referrer_domain);
+        row.push_bind(event.link_id)
+            .push_bind(event.click_time)
+            .push_bind(truncate(&event.referrer, 2048))
+            .push_bind(truncate(&event.user_agent, 1024))
+            .push_bind(truncate(&event.ip, 64))
+            .push_bind(truncate(&event.accept_language, 256))
+            .push_bind(truncate(&domain, 255))
+            .push_bind(ua_info.as_ref().map(|info| info.browser))
+            .push_bind(ua_info.as_ref().map(|info| info.os))
+            .push_bind(ua_info.as_ref().map(|info| info.device))
+            .push_bind(event.is_bot);
+    });
+
+    let result = builder.build().execute(m_conn).await?;
+    Ok(result.rows_affected())
+}
+
+/// 按字符截断，避免超出字段长度
+fn truncate(value: &Option<String>, max_chars: usize) -> Option<String> {
+    value
+        .as_ref()
+        .map(|value| value.chars().take(max_chars).collect())
+}
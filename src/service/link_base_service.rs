@@ -1,72 +1,196 @@
+use std::collections::HashMap;
+
+use crate::idgen::YitIdHelper;
 use crate::pojo::link_history::LinkHistory;
+use crate::pojo::link_revision::LinkRevision;
 use crate::pojo::Pagination;
+use crate::service::audit_service::{self, AuditEntry, Auditor};
+use crate::types::enums::{AuditAction, DeactivateReason};
 
+/// 查询工作空间内的链接，其他工作空间的链接视为不存在
 pub async fn query_by_id(
     m_conn: &sqlx::PgPool,
+    workspace_id: i64,
     id: i64,
 ) -> Result<Option<LinkHistory>, crate::AppError> {
-    let history_res =
-        sqlx::query_as::<_, LinkHistory>("select * from link_history where id = $1 and active = true")
-            .bind(id)
-            .fetch_optional(m_conn)
-            .await?;
+    let history_res = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        select h.*, coalesce(s.click_count, 0) as click_count, s.last_clicked_at
+        from link_history h left join link_stats s on s.link_id = h.id
+        where h.id = $1 and h.workspace_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(workspace_id)
+    .fetch_optional(m_conn)
+    .await?;
     Ok(history_res)
 }
 
+/// 将升级前没有域名的存量链接设置为默认域名，返回(更新数量, 因与默认域名下已有短链冲突而未更新的数量)
+pub async fn assign_default_domain(
+    m_conn: &sqlx::PgPool,
+    default_domain: &str,
+) -> Result<(u64, i64), crate::AppError> {
+    let updated = sqlx::query(
+        r#"
+        update link_history h set domain = $1
+        where h.domain = '' and not exists (
+            select 1 from link_history o
+            where o.workspace_id = h.workspace_id and o.domain = $1 and o.link_hash = h.link_hash
+        )
+        "#,
+    )
+    .bind(default_domain)
+    .execute(m_conn)
+    .await?
+    .rows_affected();
+    let remaining: i64 = sqlx::query_scalar("select count(*) from link_history where domain = ''")
+        .fetch_one(m_conn)
+        .await?;
+    Ok((updated, remaining))
+}
+
+pub async fn query_by_domain_and_id(
+    m_conn: &sqlx::PgPool,
+    domain: &str,
+    id: i64,
+) -> Result<Option<LinkHistory>, crate::AppError> {
+    let history_res = sqlx::query_as::<_, LinkHistory>(
+        "select * from link_history where id = $1 and domain = $2 and active = true",
+    )
+    .bind(id)
+    .bind(domain)
+    .fetch_optional(m_conn)
+    .await?;
+    Ok(history_res)
+}
+
+/// 按地址哈希查询工作空间内同一域名下的链接，包括已失效的链接
 pub async fn query_by_link_hash(
     m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+    domain: &str,
     link_hash: &str,
 ) -> Result<Option<LinkHistory>, crate::AppError> {
     let history_res = sqlx::query_as::<_, LinkHistory>(
-        "select * from link_history where link_hash = $1 and active = true",
+        "select * from link_history where workspace_id = $1 and domain = $2 and link_hash = $3",
     )
+    .bind(workspace_id)
+    .bind(domain)
     .bind(link_hash)
     .fetch_optional(m_conn)
     .await?;
     Ok(history_res)
 }
 
+/// 保存新链接，并在同一事务中记录创建的审计日志；工作空间内同一域名下已有相同地址的链接时返回None
 pub async fn save(
     m_conn: &sqlx::PgPool,
-    link_history: LinkHistory,
-) -> Result<bool, crate::AppError> {
+    link_history: &LinkHistory,
+    auditor: &Auditor,
+) -> Result<Option<LinkHistory>, crate::AppError> {
     let insert_query = r#"
-    INSERT INTO link_history (id, origin_url, link_type, expire_date, active, link_hash)
-    VALUES ($1, $2, $3, $4, $5, $6)
+    INSERT INTO link_history (id, workspace_id, owner_id, owner_key_id, domain, origin_url, link_type, expire_date, active, link_hash, idle_ttl, require_signature, idle_deadline)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW() + make_interval(secs => $11))
+    ON CONFLICT (workspace_id, domain, link_hash) DO NOTHING
+    RETURNING *
     "#;
     let mut tx = m_conn.begin().await?;
-    let result = sqlx::query(insert_query)
+    let created = sqlx::query_as::<_, LinkHistory>(insert_query)
         .bind(link_history.id)
-        .bind(link_history.origin_url)
+        .bind(link_history.workspace_id)
+        .bind(link_history.owner_id)
+        .bind(link_history.owner_key_id)
+        .bind(&link_history.domain)
+        .bind(&link_history.origin_url)
         .bind(link_history.link_type)
         .bind(link_history.expire_date)
         .bind(link_history.active)
-        .bind(link_history.link_hash)
-        .execute(&mut *tx)
-        .await;
+        .bind(&link_history.link_hash)
+        .bind(link_history.idle_ttl)
+        .bind(link_history.require_signature)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(created) = created else {
+        return Ok(None);
+    };
+    audit_service::record_link_change(&mut tx, auditor, AuditAction::LinkCreate, None, Some(&created)).await?;
+    tx.commit().await?;
+    Ok(Some(created))
+}
 
-    match result {
-        Ok(res) => {
-            tx.commit().await?;
-            Ok(res.rows_affected() > 0)
-        }
-        Err(_) => {
-            tx.rollback().await?;
-            Ok(false)
-        }
-    }
+/// 重新激活过期或空闲失效的链接，按新建链接重置过期时间、空闲有效期，并记录创建的审计日志
+///
+/// 因滥用举报下架或已被其他请求恢复的链接不会被修改，返回None
+pub async fn reactivate(
+    m_conn: &sqlx::PgPool,
+    id: i64,
+    idle_ttl: Option<i64>,
+    require_signature: bool,
+    auditor: &Auditor,
+) -> Result<Option<LinkHistory>, crate::AppError> {
+    let mut tx = m_conn.begin().await?;
+    let before = lock_by_id(&mut tx, id).await?;
+    let after = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        UPDATE link_history
+        SET active = true,
+            expire_date = NULL,
+            deactivated_reason = NULL,
+            deactivated_at = NULL,
+            idle_ttl = $2,
+            idle_deadline = NOW() + make_interval(secs => $2),
+            require_signature = require_signature OR $3
+        WHERE id = $1 AND active = false AND quarantined = false
+          AND deactivated_reason IS DISTINCT FROM $4
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(idle_ttl)
+    .bind(require_signature)
+    .bind(DeactivateReason::Abuse.as_str())
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(after) = after else {
+        return Ok(None);
+    };
+    audit_service::record_link_change(&mut tx, auditor, AuditAction::LinkCreate, before.as_ref(), Some(&after))
+        .await?;
+    tx.commit().await?;
+    Ok(Some(after))
+}
+
+/// 在事务中锁定并读取链接，作为审计日志中操作前的值
+pub async fn lock_by_id(
+    conn: &mut sqlx::PgConnection,
+    id: i64,
+) -> Result<Option<LinkHistory>, crate::AppError> {
+    let link = sqlx::query_as::<_, LinkHistory>("select * from link_history where id = $1 for update")
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+    Ok(link)
 }
 
 pub async fn query_all_with_pagination(
     m_conn: &sqlx::PgPool,
+    workspace_id: i64,
     pagination: &Pagination,
 ) -> Result<Vec<LinkHistory>, crate::AppError> {
     let offset = (pagination.page - 1) * pagination.page_size;
     let limit = pagination.page_size;
 
     let history_res = sqlx::query_as::<_, LinkHistory>(
-        "SELECT * FROM link_history ORDER BY create_time DESC LIMIT $1 OFFSET $2"
+        r#"
+        SELECT h.*, COALESCE(s.click_count, 0) AS click_count, s.last_clicked_at
+        FROM link_history h LEFT JOIN link_stats s ON s.link_id = h.id
+        WHERE h.workspace_id = $1
+        ORDER BY h.create_time DESC LIMIT $2 OFFSET $3
+        "#
     )
+    .bind(workspace_id)
     .bind(limit as i64)
     .bind(offset as i64)
     .fetch_all(m_conn)
@@ -75,8 +199,9 @@ pub async fn query_all_with_pagination(
     Ok(history_res)
 }
 
-pub async fn count_total_links(m_conn: &sqlx::PgPool) -> Result<i64, crate::AppError> {
-    let count: Option<i64> = sqlx::query_scalar("SELECT COUNT(*) FROM link_history WHERE active = true")
+pub async fn count_total_links(m_conn: &sqlx::PgPool, workspace_id: i64) -> Result<i64, crate::AppError> {
+    let count: Option<i64> = sqlx::query_scalar("SELECT COUNT(*) FROM link_history WHERE workspace_id = $1 AND active = true")
+        .bind(workspace_id)
         .fetch_one(m_conn)
         .await?;
 
@@ -85,7 +210,12 @@ pub async fn count_total_links(m_conn: &sqlx::PgPool) -> Result<i64, crate::AppE
 
 pub async fn query_expired_links(m_conn: &sqlx::PgPool) -> Result<Vec<LinkHistory>, crate::AppError> {
     let history_res = sqlx::query_as::<_, LinkHistory>(
-        "SELECT * FROM link_history WHERE active = true AND expire_date IS NOT NULL AND expire_date < NOW()"
+        r#"
+        SELECT * FROM link_history
+        WHERE active = true
+          AND ((expire_date IS NOT NULL AND expire_date < NOW())
+            OR (idle_deadline IS NOT NULL AND idle_deadline < NOW()))
+        "#
     )
     .fetch_all(m_conn)
     .await?;
@@ -93,23 +223,232 @@ pub async fn query_expired_links(m_conn: &sqlx::PgPool) -> Result<Vec<LinkHistor
     Ok(history_res)
 }
 
-pub async fn mark_links_as_inactive(m_conn: &sqlx::PgPool, ids: &[i64]) -> Result<u64, crate::AppError> {
+pub async fn mark_links_as_inactive(
+    m_conn: &sqlx::PgPool,
+    ids: &[i64],
+    auditor: &Auditor,
+) -> Result<u64, crate::AppError> {
     if ids.is_empty() {
         return Ok(0);
     }
 
     let mut tx = m_conn.begin().await?;
-    
-    let update_query = "UPDATE link_history SET active = false, update_time = NOW() WHERE id = ANY($1)";
-    let result = sqlx::query(update_query)
+    let before = sqlx::query_as::<_, LinkHistory>(
+        "SELECT * FROM link_history WHERE id = ANY($1) AND active = true ORDER BY id FOR UPDATE",
+    )
+    .bind(ids)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // 同时满足时按过期处理
+    let update_query = r#"
+        UPDATE link_history
+        SET active = false,
+            deactivated_reason = CASE WHEN expire_date IS NOT NULL AND expire_date < NOW() THEN $2 ELSE $3 END,
+            deactivated_at = NOW(),
+            update_time = NOW()
+        WHERE id = ANY($1) AND active = true
+        RETURNING *
+    "#;
+    let after = sqlx::query_as::<_, LinkHistory>(update_query)
         .bind(ids)
-        .execute(&mut *tx)
-        .await;
+        .bind(DeactivateReason::Expired.as_str())
+        .bind(DeactivateReason::Idle.as_str())
+        .fetch_all(&mut *tx)
+        .await?;
+
+    // 审计日志写入失败时整批回滚，下次清理时重试
+    let after: HashMap<i64, LinkHistory> = after.into_iter().map(|link| (link.id, link)).collect();
+    let entries: Vec<AuditEntry> = before
+        .iter()
+        .filter_map(|link| audit_service::link_entry(Some(link), after.get(&link.id)))
+        .collect();
+    audit_service::record_batch(&mut tx, auditor, AuditAction::LinkDeactivate, &entries).await?;
+    tx.commit().await?;
+    Ok(after.len() as u64)
+}
+
+/// 保存一批访问记录：累加点击次数、更新最近访问时间，并顺延空闲过期时间
+pub async fn save_access_records(
+    m_conn: &sqlx::PgPool,
+    ids: &[i64],
+    timestamps: &[f64],
+    counts: &[i64],
+) -> Result<(), crate::AppError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = m_conn.begin().await?;
+
+    // 未设置空闲有效期的链接不受影响
+    sqlx::query(
+        r#"
+        UPDATE link_history AS h
+        SET idle_deadline = to_timestamp(t.ts) + make_interval(secs => h.idle_ttl)
+        FROM UNNEST($1::bigint[], $2::float8[]) AS t(id, ts)
+        WHERE h.id = t.id AND h.active = true AND h.idle_ttl IS NOT NULL
+        "#,
+    )
+    .bind(ids)
+    .bind(timestamps)
+    .execute(&mut *tx)
+    .await?;
+
+    // 已被物理删除的链接直接忽略
+    sqlx::query(
+        r#"
+        INSERT INTO link_stats (link_id, click_count, last_clicked_at)
+        SELECT t.id, t.clicks, to_timestamp(t.ts)
+        FROM UNNEST($1::bigint[], $2::float8[], $3::bigint[]) AS t(id, ts, clicks)
+        WHERE EXISTS (SELECT 1 FROM link_history h WHERE h.id = t.id)
+        ON CONFLICT (link_id) DO UPDATE
+        SET click_count = link_stats.click_count + EXCLUDED.click_count,
+            last_clicked_at = GREATEST(link_stats.last_clicked_at, EXCLUDED.last_clicked_at)
+        "#,
+    )
+    .bind(ids)
+    .bind(timestamps)
+    .bind(counts)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// 更新链接的过期时间，同时顺延空闲过期时间
+///
+/// 失效的链接只有因过期或空闲失效时才重新激活，举报下架的链接不受影响，返回false
+pub async fn update_expire_date(
+    m_conn: &sqlx::PgPool,
+    id: i64,
+    expire_date: chrono::NaiveDateTime,
+    auditor: &Auditor,
+) -> Result<bool, crate::AppError> {
+    let mut tx = m_conn.begin().await?;
+    let before = lock_by_id(&mut tx, id).await?;
+    let after = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        UPDATE link_history
+        SET expire_date = $1,
+            active = true,
+            deactivated_reason = NULL,
+            deactivated_at = NULL,
+            idle_deadline = NOW() + make_interval(secs => idle_ttl)
+        WHERE id = $2 AND (active = true OR deactivated_reason = ANY($3))
+        RETURNING *
+        "#,
+    )
+    .bind(expire_date)
+    .bind(id)
+    .bind([DeactivateReason::Expired.as_str(), DeactivateReason::Idle.as_str()])
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(after) = after else {
+        return Ok(false);
+    };
+    audit_service::record_link_change(&mut tx, auditor, AuditAction::LinkExtend, before.as_ref(), Some(&after))
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// 查询失效时间超过保留天数的链接
+pub async fn query_purgeable_links(
+    m_conn: &sqlx::PgPool,
+    retention_days: u64,
+    limit: usize,
+) -> Result<Vec<LinkHistory>, crate::AppError> {
+    let history_res = sqlx::query_as::<_, LinkHistory>(
+        r#"
+        SELECT * FROM link_history
+        WHERE active = false AND COALESCE(deactivated_at, update_time, create_time) < NOW() - make_interval(days => $1)
+        ORDER BY deactivated_at
+        LIMIT $2
+        "#,
+    )
+    .bind(retention_days as i32)
+    .bind(limit as i64)
+    .fetch_all(m_conn)
+    .await?;
+
+    Ok(history_res)
+}
+
+/// 物理删除失效链接
+pub async fn delete_inactive_links(
+    m_conn: &sqlx::PgPool,
+    ids: &[i64],
+    auditor: &Auditor,
+) -> Result<u64, crate::AppError> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    // 审计日志记录删除前的完整数据，写入失败时整批回滚
+    let mut tx = m_conn.begin().await?;
+    let deleted = sqlx::query_as::<_, LinkHistory>(
+        "DELETE FROM link_history WHERE id = ANY($1) AND active = false RETURNING *",
+    )
+    .bind(ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    let entries: Vec<AuditEntry> = deleted
+        .iter()
+        .filter_map(|link| audit_service::link_entry(Some(link), None))
+        .collect();
+    audit_service::record_batch(&mut tx, auditor, AuditAction::LinkPurge, &entries).await?;
+    tx.commit().await?;
+    Ok(deleted.len() as u64)
+}
+
+/// 修改链接的目标地址，并在同一事务中记录修改前的地址和审计日志
+///
+/// 目标地址在同一工作空间和域名下已存在短链时返回false
+pub async fn update_origin_url(
+    m_conn: &sqlx::PgPool,
+    link_history: &LinkHistory,
+    origin_url: &str,
+    link_hash: &str,
+    auditor: &Auditor,
+    action: AuditAction,
+) -> Result<bool, crate::AppError> {
+    let mut tx = m_conn.begin().await?;
+    let before = match lock_by_id(&mut tx, link_history.id).await? {
+        Some(before) if before.active => before,
+        _ => return Err(crate::AppError::bad_request(anyhow::anyhow!("短链已失效"))),
+    };
+
+    sqlx::query(
+        "INSERT INTO link_history_revision (id, link_id, origin_url, actor) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(before.id)
+    .bind(&before.origin_url)
+    .bind(&auditor.actor)
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query_as::<_, LinkHistory>(
+        "UPDATE link_history SET origin_url = $1, link_hash = $2 WHERE id = $3 RETURNING *",
+    )
+    .bind(origin_url)
+    .bind(link_hash)
+    .bind(before.id)
+    .fetch_one(&mut *tx)
+    .await;
 
     match result {
-        Ok(res) => {
+        Ok(after) => {
+            audit_service::record_link_change(&mut tx, auditor, action, Some(&before), Some(&after))
+                .await?;
             tx.commit().await?;
-            Ok(res.rows_affected())
+            Ok(true)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            tx.rollback().await?;
+            Ok(false)
         }
         Err(e) => {
             tx.rollback().await?;
@@ -117,3 +456,33 @@ pub async fn mark_links_as_inactive(m_conn: &sqlx::PgPool, ids: &[i64]) -> Resul
         }
     }
 }
+
+pub async fn query_revisions(
+    m_conn: &sqlx::PgPool,
+    link_id: i64,
+) -> Result<Vec<LinkRevision>, crate::AppError> {
+    let revisions = sqlx::query_as::<_, LinkRevision>(
+        "SELECT * FROM link_history_revision WHERE link_id = $1 ORDER BY create_time DESC, id DESC",
+    )
+    .bind(link_id)
+    .fetch_all(m_conn)
+    .await?;
+
+    Ok(revisions)
+}
+
+pub async fn query_revision(
+    m_conn: &sqlx::PgPool,
+    link_id: i64,
+    revision_id: i64,
+) -> Result<Option<LinkRevision>, crate::AppError> {
+    let revision = sqlx::query_as::<_, LinkRevision>(
+        "SELECT * FROM link_history_revision WHERE id = $1 AND link_id = $2",
+    )
+    .bind(revision_id)
+    .bind(link_id)
+    .fetch_optional(m_conn)
+    .await?;
+
+    Ok(revision)
+}
@@ -1,18 +1,35 @@
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 
+use axum::http::StatusCode;
 use bb8::PooledConnection;
 use bb8_redis::{
-    redis::{cmd, AsyncCommands},
+    redis::{cmd, pipe, AsyncCommands},
     RedisConnectionManager,
 };
+use chrono::{NaiveDateTime, Utc};
 use tokio::join;
+use url::Host;
 
+use crate::config::Link;
 use crate::idgen::YitIdHelper;
-use crate::link_base_service::{query_by_id, query_by_link_hash, save, query_all_with_pagination, count_total_links};
+use crate::link_base_service::{query_by_domain_and_id, query_by_link_hash, save, query_all_with_pagination, count_total_links};
+use crate::link_base_service::{query_by_id, query_revision, query_revisions, update_origin_url};
+use crate::link_base_service::{reactivate, update_expire_date};
+use crate::pojo::api_key::ApiActor;
 use crate::pojo::link_history::{LinkHistory, LinkHistoryResponse, LinkListResponse};
+use crate::pojo::link_revision::LinkRevisionResponse;
+use crate::pojo::webhook::LinkUpdatedPayload;
 use crate::pojo::{AppError, Pagination};
+use crate::service::abuse_service::QUARANTINED_LINKS_KEY;
+use crate::service::audit_service::Auditor;
+use crate::service::{signed_link_service, url_rule_service, webhook_service};
+use crate::types::enums::{AuditAction, DeactivateReason, WebhookEvent};
 use crate::types::{HandlerResult, IState};
-use crate::utils::helper::{calculate_sha256, decode_base62, encode_base62};
+use crate::utils::helper::{
+    calculate_sha256, decode_base62, encode_base62, is_public_ip, strip_port,
+};
 
 const LINK_HASH_KEY: &'static str = "link:hash:";
 const LINK_ID_KEY: &'static str = "link:origin:uri:";
@@ -21,115 +38,375 @@ const LINK_ID_KEY: &'static str = "link:origin:uri:";
 const CACHE_TTL_SECONDS: i64 = 3600; // URL缓存1小时过期
 const HASH_CACHE_TTL_SECONDS: i64 = 86400; // 哈希缓存24小时过期
 
+/// 校验目标地址时解析DNS的超时时间
+const DNS_LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 哈希缓存key：link:hash:{workspace}:{domain}:{hash}，同一地址在不同工作空间下各自去重
+pub fn hash_cache_key(workspace_id: i64, domain: &str, link_hash: &str) -> String {
+    format!("{}{}:{}:{}", LINK_HASH_KEY, workspace_id, domain, link_hash)
+}
+
+/// URL缓存key：link:origin:uri:{domain}:{id}
+pub fn origin_cache_key(domain: &str, id: i64) -> String {
+    format!("{}{}:{}", LINK_ID_KEY, domain, id)
+}
+
+/// URL缓存的值：{workspace}|{是否需要签名}|{url}，跳转时不必再查询链接所属的工作空间和签名要求
+fn origin_cache_value(workspace_id: i64, require_signature: bool, origin_url: &str) -> String {
+    format!("{}|{}|{}", workspace_id, u8::from(require_signature), origin_url)
+}
+
+/// 解析URL缓存的值，旧格式的值返回None，按未命中处理
+fn parse_origin_cache_value(value: &str) -> Option<(i64, bool, String)> {
+    let mut parts = value.splitn(3, '|');
+    let workspace_id = parts.next()?.parse().ok()?;
+    let require_signature = match parts.next()? {
+        "0" => false,
+        "1" => true,
+        _ => return None,
+    };
+    Some((workspace_id, require_signature, parts.next()?.to_string()))
+}
+
+/// 缓存过期时间不超过链接本身的过期时间
+fn cache_ttl(default_ttl: i64, expire_date: Option<NaiveDateTime>) -> i64 {
+    match expire_date {
+        None => default_ttl,
+        Some(expire_date) => {
+            let remaining = (expire_date - Utc::now().naive_utc()).num_seconds();
+            remaining.clamp(1, default_ttl)
+        }
+    }
+}
+
+/// 创建短链时可选的链接属性
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LinkOptions {
+    /// 空闲有效期（秒），超过该时间未被访问的链接会被清理
+    pub idle_ttl: Option<u64>,
+    /// 只能通过签名链接访问
+    pub require_signature: bool,
+}
+
+/// 创建短链，返回短码以及是否为新创建的链接，工作空间内已存在相同地址时返回已有的短链
+///
+/// 指定需要签名时，已有的短链同样开启签名校验，需要有修改该短链的权限
 pub async fn create_link(
     pool: Arc<IState>,
+    actor: &ApiActor,
+    auditor: &Auditor,
     link: String,
+    domain: Option<String>,
     _duration: Option<u64>,
-) -> HandlerResult<String> {
+    options: LinkOptions,
+) -> HandlerResult<(String, bool)> {
+    if options.require_signature {
+        signed_link_service::require_secret(&pool)?;
+    }
+    validate_destination(&pool.link_config, &link).await?;
+    url_rule_service::check_url(&pool, &link)?;
+    let domain = resolve_create_domain(&pool.link_config, domain)?;
     let db_pool = &pool.db_pool;
     let redis_pool = &pool.redis_pool;
     let redis_db = pool.redis_db.unwrap_or(0);
 
     let mut r_con = redis_pool.get().await?;
     cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
-    let id = query_and_create(&mut r_con, db_pool, link).await?;
-    Ok(encode_base62(id as usize))
+    let (id, created) =
+        query_and_create(&mut r_con, db_pool, actor, auditor, &domain, link, options).await?;
+    let code = encode_base62(id as usize);
+    let is_new = created.is_some();
+    if let Some(created) = created {
+        webhook_service::dispatch(&pool, actor.workspace_id, WebhookEvent::LinkCreated, &[created]);
+    } else if options.require_signature {
+        let link = query_link_by_code(&pool, actor.workspace_id, &code).await?;
+        actor.require_editable(link.owner())?;
+        signed_link_service::require_signature(&pool, auditor, &link).await?;
+    }
+    Ok((code, is_new))
+}
+
+/// 跳转目标
+pub struct RedirectTarget {
+    pub url: String,
+    /// 链接所属的工作空间，实时点击流和Webhook按工作空间分发
+    pub workspace_id: i64,
+    /// 被隔离的链接跳转前显示风险提示页
+    pub quarantined: bool,
+    /// 只能通过签名链接访问
+    pub require_signature: bool,
 }
 
-pub async fn query_origin_url(pool: Arc<IState>, link_hash: String) -> Result<String, AppError> {
-    let id = decode_base62(&link_hash)?;
+pub async fn query_origin_url(
+    pool: Arc<IState>,
+    host: Option<String>,
+    link_hash: String,
+) -> Result<RedirectTarget, AppError> {
+    let id = decode_base62(&link_hash).map_err(AppError::not_found)?;
+    let domain = resolve_host_domain(&pool.link_config, host.as_deref());
     let db_pool = &pool.db_pool;
     let redis_pool = &pool.redis_pool;
     let redis_db = pool.redis_db.unwrap_or(0);
     let mut r_con = redis_pool.get().await?;
     cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
 
-    let link_id_key = format!("{}{}", LINK_ID_KEY, id);
-    let data: Option<String> = r_con.get(&link_id_key).await?;
-    if let Some(url) = data {
-        return Ok(url);
+    let link_id_key = origin_cache_key(&domain, id as i64);
+    let (data, quarantined): (Option<String>, bool) = pipe()
+        .get(&link_id_key)
+        .sismember(QUARANTINED_LINKS_KEY, id as i64)
+        .query_async(&mut *r_con)
+        .await?;
+    if let Some((workspace_id, require_signature, url)) =
+        data.as_deref().and_then(parse_origin_cache_value)
+    {
+        return Ok(RedirectTarget { url, workspace_id, quarantined, require_signature });
     }
-    match query_by_id(db_pool, id as i64).await? {
-        None => Err(AppError::from(anyhow::anyhow!("invalid short link"))),
+    match query_by_domain_and_id(db_pool, &domain, id as i64).await? {
+        None => Err(AppError::not_found(anyhow::anyhow!("invalid short link"))),
         Some(history) => {
-            let url = history.origin_url.clone();
-            let set_result: bool = r_con.set_nx(&link_id_key, &url).await.unwrap_or(false);
-            if set_result {
-                let expire_result: () = r_con.expire(&link_id_key, CACHE_TTL_SECONDS).await.unwrap_or(());
-                let _ = expire_result;
+            let value =
+                origin_cache_value(history.workspace_id, history.require_signature, &history.origin_url);
+            let ttl = cache_ttl(CACHE_TTL_SECONDS, history.expire_date);
+            if data.is_some() {
+                // 旧格式的缓存直接覆盖
+                let _: () = r_con.set_ex(&link_id_key, &value, ttl as u64).await.unwrap_or(());
+            } else {
+                let set_result: bool = r_con.set_nx(&link_id_key, &value).await.unwrap_or(false);
+                if set_result {
+                    let expire_result: () = r_con.expire(&link_id_key, ttl).await.unwrap_or(());
+                    let _ = expire_result;
+                }
+            }
+            // Redis中的隔离集合丢失时以数据库为准重新写入
+            if history.quarantined && !quarantined {
+                let _: () = r_con.sadd(QUARANTINED_LINKS_KEY, history.id).await.unwrap_or(());
+            }
+            Ok(RedirectTarget {
+                url: history.origin_url,
+                workspace_id: history.workspace_id,
+                quarantined: history.quarantined,
+                require_signature: history.require_signature,
+            })
+        }
+    }
+}
+
+/// 按请求的Host和短码查询有效的链接，不限工作空间
+pub async fn query_active_link(
+    pool: &IState,
+    host: Option<String>,
+    link_code: &str,
+) -> Result<LinkHistory, AppError> {
+    let id = decode_base62(link_code).map_err(AppError::not_found)?;
+    let domain = resolve_host_domain(&pool.link_config, host.as_deref());
+    query_by_domain_and_id(&pool.db_pool, &domain, id as i64)
+        .await?
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("invalid short link")))
+}
+
+/// 解析创建短链时使用的域名，未指定时使用默认域名
+fn resolve_create_domain(link_config: &Link, domain: Option<String>) -> Result<String, AppError> {
+    match domain {
+        None => Ok(link_config.default_domain()),
+        Some(domain) => {
+            let domain = domain.trim().to_lowercase();
+            if link_config.is_allowed_domain(&domain) {
+                Ok(domain)
+            } else {
+                Err(AppError::bad_request(anyhow::anyhow!("不支持的域名: {}", domain)))
+            }
+        }
+    }
+}
+
+/// 校验目标地址：协议在允许列表中，不指向本服务的短链域名，也不指向内网和本机地址
+///
+/// 域名会先解析DNS，任一解析结果为内网地址即拒绝；解析失败或超时同样拒绝，无法确认目标不是内网地址
+async fn validate_destination(link_config: &Link, url: &str) -> Result<(), AppError> {
+    let target = check_destination(link_config, url)
+        .map_err(|reason| AppError::bad_request(anyhow::anyhow!(reason)))?;
+    if let Some((host, port)) = target {
+        lookup_public_host(&host, port)
+            .await
+            .map_err(|reason| AppError::bad_request(anyhow::anyhow!(reason)))?;
+    }
+    Ok(())
+}
+
+/// 解析域名，全部解析结果都是公网地址时返回，解析失败或超时同样拒绝
+pub async fn lookup_public_host(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let lookup = tokio::net::lookup_host((host, port));
+    match tokio::time::timeout(DNS_LOOKUP_TIMEOUT, lookup).await {
+        Ok(Ok(addrs)) => {
+            let addrs: Vec<SocketAddr> = addrs.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+                return Err(format!("目标域名 {} 解析到内网或本机地址 {}", host, addr.ip()));
             }
-            Ok(history.origin_url)
+            Ok(addrs)
         }
+        Ok(Err(e)) => {
+            tracing::debug!("解析目标域名 {} 失败: {}", host, e);
+            Err(format!("无法解析目标域名: {}", host))
+        }
+        Err(_) => {
+            tracing::debug!("解析目标域名 {} 超时", host);
+            Err(format!("解析目标域名超时: {}", host))
+        }
+    }
+}
+
+/// 不需要DNS的校验，返回需要解析的域名和端口，IP地址或允许内网地址时返回None
+fn check_destination(link_config: &Link, url: &str) -> Result<Option<(String, u16)>, String> {
+    let parsed = url::Url::parse(url).map_err(|_| "无效的目标地址".to_string())?;
+    if !link_config.is_allowed_scheme(parsed.scheme()) {
+        return Err(format!("不支持的协议: {}", parsed.scheme()));
     }
+    match parsed.host() {
+        None => return Err("目标地址缺少主机".to_string()),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            if link_config.is_allowed_domain(&domain) {
+                return Err(format!("目标地址不能指向短链域名 {}，会造成循环跳转", domain));
+            }
+        }
+        Some(_) => {}
+    }
+    if link_config.allow_private_destinations.unwrap_or(false) {
+        return Ok(None);
+    }
+    check_public_host(&parsed)
 }
 
+/// 校验地址的主机不是内网或本机地址，返回需要解析的域名和端口，主机为IP地址时返回None
+pub fn check_public_host(parsed: &url::Url) -> Result<Option<(String, u16)>, String> {
+    let ip = match parsed.host() {
+        None => return Err("目标地址缺少主机".to_string()),
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            if domain == "localhost" || domain.ends_with(".localhost") {
+                return Err(format!("目标地址不能指向本机: {}", domain));
+            }
+            let port = parsed.port_or_known_default().unwrap_or(80);
+            return Ok(Some((domain, port)));
+        }
+    };
+    if !is_public_ip(ip) {
+        return Err(format!("目标地址不能指向内网或本机地址: {}", ip));
+    }
+    Ok(None)
+}
+
+/// 根据请求的Host解析短链域名，未配置的域名回落到默认域名
+fn resolve_host_domain(link_config: &Link, host: Option<&str>) -> String {
+    match host.map(strip_port) {
+        Some(domain) if link_config.is_allowed_domain(&domain) => domain,
+        _ => link_config.default_domain(),
+    }
+}
+
+/// 查询或创建短链，新创建时同时返回新链接的数据
+///
+/// 按工作空间去重，同一地址在不同工作空间下生成不同的短链；工作空间内已存在时不改变创建人
 async fn query_and_create<'a>(
     r_con: &mut PooledConnection<'a, RedisConnectionManager>,
     m_conn: &sqlx::PgPool,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    domain: &str,
     origin_link: String,
-) -> Result<u64, AppError> {
+    options: LinkOptions,
+) -> Result<(u64, Option<LinkHistoryResponse>), AppError> {
+    let workspace_id = actor.workspace_id;
     let link_hash = calculate_sha256(&origin_link);
-    let key = format!("{}{}", LINK_HASH_KEY, link_hash);
+    let key = hash_cache_key(workspace_id, domain, &link_hash);
+    let idle_ttl = options.idle_ttl.map(|ttl| ttl as i64);
 
     let (cached_id, db_result) = join!(
         async {
             let data: Option<String> = r_con.get(&key).await.ok();
             data.and_then(|s| s.parse().ok())
         },
-        async {
-            query_by_link_hash(m_conn, &link_hash).await.ok()
-        }
+        query_by_link_hash(m_conn, workspace_id, domain, &link_hash)
     );
 
     if let Some(id) = cached_id {
-        return Ok(id);
+        return Ok((id, None));
     }
 
-    match db_result.flatten() {
+    let history = match db_result? {
+        Some(history) => history,
         None => {
             let id = YitIdHelper::next_id();
-            let db = LinkHistory::from_url(id, &origin_link, link_hash);
-            assert!(save(m_conn, db).await?, "生成短链失败");
-            if let Err(err) = set_cache(r_con, key, id, origin_link).await {
-                tracing::error!("设置缓存失败: {}", err);
+            let mut db =
+                LinkHistory::from_url(id, workspace_id, actor.owner(), domain, &origin_link, link_hash.clone(), idle_ttl);
+            db.require_signature = options.require_signature;
+            match save(m_conn, &db, auditor).await? {
+                Some(created) => return created_link(r_con, created).await,
+                // 并发请求已创建相同地址的链接
+                None => query_by_link_hash(m_conn, workspace_id, domain, &link_hash)
+                    .await?
+                    .ok_or_else(|| AppError::new(StatusCode::CONFLICT, anyhow::anyhow!("生成短链失败，请重试")))?,
             }
-            Ok(id as u64)
         }
-        Some(history) => {
-            let id = history.id;
-            if let Err(err) = set_cache(r_con, key, id, origin_link).await {
-                tracing::error!("设置缓存失败: {}", err);
-            }
-            Ok(id as u64)
+    };
+    if history.active {
+        if let Err(err) = set_cache(r_con, &history).await {
+            tracing::error!("设置缓存失败: {}", err);
         }
+        return Ok((history.id as u64, None));
+    }
+
+    // 唯一索引包含已失效的链接，同一地址不能重新生成短链：下架的链接拒绝，过期和空闲失效的链接重新激活
+    if history.quarantined || history.deactivated_reason.as_deref() == Some(DeactivateReason::Abuse.as_str()) {
+        return Err(AppError::bad_request(anyhow::anyhow!("该地址的短链已因滥用举报下架")));
+    }
+    match reactivate(m_conn, history.id, idle_ttl, options.require_signature, auditor).await? {
+        Some(reactivated) => created_link(r_con, reactivated).await,
+        None => Err(AppError::new(StatusCode::CONFLICT, anyhow::anyhow!("短链状态已变更，请重试"))),
     }
 }
 
+/// 新建或重新激活的链接写入缓存，同时返回链接数据用于派发创建事件
+async fn created_link<'a>(
+    r_con: &mut PooledConnection<'a, RedisConnectionManager>,
+    link: LinkHistory,
+) -> Result<(u64, Option<LinkHistoryResponse>), AppError> {
+    if let Err(err) = set_cache(r_con, &link).await {
+        tracing::error!("设置缓存失败: {}", err);
+    }
+    Ok((link.id as u64, Some(link.to_response())))
+}
+
 async fn set_cache<'a>(
     r_con: &mut PooledConnection<'a, RedisConnectionManager>,
-    key: String,
-    id: i64,
-    origin_link: String,
+    link: &LinkHistory,
 ) -> Result<(), anyhow::Error> {
+    let expire_date = link.expire_date;
+
     // 设置哈希缓存
-    let _: () = r_con.set(&key, id).await?;
-    let _: () = r_con.expire(&key, HASH_CACHE_TTL_SECONDS).await?;
+    let key = hash_cache_key(link.workspace_id, &link.domain, &link.link_hash);
+    let _: () = r_con.set(&key, link.id).await?;
+    let _: () = r_con.expire(&key, cache_ttl(HASH_CACHE_TTL_SECONDS, expire_date)).await?;
 
     // 设置URL缓存
-    let url_key = format!("{}{}", LINK_ID_KEY, id);
-    let _: () = r_con.set(&url_key, &origin_link).await?;
-    let _: () = r_con.expire(&url_key, CACHE_TTL_SECONDS).await?;
+    let url_key = origin_cache_key(&link.domain, link.id);
+    let value = origin_cache_value(link.workspace_id, link.require_signature, &link.origin_url);
+    let _: () = r_con.set(&url_key, value).await?;
+    let _: () = r_con.expire(&url_key, cache_ttl(CACHE_TTL_SECONDS, expire_date)).await?;
 
     Ok(())
 }
 
 pub async fn get_link_list(
     pool: Arc<IState>,
+    workspace_id: i64,
     pagination: Pagination,
 ) -> HandlerResult<LinkListResponse> {
     let db_pool = &pool.db_pool;
 
-    let total = count_total_links(db_pool).await?;
+    let total = count_total_links(db_pool, workspace_id).await?;
 
     if total == 0 {
         return Ok(LinkListResponse {
@@ -141,7 +418,7 @@ pub async fn get_link_list(
         });
     }
 
-    let links = query_all_with_pagination(db_pool, &pagination).await?;
+    let links = query_all_with_pagination(db_pool, workspace_id, &pagination).await?;
     let response_links: Vec<LinkHistoryResponse> = links.into_iter().map(|link| link.to_response()).collect();
 
     let total_pages = ((total as f64) / (pagination.page_size as f64)).ceil() as usize;
@@ -155,3 +432,383 @@ pub async fn get_link_list(
         last_page,
     })
 }
+
+/// 修改短链的目标地址，修改前的地址会记录到修改历史中
+pub async fn update_link(
+    pool: Arc<IState>,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    link_code: String,
+    origin_url: String,
+) -> HandlerResult<()> {
+    let link = query_link_by_code(&pool, actor.workspace_id, &link_code).await?;
+    actor.require_editable(link.owner())?;
+    change_origin_url(&pool, link, origin_url, auditor, AuditAction::LinkUpdate).await
+}
+
+/// 查询短链目标地址的修改历史（按时间倒序）
+pub async fn get_link_revisions(
+    pool: Arc<IState>,
+    workspace_id: i64,
+    link_code: String,
+) -> HandlerResult<Vec<LinkRevisionResponse>> {
+    let link = query_link_by_code(&pool, workspace_id, &link_code).await?;
+    let revisions = query_revisions(&pool.db_pool, link.id).await?;
+    Ok(revisions.iter().map(|revision| revision.to_response()).collect())
+}
+
+/// 将短链的目标地址回滚到指定修改记录中的地址
+pub async fn rollback_link(
+    pool: Arc<IState>,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    link_code: String,
+    revision_id: i64,
+) -> HandlerResult<()> {
+    let link = query_link_by_code(&pool, actor.workspace_id, &link_code).await?;
+    actor.require_editable(link.owner())?;
+    let revision = query_revision(&pool.db_pool, link.id, revision_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("修改记录不存在")))?;
+    change_origin_url(&pool, link, revision.origin_url, auditor, AuditAction::LinkRollback).await
+}
+
+/// 延长短链的有效期，可指定新的过期时间（毫秒时间戳）或在当前过期时间基础上顺延的秒数
+///
+/// 已被清理任务置为失效的链接，在失效后的宽限期内可以重新激活
+pub async fn extend_link(
+    pool: Arc<IState>,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    link_code: String,
+    expire_date: Option<i64>,
+    duration: Option<u64>,
+) -> HandlerResult<LinkHistoryResponse> {
+    let workspace_id = actor.workspace_id;
+    let link = query_link_by_code(&pool, workspace_id, &link_code).await?;
+    actor.require_editable(link.owner())?;
+    if link.quarantined && !link.active {
+        return Err(AppError::bad_request(anyhow::anyhow!("短链已因滥用举报下架")));
+    }
+    let now = Utc::now().naive_utc();
+
+    let new_expire_date = match (expire_date, duration) {
+        (Some(millis), None) => chrono::DateTime::from_timestamp_millis(millis)
+            .map(|dt| dt.naive_utc())
+            .ok_or_else(|| AppError::bad_request(anyhow::anyhow!("无效的过期时间")))?,
+        (None, Some(secs)) => {
+            let base = link.expire_date.filter(|date| *date > now).unwrap_or(now);
+            base + chrono::Duration::seconds(secs as i64)
+        }
+        _ => {
+            return Err(AppError::bad_request(anyhow::anyhow!(
+                "expire_date 和 duration 必须且只能指定一个"
+            )));
+        }
+    };
+    if new_expire_date <= now {
+        return Err(AppError::bad_request(anyhow::anyhow!("过期时间必须晚于当前时间")));
+    }
+
+    let max_lifetime = pool.link_config.max_lifetime_secs.unwrap_or(31536000) as i64;
+    let create_time = link.create_time.unwrap_or(now);
+    if (new_expire_date - create_time).num_seconds() > max_lifetime {
+        return Err(AppError::bad_request(anyhow::anyhow!(
+            "超过链接最大有效期 {} 秒",
+            max_lifetime
+        )));
+    }
+
+    if !link.active {
+        if !DeactivateReason::is_reactivatable(link.deactivated_reason.as_deref()) {
+            return Err(AppError::bad_request(anyhow::anyhow!("链接已被下架，无法恢复")));
+        }
+        let grace = pool.link_config.reactivate_grace_secs.unwrap_or(604800) as i64;
+        let inactive_since = link.deactivated_at.or(link.update_time).unwrap_or(create_time);
+        if (now - inactive_since).num_seconds() > grace {
+            return Err(AppError::bad_request(anyhow::anyhow!("链接失效已超过 {} 秒，无法恢复", grace)));
+        }
+    }
+
+    if !update_expire_date(&pool.db_pool, link.id, new_expire_date, auditor).await? {
+        return Err(AppError::not_found(anyhow::anyhow!("invalid short link")));
+    }
+
+    let link = query_link_by_code(&pool, workspace_id, &link_code).await?;
+    if let Err(err) = refresh_cache(&pool, &link).await {
+        tracing::error!("重置缓存失败: {}", err);
+    }
+    let payload = LinkUpdatedPayload {
+        link: link.to_response(),
+        previous_url: None,
+        actor: Some(auditor.actor.clone()),
+    };
+    webhook_service::dispatch(&pool, workspace_id, WebhookEvent::LinkUpdated, &[payload]);
+    Ok(link.to_response())
+}
+
+/// 按链接最新的过期时间重写缓存
+async fn refresh_cache(pool: &IState, link: &LinkHistory) -> Result<(), anyhow::Error> {
+    let redis_db = pool.redis_db.unwrap_or(0);
+    let mut r_con = pool.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    set_cache(&mut r_con, link).await
+}
+
+/// 按短码查询工作空间内的链接，其他工作空间的短码返回无效
+pub async fn query_link_by_code(
+    pool: &IState,
+    workspace_id: i64,
+    link_code: &str,
+) -> Result<LinkHistory, AppError> {
+    let id = decode_base62(link_code).map_err(AppError::not_found)?;
+    query_by_id(&pool.db_pool, workspace_id, id as i64)
+        .await?
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("invalid short link")))
+}
+
+async fn change_origin_url(
+    pool: &Arc<IState>,
+    mut link: LinkHistory,
+    origin_url: String,
+    auditor: &Auditor,
+    action: AuditAction,
+) -> HandlerResult<()> {
+    if !link.active {
+        return Err(AppError::bad_request(anyhow::anyhow!("短链已失效")));
+    }
+    if link.origin_url == origin_url {
+        return Ok(());
+    }
+    validate_destination(&pool.link_config, &origin_url).await?;
+    url_rule_service::check_url(pool, &origin_url)?;
+
+    let link_hash = calculate_sha256(&origin_url);
+    if !update_origin_url(&pool.db_pool, &link, &origin_url, &link_hash, auditor, action).await? {
+        return Err(AppError::bad_request(anyhow::anyhow!("该工作空间的该域名下目标地址已存在短链")));
+    }
+
+    // 旧地址的缓存需要失效，新缓存在下次访问时重建
+    if let Err(err) = evict_cache(pool, &link).await {
+        tracing::error!("清除缓存失败: {}", err);
+    }
+
+    let previous_url = std::mem::replace(&mut link.origin_url, origin_url);
+    link.link_hash = link_hash;
+    let payload = LinkUpdatedPayload {
+        link: link.to_response(),
+        previous_url: Some(previous_url),
+        actor: Some(auditor.actor.clone()),
+    };
+    webhook_service::dispatch(pool, link.workspace_id, WebhookEvent::LinkUpdated, &[payload]);
+    Ok(())
+}
+
+pub async fn evict_cache(pool: &IState, link: &LinkHistory) -> Result<(), anyhow::Error> {
+    let redis_db = pool.redis_db.unwrap_or(0);
+    let mut r_con = pool.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    let keys = [
+        hash_cache_key(link.workspace_id, &link.domain, &link.link_hash),
+        origin_cache_key(&link.domain, link.id),
+    ];
+    let _: () = r_con.del(&keys).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use crate::types::enums::Role;
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
+    fn destination_check() {
+        let mut config = Link::default();
+        config.domains = Some(vec!["s.example.com".to_string()]);
+
+        let target = check_destination(&config, "https://example.org:8443/a").unwrap();
+        assert_eq!(target, Some(("example.org".to_string(), 8443)));
+        assert!(check_destination(&config, "javascript:alert(1)").is_err());
+        assert!(check_destination(&config, "data:text/html,hi").is_err());
+        assert!(check_destination(&config, "file:///etc/passwd").is_err());
+        assert!(check_destination(&config, "https://S.Example.com/s/abc").is_err());
+        assert!(check_destination(&config, "http://127.0.0.1:8008/s/abc").is_err());
+        assert!(check_destination(&config, "http://[::1]/").is_err());
+        assert!(check_destination(&config, "http://192.168.1.10/").is_err());
+        assert!(check_destination(&config, "http://app.localhost/").is_err());
+        assert_eq!(check_destination(&config, "http://93.184.216.34/").unwrap(), None);
+
+        config.allow_private_destinations = Some(true);
+        assert_eq!(check_destination(&config, "http://192.168.1.10/").unwrap(), None);
+        assert!(check_destination(&config, "https://s.example.com/s/abc").is_err());
+    }
+
+    #[tokio::test]
+    async fn unresolvable_destination_rejected() {
+        // .invalid 保留域名不会被解析
+        let err = validate_destination(&Link::default(), "https://short-link.invalid/").await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn rollback_to_revision() {
+        let Some(state) = test_support::state().await else { return };
+        let workspace_id = test_support::workspace_id();
+        let actor = test_support::actor(workspace_id, Role::Admin);
+        let auditor = test_support::auditor(&actor);
+        let link = test_support::insert_link(&state, workspace_id, "https://93.184.216.34/v1").await;
+        let code = encode_base62(link.id as usize);
+
+        for url in ["https://93.184.216.34/v2", "https://93.184.216.34/v3"] {
+            update_link(state.clone(), &actor, &auditor, code.clone(), url.to_string()).await.unwrap();
+        }
+        // 修改历史按时间倒序，保存的是修改前的地址
+        let revisions = get_link_revisions(state.clone(), workspace_id, code.clone()).await.unwrap();
+        let urls: Vec<&str> = revisions.iter().map(|r| r.origin_url.as_str()).collect();
+        assert_eq!(urls, ["https://93.184.216.34/v2", "https://93.184.216.34/v1"]);
+        assert!(revisions.iter().all(|r| r.actor == actor.name));
+
+        rollback_link(state.clone(), &actor, &auditor, code.clone(), revisions[1].id).await.unwrap();
+        let link = query_link_by_code(&state, workspace_id, &code).await.unwrap();
+        assert_eq!(link.origin_url, "https://93.184.216.34/v1");
+        assert_eq!(link.link_hash, calculate_sha256("https://93.184.216.34/v1"));
+        let revisions = get_link_revisions(state.clone(), workspace_id, code.clone()).await.unwrap();
+        assert_eq!(revisions.len(), 3);
+        assert_eq!(revisions[0].origin_url, "https://93.184.216.34/v3");
+
+        // 其他链接的修改记录不能用于回滚
+        let other = test_support::insert_link(&state, workspace_id, "https://93.184.216.34/other").await;
+        let other_code = encode_base62(other.id as usize);
+        let err = rollback_link(state.clone(), &actor, &auditor, other_code, revisions[0].id).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn query_link_scoped_to_workspace() {
+        let Some(state) = test_support::state().await else { return };
+        let workspace_id = test_support::workspace_id();
+        let other_workspace = test_support::workspace_id();
+        let link = test_support::insert_link(&state, workspace_id, "https://93.184.216.34/scoped").await;
+        let code = encode_base62(link.id as usize);
+
+        assert_eq!(query_link_by_code(&state, workspace_id, &code).await.unwrap().id, link.id);
+        let err = query_link_by_code(&state, other_workspace, &code).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn origin_cache_value_carries_workspace() {
+        let value = origin_cache_value(42, true, "https://93.184.216.34/a|b");
+        assert_eq!(parse_origin_cache_value(&value), Some((42, true, "https://93.184.216.34/a|b".to_string())));
+        let value = origin_cache_value(42, false, "https://93.184.216.34/a");
+        assert_eq!(parse_origin_cache_value(&value), Some((42, false, "https://93.184.216.34/a".to_string())));
+        // 旧格式没有签名要求或只有地址，按未命中处理
+        assert_eq!(parse_origin_cache_value("42|https://93.184.216.34/a|b"), None);
+        assert_eq!(parse_origin_cache_value("https://93.184.216.34/a"), None);
+    }
+
+    async fn deactivate(state: &IState, id: i64, reason: DeactivateReason, days: i32) {
+        sqlx::query(
+            r#"
+            UPDATE link_history
+            SET active = false, deactivated_reason = $2, deactivated_at = NOW() - make_interval(days => $3)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(reason.as_str())
+        .bind(days)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn extend_reactivates_expired_only() {
+        let Some(state) = test_support::state().await else { return };
+        let ws = test_support::workspace_id();
+        let admin = test_support::actor(ws, Role::Admin);
+        let auditor = test_support::auditor(&admin);
+        let idle = test_support::insert_link(&state, ws, "https://93.184.216.34/idle").await;
+        let abuse = test_support::insert_link(&state, ws, "https://93.184.216.34/abuse").await;
+        let stale = test_support::insert_link(&state, ws, "https://93.184.216.34/stale").await;
+        deactivate(&state, idle.id, DeactivateReason::Idle, 1).await;
+        deactivate(&state, abuse.id, DeactivateReason::Abuse, 1).await;
+        // 默认宽限期7天，最后修改时间是刚才，但失效时间已超过宽限期
+        deactivate(&state, stale.id, DeactivateReason::Expired, 8).await;
+
+        let code = |link: &LinkHistory| encode_base62(link.id as usize);
+        let extended = extend_link(state.clone(), &admin, &auditor, code(&idle), None, Some(3600)).await.unwrap();
+        assert!(extended.active);
+        assert!(extended.deactivated_reason.is_none());
+        assert!(extended.deactivated_at.is_none());
+
+        let err = extend_link(state.clone(), &admin, &auditor, code(&abuse), None, Some(3600)).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+        let err = extend_link(state.clone(), &admin, &auditor, code(&stale), None, Some(3600)).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        let abuse = query_link_by_code(&state, ws, &code(&abuse)).await.unwrap();
+        assert!(!abuse.active);
+        assert_eq!(abuse.deactivated_reason.as_deref(), Some("abuse"));
+    }
+
+    #[tokio::test]
+    async fn inactive_link_reactivated_unless_taken_down() {
+        let Some(state) = test_support::state().await else { return };
+        let ws = test_support::workspace_id();
+        let auditor = Auditor::system();
+        let idle = test_support::insert_link(&state, ws, "https://93.184.216.34/recreate/idle").await;
+        let abuse = test_support::insert_link(&state, ws, "https://93.184.216.34/recreate/abuse").await;
+        deactivate(&state, idle.id, DeactivateReason::Idle, 30).await;
+        deactivate(&state, abuse.id, DeactivateReason::Abuse, 1).await;
+
+        // 唯一索引包含已失效的链接，重复写入返回None而不是报错
+        let duplicate = LinkHistory::from_url(
+            YitIdHelper::next_id(),
+            ws,
+            idle.owner(),
+            &idle.domain,
+            &idle.origin_url,
+            idle.link_hash.clone(),
+            None,
+        );
+        assert!(save(&state.db_pool, &duplicate, &auditor).await.unwrap().is_none());
+        let found = query_by_link_hash(&state.db_pool, ws, &idle.domain, &idle.link_hash).await.unwrap().unwrap();
+        assert_eq!(found.id, idle.id);
+
+        let reactivated = reactivate(&state.db_pool, idle.id, Some(60), true, &auditor).await.unwrap().unwrap();
+        assert!(reactivated.active);
+        assert!(reactivated.deactivated_reason.is_none());
+        assert_eq!(reactivated.idle_ttl, Some(60));
+        assert!(reactivated.require_signature);
+        assert!(reactivate(&state.db_pool, idle.id, None, false, &auditor).await.unwrap().is_none());
+        assert!(reactivate(&state.db_pool, abuse.id, None, false, &auditor).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn create_taken_down_url_rejected() {
+        let Some(state) = test_support::redis_state().await else { return };
+        let ws = test_support::workspace_id();
+        let admin = test_support::actor(ws, Role::Admin);
+        let auditor = test_support::auditor(&admin);
+        let idle_url = "https://93.184.216.34/recreate/create-idle";
+        let abuse_url = "https://93.184.216.34/recreate/create-abuse";
+        let idle = test_support::insert_link(&state, ws, idle_url).await;
+        let abuse = test_support::insert_link(&state, ws, abuse_url).await;
+        deactivate(&state, idle.id, DeactivateReason::Idle, 1).await;
+        deactivate(&state, abuse.id, DeactivateReason::Abuse, 1).await;
+
+        let create = |url: &str| {
+            create_link(state.clone(), &admin, &auditor, url.to_string(), None, None, LinkOptions::default())
+        };
+        create(idle_url).await.unwrap();
+        assert!(query_link_by_code(&state, ws, &encode_base62(idle.id as usize)).await.unwrap().active);
+        let err = create(abuse_url).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    }
+}
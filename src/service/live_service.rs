@@ -0,0 +1,218 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::response::sse::Event;
+use bb8_redis::redis::pipe;
+use futures_util::{Stream, StreamExt};
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::pojo::click_event::LiveClick;
+use crate::pojo::AppError;
+use crate::types::IState;
+
+/// 实时点击流的Redis频道，所有实例都向该频道发布并订阅
+const LIVE_CHANNEL: &str = "link:click:live";
+/// 每个Pipeline最多发布的事件数
+const PUBLISH_BATCH_SIZE: usize = 500;
+/// 订阅连接断开后的重连间隔
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// 将点击事件放入发布队列，队列已满时直接丢弃，不阻塞重定向
+///
+/// 本实例的SSE连接同样经由订阅任务收到事件，避免重复推送
+pub fn publish(state: &IState, click: LiveClick) {
+    if let Err(TrySendError::Full(click)) = state.live_publish_tx.try_send(click) {
+        tracing::debug!("实时点击发布队列已满，丢弃短链 {} 的点击", click.link_code);
+    }
+}
+
+/// 实时点击发布任务，每次取出队列中已有的事件，通过一个Pipeline发布到Redis频道
+pub async fn live_publish_task(
+    state: Arc<IState>,
+    mut publish_rx: mpsc::Receiver<LiveClick>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) {
+    let mut buffer: Vec<LiveClick> = Vec::with_capacity(PUBLISH_BATCH_SIZE);
+    tracing::info!("实时点击流发布任务已启动");
+
+    loop {
+        select! {
+            received = publish_rx.recv_many(&mut buffer, PUBLISH_BATCH_SIZE) => {
+                if received == 0 {
+                    break;
+                }
+                if let Err(e) = publish_batch(&state, &buffer).await {
+                    tracing::warn!("发布 {} 条实时点击事件失败: {}", buffer.len(), e);
+                }
+                buffer.clear();
+            }
+            // 实时点击流只推送给在线的连接，关闭时丢弃队列中剩余的事件
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+    tracing::info!("实时点击流发布任务已停止");
+}
+
+async fn publish_batch(state: &IState, clicks: &[LiveClick]) -> Result<(), AppError> {
+    let mut publish = pipe();
+    for click in clicks {
+        match serde_json::to_string(click) {
+            Ok(payload) => {
+                publish.cmd("PUBLISH").arg(LIVE_CHANNEL).arg(payload).ignore();
+            }
+            Err(e) => tracing::warn!("序列化实时点击事件失败: {}", e),
+        }
+    }
+    let mut r_con = state.redis_pool.get().await?;
+    let _: () = publish.query_async(&mut *r_con).await?;
+    Ok(())
+}
+
+/// 订阅Redis频道并转发到本地广播的任务，连接断开后自动重连
+pub async fn live_subscribe_task(state: Arc<IState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    if !state.analytics_config.live_stream.unwrap_or(true) {
+        tracing::info!("实时点击流未启用");
+        return;
+    }
+
+    tracing::info!("实时点击流订阅任务已启动，频道: {}", LIVE_CHANNEL);
+
+    loop {
+        select! {
+            result = subscribe(&state) => {
+                match result {
+                    Ok(()) => tracing::warn!("实时点击流订阅连接已断开，稍后重连"),
+                    Err(e) => tracing::error!("实时点击流订阅失败: {}，稍后重连", e),
+                }
+            }
+            _ = shutdown_rx.recv() => break,
+        }
+        select! {
+            _ = tokio::time::sleep(RESUBSCRIBE_DELAY) => {}
+            _ = shutdown_rx.recv() => break,
+        }
+    }
+
+    tracing::info!("实时点击流订阅任务已停止");
+}
+
+async fn subscribe(state: &IState) -> Result<(), AppError> {
+    let mut pubsub = state.redis_client.get_async_pubsub().await?;
+    pubsub.subscribe(LIVE_CHANNEL).await?;
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = match msg.get_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("读取实时点击事件失败: {}", e);
+                continue;
+            }
+        };
+        match serde_json::from_str::<LiveClick>(&payload) {
+            // 没有SSE连接时发送失败，直接忽略
+            Ok(click) => {
+                let _ = state.live_tx.send(click);
+            }
+            Err(e) => tracing::warn!("解析实时点击事件失败: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// 为一个SSE连接订阅工作空间内的实时点击流，可按短码过滤，默认不包含机器人的点击
+///
+/// 订阅端处理过慢时会收到 `lagged` 事件，data为丢弃的事件数
+pub fn click_stream(
+    state: &IState,
+    workspace_id: i64,
+    link_code: Option<String>,
+    include_bots: bool,
+) -> impl Stream<Item = Result<Event, axum::Error>> + use<> {
+    let live_rx = state.live_tx.subscribe();
+    let shutdown_rx = state.stream_shutdown.subscribe();
+    futures_util::stream::unfold(
+        (live_rx, shutdown_rx),
+        move |(mut live_rx, mut shutdown_rx)| {
+            let link_code = link_code.clone();
+            async move {
+                loop {
+                    let received = select! {
+                        received = live_rx.recv() => received,
+                        _ = shutdown_rx.recv() => return None,
+                    };
+                    let event = match received {
+                        Ok(click) => {
+                            if click.workspace_id != workspace_id || (!include_bots && click.is_bot) {
+                                continue;
+                            }
+                            if link_code.as_ref().is_some_and(|code| *code != click.link_code) {
+                                continue;
+                            }
+                            Event::default().event("click").json_data(&click)
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            Ok(Event::default().event("lagged").data(skipped.to_string()))
+                        }
+                        Err(RecvError::Closed) => return None,
+                    };
+                    return Some((event, (live_rx, shutdown_rx)));
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Analytics, Config};
+    use crate::test_support;
+
+    fn click(link_code: &str) -> LiveClick {
+        LiveClick {
+            workspace_id: 1,
+            link_code: link_code.to_string(),
+            click_time: 0,
+            referrer_domain: None,
+            browser: None,
+            os: None,
+            device: None,
+            is_bot: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn publish_drops_when_queue_full() {
+        let cfg = Config {
+            analytics: Some(Analytics {
+                channel_capacity: Some(1),
+                ..Analytics::default()
+            }),
+            ..Config::default()
+        };
+        let Some((state, mut receivers)) = test_support::state_with_config(cfg).await else { return };
+        publish(&state, click("a"));
+        publish(&state, click("b"));
+        assert_eq!(receivers.live_publish_rx.try_recv().unwrap().link_code, "a");
+        assert!(receivers.live_publish_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn publish_batch_reaches_subscribers() {
+        let Some(state) = test_support::redis_state().await else { return };
+        let mut pubsub = state.redis_client.get_async_pubsub().await.unwrap();
+        pubsub.subscribe(LIVE_CHANNEL).await.unwrap();
+        let code = format!("live{}", test_support::workspace_id());
+        publish_batch(&state, &[click(&code), click("other")]).await.unwrap();
+
+        let mut messages = pubsub.on_message();
+        let received: LiveClick = messages.next().await.unwrap().get_payload::<String>()
+            .map(|payload| serde_json::from_str(&payload).unwrap())
+            .unwrap();
+        assert_eq!(received.link_code, code);
+    }
+}
+
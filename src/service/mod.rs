@@ -1,3 +1,17 @@
+pub mod api_key_service;
+pub mod access_service;
+pub mod click_service;
 pub mod link_base_service;
 pub mod link_service;
+pub mod live_service;
+pub mod privacy_service;
+pub mod stats_service;
 pub mod cleanup_service;
+pub mod webhook_service;
+pub mod workspace_service;
+pub mod jwt_service;
+pub mod rate_limit_service;
+pub mod url_rule_service;
+pub mod abuse_service;
+pub mod audit_service;
+pub mod signed_link_service;
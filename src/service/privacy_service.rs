@@ -0,0 +1,240 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bb8_redis::redis::{cmd, AsyncCommands};
+use chrono::Utc;
+use rand::RngCore;
+use tokio::select;
+use tokio::sync::broadcast;
+
+use crate::access_service::erase_access;
+use crate::link_service::query_link_by_code;
+use crate::pojo::click_stats::AnalyticsEraseResponse;
+use crate::pojo::AppError;
+use crate::service::audit_service::{self, Auditor};
+use crate::types::enums::{AuditAction, IpMode};
+use crate::types::{HandlerResult, IState};
+use crate::utils::helper::{anonymize_ip, calculate_sha256};
+
+/// IP哈希盐，key为 analytics:ip_salt:{周期序号}，所有实例共用同一个盐
+const IP_SALT_KEY: &str = "analytics:ip_salt:";
+/// 过期点击事件每批删除的数量
+const PURGE_BATCH_SIZE: i64 = 10000;
+/// 过期点击事件的检查间隔
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 按配置将IP转换为可保存的形式，不会返回原始IP
+///
+/// 哈希模式下获取盐失败时退回截断模式
+pub async fn storage_ip(state: &IState, ip: IpAddr) -> String {
+    if state.analytics_config.ip_mode.unwrap_or(IpMode::Truncate) == IpMode::Truncate {
+        return anonymize_ip(ip);
+    }
+    match current_salt(state).await {
+        Ok(salt) => calculate_sha256(&format!("{}|{}", salt, ip)),
+        Err(e) => {
+            tracing::warn!("获取IP哈希盐失败: {}，改为截断IP", e);
+            anonymize_ip(ip)
+        }
+    }
+}
+
+/// 当前周期的盐，进程内缓存，周期切换时从Redis读取或生成
+async fn current_salt(state: &IState) -> Result<String, AppError> {
+    let rotation_secs = state.analytics_config.ip_salt_rotation_hours.unwrap_or(24).max(1) * 3600;
+    let period = Utc::now().timestamp() as u64 / rotation_secs;
+    let cached = state.ip_salt.read().ok().and_then(|cached| {
+        cached
+            .as_ref()
+            .filter(|(cached_period, _)| *cached_period == period)
+            .map(|(_, salt)| salt.clone())
+    });
+    if let Some(salt) = cached {
+        return Ok(salt);
+    }
+
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let key = format!("{}{}", IP_SALT_KEY, period);
+    // 只保留到下个周期结束，过期后无法再由哈希反推IP
+    let _: Option<String> = cmd("SET")
+        .arg(&key)
+        .arg(hex::encode(bytes))
+        .arg("NX")
+        .arg("EX")
+        .arg(rotation_secs * 2)
+        .query_async(&mut *r_con)
+        .await?;
+    let salt: String = r_con.get(&key).await?;
+
+    if let Ok(mut cached) = state.ip_salt.write() {
+        *cached = Some((period, salt.clone()));
+    }
+    Ok(salt)
+}
+
+/// 定时删除超过保留天数的点击事件
+pub async fn click_retention_task(state: Arc<IState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let retention_days = state.analytics_config.retention_days.unwrap_or(0);
+    if retention_days == 0 {
+        tracing::info!("点击事件保留天数为0，不删除过期点击事件");
+        return;
+    }
+
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    tracing::info!("点击事件清理任务已启动，保留天数: {}", retention_days);
+
+    loop {
+        select! {
+            _ = interval.tick() => {
+                match purge_click_events(&state, retention_days).await {
+                    Ok(count) if count > 0 => tracing::info!("删除 {} 条过期点击事件", count),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("删除过期点击事件失败: {}", e),
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                tracing::info!("收到关闭信号，停止点击事件清理任务");
+                break;
+            }
+        }
+    }
+
+    tracing::info!("点击事件清理任务已停止");
+}
+
+/// 分批删除，避免长事务
+async fn purge_click_events(state: &IState, retention_days: u64) -> Result<u64, AppError> {
+    let before = Utc::now().naive_utc() - chrono::Duration::days(retention_days as i64);
+    let mut total = 0;
+    loop {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM click_event
+            WHERE id IN (SELECT id FROM click_event WHERE click_time < $1 LIMIT $2)
+            "#,
+        )
+        .bind(before)
+        .bind(PURGE_BATCH_SIZE)
+        .execute(&state.db_pool)
+        .await?;
+        total += result.rows_affected();
+        if result.rows_affected() < PURGE_BATCH_SIZE as u64 {
+            return Ok(total);
+        }
+    }
+}
+
+/// 清除工作空间内短链的全部统计数据：点击事件、汇总统计、点击次数以及Redis中尚未刷新的数据
+pub async fn erase_link_analytics(
+    pool: Arc<IState>,
+    auditor: &Auditor,
+    workspace_id: i64,
+    link_code: String,
+) -> HandlerResult<AnalyticsEraseResponse> {
+    let link = query_link_by_code(&pool, workspace_id, &link_code).await?;
+
+    let mut tx = pool.db_pool.begin().await?;
+    let click_events = sqlx::query("DELETE FROM click_event WHERE link_id = $1")
+        .bind(link.id)
+        .execute(&mut *tx)
+        .await?;
+    let hourly = sqlx::query("DELETE FROM click_stats_hourly WHERE link_id = $1")
+        .bind(link.id)
+        .execute(&mut *tx)
+        .await?;
+    let daily = sqlx::query("DELETE FROM click_stats_daily WHERE link_id = $1")
+        .bind(link.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM link_stats WHERE link_id = $1")
+        .bind(link.id)
+        .execute(&mut *tx)
+        .await?;
+    let erased = AnalyticsEraseResponse {
+        link_code,
+        click_events: click_events.rows_affected(),
+        hourly_buckets: hourly.rows_affected(),
+        daily_buckets: daily.rows_affected(),
+    };
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace_id,
+        AuditAction::AnalyticsErase,
+        &erased.link_code,
+        None,
+        serde_json::to_value(&erased).ok(),
+    )
+    .await?;
+    tx.commit().await?;
+
+    erase_access(&pool, link.id).await?;
+    tracing::info!("已清除短链 {} 的统计数据", erased.link_code);
+    Ok(erased)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Analytics, Config};
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn storage_ip_never_keeps_raw_ip() {
+        let cfg = Config {
+            analytics: Some(Analytics {
+                ip_mode: Some(IpMode::Hash),
+                ..Analytics::default()
+            }),
+            ..Config::default()
+        };
+        let Some((state, _)) = test_support::state_with_config(cfg).await else { return };
+        let ip: IpAddr = "203.0.113.77".parse().unwrap();
+
+        // 当前周期的盐已缓存时直接哈希
+        let period = Utc::now().timestamp() as u64 / (24 * 3600);
+        *state.ip_salt.write().unwrap() = Some((period, "salt".to_string()));
+        let hashed = storage_ip(&state, ip).await;
+        assert_eq!(hashed, calculate_sha256("salt|203.0.113.77"));
+
+        // 盐已过期且无法从Redis获取时退回截断
+        *state.ip_salt.write().unwrap() = Some((period - 1, "salt".to_string()));
+        if std::env::var("TEST_REDIS_URL").is_err() {
+            assert_eq!(storage_ip(&state, ip).await, "203.0.113.0");
+        }
+    }
+
+    #[tokio::test]
+    async fn purge_respects_click_retention() {
+        let Some(state) = test_support::state().await else { return };
+        let ws = test_support::workspace_id();
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/retention").await;
+        let now = Utc::now().naive_utc();
+        for days in [31, 29] {
+            sqlx::query("INSERT INTO click_event (link_id, click_time) VALUES ($1, $2)")
+                .bind(link.id)
+                .bind(now - chrono::Duration::days(days))
+                .execute(&state.db_pool)
+                .await
+                .unwrap();
+        }
+
+        assert!(purge_click_events(&state, 30).await.unwrap() >= 1);
+        let remaining: Vec<chrono::NaiveDateTime> =
+            sqlx::query_scalar("SELECT click_time FROM click_event WHERE link_id = $1")
+                .bind(link.id)
+                .fetch_all(&state.db_pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining[0] > now - chrono::Duration::days(30));
+    }
+}
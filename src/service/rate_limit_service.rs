@@ -0,0 +1,174 @@
+use std::sync::LazyLock;
+
+use bb8_redis::redis::{cmd, Script};
+use chrono::Utc;
+
+use crate::config::RatePolicy;
+use crate::types::enums::{RateAlgorithm, RateGroup};
+use crate::types::IState;
+
+/// 限流计数key前缀，key为 ratelimit:{分组}:{对象}
+const RATE_LIMIT_KEY: &str = "ratelimit:";
+
+/// 滑动窗口：上一窗口的计数按剩余比例加权后与当前窗口相加
+///
+/// 返回 {是否放行, 需要等待的毫秒数}
+static SLIDING_WINDOW: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r#"
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local elapsed = now % window
+local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+local previous = tonumber(redis.call('GET', KEYS[2]) or '0')
+if current + 1 > limit then
+    return {0, window - elapsed}
+end
+if previous * (window - elapsed) / window + current + 1 > limit then
+    local wait = window - math.floor(window * (limit - current - 1) / previous) - elapsed
+    return {0, math.max(wait, 1)}
+end
+redis.call('INCR', KEYS[1])
+redis.call('PEXPIRE', KEYS[1], window * 2)
+return {1, 0}
+"#,
+    )
+});
+
+/// 令牌桶：容量为limit，每个窗口匀速补满
+///
+/// 返回 {是否放行, 需要等待的毫秒数}
+static TOKEN_BUCKET: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r#"
+local capacity = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local rate = capacity / window
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1]) or capacity
+local ts = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - ts) * rate)
+local allowed = 0
+local wait = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    wait = math.max(math.ceil((1 - tokens) / rate), 1)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], window * 2)
+return {allowed, wait}
+"#,
+    )
+});
+
+/// 按分组策略为一个对象（IP或API Key）计数
+///
+/// * `subject`: 限流对象，如 `ip:1.2.3.4`、`key:<哈希>`
+///
+/// returns: 超出限制时返回需要等待的秒数，未超出或策略不限流时返回None
+pub async fn check(
+    state: &IState,
+    group: RateGroup,
+    policy: &RatePolicy,
+    subject: &str,
+) -> Result<Option<u64>, crate::AppError> {
+    let limit = policy.limit.unwrap_or(0);
+    if limit == 0 {
+        return Ok(None);
+    }
+    let window_ms = policy.window_secs.unwrap_or(60).max(1) * 1000;
+    let now_ms = Utc::now().timestamp_millis() as u64;
+    let prefix = format!("{}{}:{}", RATE_LIMIT_KEY, group.as_str(), subject);
+
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    // 脚本内读取和更新计数，保证多实例并发时的原子性；按SHA执行，脚本不在缓存中时自动加载
+    let mut invocation = match policy.algorithm.unwrap_or(RateAlgorithm::SlidingWindow) {
+        RateAlgorithm::SlidingWindow => {
+            let (current, previous) = window_keys(&prefix, now_ms, window_ms);
+            let mut invocation = SLIDING_WINDOW.prepare_invoke();
+            invocation.key(current).key(previous);
+            invocation
+        }
+        RateAlgorithm::TokenBucket => {
+            let mut invocation = TOKEN_BUCKET.prepare_invoke();
+            invocation.key(&prefix);
+            invocation
+        }
+    };
+    let (allowed, wait_ms): (i64, u64) = invocation
+        .arg(limit)
+        .arg(window_ms)
+        .arg(now_ms)
+        .invoke_async(&mut *r_con)
+        .await?;
+    Ok((allowed == 0).then(|| retry_after_secs(wait_ms)))
+}
+
+/// 滑动窗口当前和上一个窗口的key：{前缀}:{窗口序号}
+fn window_keys(prefix: &str, now_ms: u64, window_ms: u64) -> (String, String) {
+    let index = now_ms / window_ms;
+    (
+        format!("{}:{}", prefix, index),
+        format!("{}:{}", prefix, index.saturating_sub(1)),
+    )
+}
+
+/// `Retry-After` 以秒为单位，向上取整且至少为1秒
+fn retry_after_secs(wait_ms: u64) -> u64 {
+    wait_ms.div_ceil(1000).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RateLimit;
+    use crate::idgen::YitIdHelper;
+    use crate::test_support;
+
+    #[test]
+    fn window_and_retry_after() {
+        let (current, previous) = window_keys("ratelimit:redirect:ip:1.2.3.4", 125_000, 60_000);
+        assert_eq!(current, "ratelimit:redirect:ip:1.2.3.4:2");
+        assert_eq!(previous, "ratelimit:redirect:ip:1.2.3.4:1");
+
+        assert_eq!(retry_after_secs(0), 1);
+        assert_eq!(retry_after_secs(1000), 1);
+        assert_eq!(retry_after_secs(1001), 2);
+    }
+
+    #[test]
+    fn default_policies() {
+        let config = RateLimit::default();
+        assert_eq!(config.policy(RateGroup::Create).algorithm, Some(RateAlgorithm::TokenBucket));
+        assert_eq!(config.policy(RateGroup::Redirect).algorithm, Some(RateAlgorithm::SlidingWindow));
+    }
+
+    #[tokio::test]
+    async fn scripts_reject_over_limit() {
+        let Some(state) = test_support::redis_state().await else { return };
+        for algorithm in [RateAlgorithm::SlidingWindow, RateAlgorithm::TokenBucket] {
+            let policy = RatePolicy {
+                algorithm: Some(algorithm),
+                limit: Some(3),
+                window_secs: Some(60),
+            };
+            let subject = format!("ip:test-{}", YitIdHelper::next_id());
+            for _ in 0..3 {
+                assert_eq!(check(&state, RateGroup::Create, &policy, &subject).await.unwrap(), None);
+            }
+            let retry_after = check(&state, RateGroup::Create, &policy, &subject).await.unwrap();
+            assert!(matches!(retry_after, Some(1..=60)), "{:?}", retry_after);
+
+            // 其他对象的计数互不影响
+            let other = format!("ip:test-{}", YitIdHelper::next_id());
+            assert_eq!(check(&state, RateGroup::Create, &policy, &other).await.unwrap(), None);
+        }
+    }
+}
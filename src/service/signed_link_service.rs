@@ -0,0 +1,242 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::link_base_service::lock_by_id;
+use crate::link_service::{evict_cache, query_link_by_code};
+use crate::pojo::api_key::ApiActor;
+use crate::pojo::link_history::{LinkHistory, SignedLinkResponse};
+use crate::pojo::AppError;
+use crate::service::audit_service::{self, Auditor};
+use crate::types::enums::AuditAction;
+use crate::types::{HandlerResult, IState};
+use crate::utils::helper::hmac_sha256_hex;
+
+/// 签名校验失败的原因
+#[derive(Debug, PartialEq)]
+pub enum SignatureError {
+    /// 签名与短码和过期时间不匹配
+    Invalid,
+    /// 签名正确但已过期
+    Expired,
+}
+
+/// 签名的内容：短码和过期时间
+fn signing_payload(link_code: &str, exp: i64) -> String {
+    format!("{}.{}", link_code, exp)
+}
+
+/// 对短码和过期时间（Unix时间戳，秒）签名，返回小写hex
+pub fn sign(secret: &str, link_code: &str, exp: i64) -> String {
+    hmac_sha256_hex(secret, &signing_payload(link_code, exp))
+}
+
+/// 校验签名和过期时间，签名以常量时间比较
+pub fn verify_signature(
+    secret: &str,
+    link_code: &str,
+    exp: i64,
+    sig: &str,
+    now: i64,
+) -> Result<(), SignatureError> {
+    let sig = hex::decode(sig).map_err(|_| SignatureError::Invalid)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(signing_payload(link_code, exp).as_bytes());
+    mac.verify_slice(&sig).map_err(|_| SignatureError::Invalid)?;
+    if exp <= now {
+        return Err(SignatureError::Expired);
+    }
+    Ok(())
+}
+
+fn secret(state: &IState) -> Option<&str> {
+    state.signed_link_config.secret.as_deref().filter(|secret| !secret.is_empty())
+}
+
+/// 生成签名链接和开启签名校验前确认已配置密钥，未配置时开启后链接将无法访问
+pub fn require_secret(state: &IState) -> Result<&str, AppError> {
+    secret(state).ok_or_else(|| AppError::bad_request(anyhow::anyhow!("未配置签名密钥")))
+}
+
+/// 校验跳转请求中的签名参数，只依赖配置的密钥，不查询数据库和缓存
+///
+/// 未携带签名参数时返回false，由调用方按链接是否开启签名校验决定是否放行；签名无效返回403，已过期返回410
+pub fn check_request(
+    state: &IState,
+    link_code: &str,
+    exp: Option<i64>,
+    sig: Option<&str>,
+) -> Result<bool, AppError> {
+    let (exp, sig) = match (exp, sig) {
+        (None, None) => return Ok(false),
+        (Some(exp), Some(sig)) => (exp, sig),
+        _ => return Err(AppError::forbidden(anyhow::anyhow!("签名无效"))),
+    };
+    let secret = secret(state).ok_or_else(|| AppError::forbidden(anyhow::anyhow!("签名无效")))?;
+    match verify_signature(secret, link_code, exp, sig, Utc::now().timestamp()) {
+        Ok(()) => Ok(true),
+        Err(SignatureError::Invalid) => Err(AppError::forbidden(anyhow::anyhow!("签名无效"))),
+        Err(SignatureError::Expired) => {
+            Err(AppError::new(StatusCode::GONE, anyhow::anyhow!("签名链接已过期")))
+        }
+    }
+}
+
+/// 为工作空间内的有效链接生成签名链接，并开启链接的签名校验，之后未签名的访问返回403
+///
+/// 需要有修改该链接的权限
+///
+/// * `duration`: 有效期（秒），为空时使用默认有效期，不能超过最长有效期
+pub async fn sign_link(
+    state: &IState,
+    actor: &ApiActor,
+    auditor: &Auditor,
+    link_code: &str,
+    duration: Option<u64>,
+) -> HandlerResult<SignedLinkResponse> {
+    let secret = require_secret(state)?;
+    let config = &state.signed_link_config;
+    let duration = duration.unwrap_or(config.default_ttl_secs.unwrap_or(3600));
+    let max_ttl = config.max_ttl_secs.unwrap_or(604800);
+    if duration == 0 || duration > max_ttl {
+        return Err(AppError::bad_request(anyhow::anyhow!(
+            "有效期需在1到{}秒之间",
+            max_ttl
+        )));
+    }
+
+    let link = query_link_by_code(state, actor.workspace_id, link_code).await?;
+    actor.require_editable(link.owner())?;
+    require_signature(state, auditor, &link).await?;
+    let exp = Utc::now().timestamp() + duration as i64;
+    let sig = sign(secret, link_code, exp);
+    Ok(SignedLinkResponse {
+        link_code: link_code.to_string(),
+        path: format!("/s/{}?exp={}&sig={}", link_code, exp, sig),
+        domain: link.domain,
+        exp,
+        sig,
+    })
+}
+
+/// 开启链接的签名校验，与审计日志在同一事务中写入，已开启时不重复记录
+///
+/// 清除缓存失败时返回错误：缓存中的值仍允许未签名的访问，重试时会再次清除
+pub async fn require_signature(state: &IState, auditor: &Auditor, link: &LinkHistory) -> HandlerResult<()> {
+    let mut tx = state.db_pool.begin().await?;
+    let before = match lock_by_id(&mut tx, link.id).await? {
+        Some(before) if before.active => before,
+        _ => return Err(AppError::bad_request(anyhow::anyhow!("短链已失效"))),
+    };
+    if !before.require_signature {
+        let after = sqlx::query_as::<_, LinkHistory>(
+            "UPDATE link_history SET require_signature = true, update_time = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(link.id)
+        .fetch_one(&mut *tx)
+        .await?;
+        audit_service::record_link_change(
+            &mut tx,
+            auditor,
+            AuditAction::LinkRequireSignature,
+            Some(&before),
+            Some(&after),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    evict_cache(state, link).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, SignedLink};
+    use crate::link_service::{create_link, query_origin_url, LinkOptions};
+    use crate::test_support;
+    use crate::types::enums::Role;
+    use crate::utils::helper::encode_base62;
+
+    fn signed_config() -> Config {
+        Config {
+            signed_link: Some(SignedLink {
+                secret: Some("secret".to_string()),
+                ..SignedLink::default()
+            }),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn signature_check() {
+        let sig = sign("secret", "abc", 1_000);
+        assert!(verify_signature("secret", "abc", 1_000, &sig, 999).is_ok());
+        assert_eq!(verify_signature("secret", "abc", 1_000, &sig, 1_000), Err(SignatureError::Expired));
+        assert_eq!(verify_signature("secret", "abd", 1_000, &sig, 999), Err(SignatureError::Invalid));
+        assert_eq!(verify_signature("secret", "abc", 2_000, &sig, 999), Err(SignatureError::Invalid));
+        assert_eq!(verify_signature("other", "abc", 1_000, &sig, 999), Err(SignatureError::Invalid));
+        assert_eq!(verify_signature("secret", "abc", 1_000, "zz", 999), Err(SignatureError::Invalid));
+    }
+
+    #[tokio::test]
+    async fn sign_requires_editable_link() {
+        let Some((state, _receivers)) = test_support::state_with_config(signed_config()).await else { return };
+        let ws = test_support::workspace_id();
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/signed/editable").await;
+        let code = encode_base62(link.id as usize);
+
+        // editor只能为自己创建的链接生成签名链接，viewer没有修改权限
+        for role in [Role::Viewer, Role::Editor] {
+            let actor = test_support::actor(ws, role);
+            let err = sign_link(&state, &actor, &test_support::auditor(&actor), &code, None).await.unwrap_err();
+            assert_eq!(err.status(), StatusCode::FORBIDDEN);
+        }
+        let link = query_link_by_code(&state, ws, &code).await.unwrap();
+        assert!(!link.require_signature);
+    }
+
+    #[tokio::test]
+    async fn create_requiring_signature_needs_secret() {
+        let Some(state) = test_support::state().await else { return };
+        let admin = test_support::actor(test_support::workspace_id(), Role::Admin);
+        let options = LinkOptions {
+            require_signature: true,
+            ..LinkOptions::default()
+        };
+        let err = create_link(
+            state.clone(),
+            &admin,
+            &test_support::auditor(&admin),
+            "https://93.184.216.34/signed/no-secret".to_string(),
+            None,
+            None,
+            options,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn signing_enables_signature_check() {
+        if std::env::var("TEST_REDIS_URL").is_err() {
+            return;
+        }
+        let Some((state, _receivers)) = test_support::state_with_config(signed_config()).await else { return };
+        let ws = test_support::workspace_id();
+        let admin = test_support::actor(ws, Role::Admin);
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/signed/enabled").await;
+        let code = encode_base62(link.id as usize);
+
+        // 签名前访问过的链接已有缓存，开启后缓存同样需要签名
+        assert!(!query_origin_url(state.clone(), None, code.clone()).await.unwrap().require_signature);
+        let signed = sign_link(&state, &admin, &test_support::auditor(&admin), &code, None).await.unwrap();
+        assert!(check_request(&state, &code, Some(signed.exp), Some(&signed.sig)).unwrap());
+        assert!(query_link_by_code(&state, ws, &code).await.unwrap().require_signature);
+        assert!(query_origin_url(state.clone(), None, code.clone()).await.unwrap().require_signature);
+    }
+}
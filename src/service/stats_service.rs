@@ -0,0 +1,629 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bb8_redis::redis::{cmd, pipe};
+use chrono::{NaiveDateTime, Utc};
+use tokio::select;
+use tokio::sync::broadcast;
+
+use crate::access_service::unique_visitor_key;
+use crate::link_service::query_link_by_code;
+use crate::pojo::click_stats::{
+    BreakdownItem, ClickBreakdownResponse, ClickPoint, ClickSeriesResponse, DailyCount,
+    StatsOverviewResponse, TopLink,
+};
+use crate::pojo::AppError;
+use crate::types::enums::Granularity;
+use crate::types::{HandlerResult, IState};
+use crate::utils::helper::encode_base62;
+
+/// 定时将点击事件汇总到按小时、按天的统计表的任务
+pub async fn rollup_clicks_task(state: Arc<IState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let interval_secs = state.analytics_config.rollup_interval_secs.unwrap_or(300);
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    tracing::info!("点击统计汇总任务已启动，汇总间隔: {} 秒", interval_secs);
+
+    loop {
+        select! {
+            _ = interval.tick() => {
+                match rollup_clicks(&state).await {
+                    Ok(count) => tracing::debug!("点击统计汇总完成，更新 {} 个时间桶", count),
+                    Err(e) => tracing::error!("点击统计汇总失败: {}", e),
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                tracing::info!("收到关闭信号，停止点击统计汇总任务");
+                break;
+            }
+        }
+    }
+
+    tracing::info!("点击统计汇总任务已停止");
+}
+
+/// 重新汇总回看窗口内的点击事件，重复执行结果一致
+async fn rollup_clicks(state: &IState) -> Result<u64, AppError> {
+    let lookback_hours = state.analytics_config.rollup_lookback_hours.unwrap_or(2) as i64;
+    let since = Utc::now().naive_utc() - chrono::Duration::hours(lookback_hours);
+    let mut tx = state.db_pool.begin().await?;
+
+    let hourly = sqlx::query(
+        r#"
+        INSERT INTO click_stats_hourly (link_id, bucket, clicks, bot_clicks)
+        SELECT link_id, date_trunc('hour', click_time),
+               COUNT(*) FILTER (WHERE NOT is_bot), COUNT(*) FILTER (WHERE is_bot)
+        FROM click_event
+        WHERE click_time >= date_trunc('hour', $1::timestamp)
+        GROUP BY 1, 2
+        ON CONFLICT (link_id, bucket) DO UPDATE
+        SET clicks = EXCLUDED.clicks, bot_clicks = EXCLUDED.bot_clicks
+        "#,
+    )
+    .bind(since)
+    .execute(&mut *tx)
+    .await?;
+
+    // 按天统计由小时统计汇总，覆盖回看窗口涉及的整天
+    let daily = sqlx::query(
+        r#"
+        INSERT INTO click_stats_daily (link_id, bucket, clicks, bot_clicks)
+        SELECT link_id, date_trunc('day', bucket), SUM(clicks), SUM(bot_clicks)
+        FROM click_stats_hourly
+        WHERE bucket >= date_trunc('day', $1::timestamp)
+        GROUP BY 1, 2
+        ON CONFLICT (link_id, bucket) DO UPDATE
+        SET clicks = EXCLUDED.clicks, bot_clicks = EXCLUDED.bot_clicks
+        "#,
+    )
+    .bind(since)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let unique = persist_unique_visitors(state, since).await?;
+    Ok(hourly.rows_affected() + daily.rows_affected() + unique)
+}
+
+/// 将Redis中每日独立访客的PFCOUNT估算值写入按天统计表
+async fn persist_unique_visitors(state: &IState, since: NaiveDateTime) -> Result<u64, AppError> {
+    let buckets: Vec<(i64, NaiveDateTime)> = sqlx::query_as(
+        "SELECT link_id, bucket FROM click_stats_daily WHERE bucket >= date_trunc('day', $1::timestamp)",
+    )
+    .bind(since)
+    .fetch_all(&state.db_pool)
+    .await?;
+    if buckets.is_empty() {
+        return Ok(0);
+    }
+
+    let redis_db = state.redis_db.unwrap_or(0);
+    let mut r_con = state.redis_pool.get().await?;
+    cmd("SELECT").arg(redis_db).query_async::<_, ()>(&mut *r_con).await?;
+
+    let mut total_updated = 0;
+    for chunk in buckets.chunks(1000) {
+        let mut pfcount = pipe();
+        for (link_id, bucket) in chunk {
+            pfcount.cmd("PFCOUNT").arg(unique_visitor_key(*link_id, bucket.date()));
+        }
+        let counts: Vec<i64> = pfcount.query_async(&mut *r_con).await?;
+
+        let link_ids: Vec<i64> = chunk.iter().map(|(link_id, _)| *link_id).collect();
+        let days: Vec<NaiveDateTime> = chunk.iter().map(|(_, bucket)| *bucket).collect();
+        // HyperLogLog数据丢失时保留已持久化的值
+        let result = sqlx::query(
+            r#"
+            UPDATE click_stats_daily AS s
+            SET unique_visitors = GREATEST(s.unique_visitors, t.uv)
+            FROM UNNEST($1::bigint[], $2::timestamp[], $3::bigint[]) AS t(link_id, bucket, uv)
+            WHERE s.link_id = t.link_id AND s.bucket = t.bucket
+            "#,
+        )
+        .bind(&link_ids)
+        .bind(&days)
+        .bind(&counts)
+        .execute(&state.db_pool)
+        .await?;
+        total_updated += result.rows_affected();
+    }
+
+    Ok(total_updated)
+}
+
+/// 查询短链在时间范围内的点击时间序列，数据来自汇总表，没有点击的时间桶补0
+///
+/// 默认不包含机器人的点击
+pub async fn get_click_series(
+    pool: Arc<IState>,
+    workspace_id: i64,
+    link_code: String,
+    from: Option<i64>,
+    to: Option<i64>,
+    granularity: Granularity,
+    include_bots: bool,
+) -> HandlerResult<ClickSeriesResponse> {
+    let link = query_link_by_code(&pool, workspace_id, &link_code).await?;
+    let (from, to) = resolve_range(from, to, granularity)?;
+
+    // 只有按天统计有独立访客数
+    let unique_visitors = match granularity {
+        Granularity::Hour => "NULL::bigint",
+        Granularity::Day => "COALESCE(s.unique_visitors, 0)::bigint",
+    };
+    let sql = format!(
+        r#"
+        SELECT g.bucket,
+               COALESCE(s.clicks + CASE WHEN $4 THEN s.bot_clicks ELSE 0 END, 0)::bigint
+                   AS clicks,
+               {unique_visitors} AS unique_visitors
+        FROM generate_series(date_trunc('{unit}', $2::timestamp), $3::timestamp, interval '1 {unit}') AS g(bucket)
+        LEFT JOIN {table} s ON s.bucket = g.bucket AND s.link_id = $1
+        ORDER BY g.bucket
+        "#,
+        unit = granularity.unit(),
+        table = granularity.table(),
+    );
+    let rows: Vec<(NaiveDateTime, i64, Option<i64>)> = sqlx::query_as(&sql)
+        .bind(link.id)
+        .bind(from)
+        .bind(to)
+        .bind(include_bots)
+        .fetch_all(&pool.db_pool)
+        .await?;
+
+    let points: Vec<ClickPoint> = rows
+        .into_iter()
+        .map(|(bucket, clicks, unique_visitors)| ClickPoint {
+            time: bucket.and_utc().timestamp_millis(),
+            clicks,
+            unique_visitors,
+        })
+        .collect();
+
+    Ok(ClickSeriesResponse {
+        link_code,
+        granularity: granularity.unit().to_string(),
+        from: from.and_utc().timestamp_millis(),
+        to: to.and_utc().timestamp_millis(),
+        total: points.iter().map(|point| point.clicks).sum(),
+        points,
+    })
+}
+
+/// 查询短链在时间范围内的来源域名、浏览器、操作系统、设备类型排行，默认不包含机器人的点击
+pub async fn get_click_breakdown(
+    pool: Arc<IState>,
+    workspace_id: i64,
+    link_code: String,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<usize>,
+    include_bots: bool,
+) -> HandlerResult<ClickBreakdownResponse> {
+    let link = query_link_by_code(&pool, workspace_id, &link_code).await?;
+    let (from, to) = resolve_range(from, to, Granularity::Day)?;
+    let limit = limit.unwrap_or(10).clamp(1, 100);
+
+    let filter = BreakdownFilter {
+        link_id: link.id,
+        from,
+        to,
+        limit,
+        include_bots,
+    };
+    let db_pool = &pool.db_pool;
+    let referrers = query_top_values(db_pool, "referrer_domain", "(direct)", &filter);
+    let browsers = query_top_values(db_pool, "browser", "Other", &filter);
+    let os = query_top_values(db_pool, "os", "Other", &filter);
+    let devices = query_top_values(db_pool, "device", "Other", &filter);
+    let (referrers, browsers, os, devices) = tokio::try_join!(referrers, browsers, os, devices)?;
+
+    Ok(ClickBreakdownResponse {
+        link_code,
+        from: from.and_utc().timestamp_millis(),
+        to: to.and_utc().timestamp_millis(),
+        referrers,
+        browsers,
+        os,
+        devices,
+    })
+}
+
+/// 维度排行的查询条件
+struct BreakdownFilter {
+    link_id: i64,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    limit: usize,
+    include_bots: bool,
+}
+
+/// 按指定维度统计点击数排行，column只能是内部固定的列名
+async fn query_top_values(
+    m_conn: &sqlx::PgPool,
+    column: &str,
+    empty_name: &str,
+    filter: &BreakdownFilter,
+) -> Result<Vec<BreakdownItem>, AppError> {
+    let sql = format!(
+        r#"
+        SELECT COALESCE({column}, $5) AS name, COUNT(*) AS clicks
+        FROM click_event
+        WHERE link_id = $1 AND click_time >= $2 AND click_time <= $3 AND (NOT is_bot OR $6)
+        GROUP BY 1
+        ORDER BY 2 DESC, 1
+        LIMIT $4
+        "#
+    );
+    let rows: Vec<(String, i64)> = sqlx::query_as(&sql)
+        .bind(filter.link_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.limit as i64)
+        .bind(empty_name)
+        .bind(filter.include_bots)
+        .fetch_all(m_conn)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, clicks)| BreakdownItem { name, clicks })
+        .collect())
+}
+
+/// 查询工作空间的概览：链接状态分布、每天新建链接数、每天点击数以及点击排行
+///
+/// 点击数据来自按天统计表，默认不包含机器人的点击
+pub async fn get_overview(
+    pool: Arc<IState>,
+    workspace_id: i64,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<usize>,
+    include_bots: bool,
+) -> HandlerResult<StatsOverviewResponse> {
+    let (from, to) = resolve_range(from, to, Granularity::Day)?;
+    let limit = limit.unwrap_or(10).clamp(1, 100);
+    let db_pool = &pool.db_pool;
+
+    let (link_counts, links_created, clicks, top_links) = tokio::try_join!(
+        query_link_status_counts(db_pool, workspace_id),
+        query_links_created(db_pool, workspace_id, from, to),
+        query_daily_clicks(db_pool, workspace_id, from, to, include_bots),
+        query_top_links(db_pool, workspace_id, from, to, limit, include_bots),
+    )?;
+    let (total_links, active_links, inactive_links, expired_links) = link_counts;
+
+    Ok(StatsOverviewResponse {
+        from: from.and_utc().timestamp_millis(),
+        to: to.and_utc().timestamp_millis(),
+        total_links,
+        active_links,
+        inactive_links,
+        expired_links,
+        total_clicks: clicks.iter().map(|point| point.count).sum(),
+        links_created,
+        clicks,
+        top_links,
+    })
+}
+
+/// 统计链接总数、有效数、失效数以及已过期待清理数
+async fn query_link_status_counts(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+) -> Result<(i64, i64, i64, i64), AppError> {
+    let counts = sqlx::query_as(
+        r#"
+        SELECT COUNT(*),
+               COUNT(*) FILTER (WHERE active AND NOT expired),
+               COUNT(*) FILTER (WHERE NOT active),
+               COUNT(*) FILTER (WHERE active AND expired)
+        FROM (
+            SELECT active,
+                   COALESCE(expire_date < NOW(), false)
+                       OR COALESCE(idle_deadline < NOW(), false) AS expired
+            FROM link_history
+            WHERE workspace_id = $1
+        ) AS t
+        "#,
+    )
+    .bind(workspace_id)
+    .fetch_one(m_conn)
+    .await?;
+    Ok(counts)
+}
+
+/// 按天统计新建的链接数，没有新建的日期补0
+async fn query_links_created(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<DailyCount>, AppError> {
+    let rows: Vec<(NaiveDateTime, i64)> = sqlx::query_as(
+        r#"
+        SELECT g.bucket, COUNT(h.id)
+        FROM generate_series(date_trunc('day', $1::timestamp), $2::timestamp, interval '1 day') AS g(bucket)
+        LEFT JOIN link_history h
+          ON h.create_time >= g.bucket AND h.create_time < g.bucket + interval '1 day'
+         AND h.workspace_id = $3
+        GROUP BY g.bucket
+        ORDER BY g.bucket
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(workspace_id)
+    .fetch_all(m_conn)
+    .await?;
+    Ok(to_daily_counts(rows))
+}
+
+/// 按天统计工作空间内所有短链的点击数，没有点击的日期补0
+async fn query_daily_clicks(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    include_bots: bool,
+) -> Result<Vec<DailyCount>, AppError> {
+    let rows: Vec<(NaiveDateTime, i64)> = sqlx::query_as(
+        r#"
+        SELECT g.bucket,
+               COALESCE(SUM(s.clicks + CASE WHEN $3 THEN s.bot_clicks ELSE 0 END), 0)::bigint
+        FROM generate_series(date_trunc('day', $1::timestamp), $2::timestamp, interval '1 day') AS g(bucket)
+        LEFT JOIN (
+            click_stats_daily s JOIN link_history h ON h.id = s.link_id AND h.workspace_id = $4
+        ) ON s.bucket = g.bucket
+        GROUP BY g.bucket
+        ORDER BY g.bucket
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(include_bots)
+    .bind(workspace_id)
+    .fetch_all(m_conn)
+    .await?;
+    Ok(to_daily_counts(rows))
+}
+
+/// 查询工作空间内时间范围内点击数最多的短链
+async fn query_top_links(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    limit: usize,
+    include_bots: bool,
+) -> Result<Vec<TopLink>, AppError> {
+    let rows: Vec<(i64, String, String, bool, i64)> = sqlx::query_as(
+        r#"
+        SELECT h.id, h.domain, h.origin_url, h.active, t.clicks
+        FROM (
+            SELECT link_id, SUM(clicks + CASE WHEN $4 THEN bot_clicks ELSE 0 END)::bigint AS clicks
+            FROM click_stats_daily
+            WHERE bucket >= date_trunc('day', $1::timestamp) AND bucket <= $2
+            GROUP BY link_id
+        ) AS t
+        JOIN link_history h ON h.id = t.link_id
+        WHERE t.clicks > 0 AND h.workspace_id = $5
+        ORDER BY t.clicks DESC, h.id
+        LIMIT $3
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(limit as i64)
+    .bind(include_bots)
+    .bind(workspace_id)
+    .fetch_all(m_conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, domain, origin_url, active, clicks)| TopLink {
+            link_code: encode_base62(id as usize),
+            domain,
+            origin_url,
+            active,
+            clicks,
+        })
+        .collect())
+}
+
+fn to_daily_counts(rows: Vec<(NaiveDateTime, i64)>) -> Vec<DailyCount> {
+    rows.into_iter()
+        .map(|(bucket, count)| DailyCount {
+            time: bucket.and_utc().timestamp_millis(),
+            count,
+        })
+        .collect()
+}
+
+/// 解析查询的时间范围（毫秒时间戳），默认查询截止当前的最近一段时间
+pub fn resolve_range(
+    from: Option<i64>,
+    to: Option<i64>,
+    granularity: Granularity,
+) -> Result<(NaiveDateTime, NaiveDateTime), AppError> {
+    let to = match to {
+        Some(millis) => from_millis(millis)?,
+        None => Utc::now().naive_utc(),
+    };
+    let from = match from {
+        Some(millis) => from_millis(millis)?,
+        None => match granularity {
+            Granularity::Hour => to - chrono::Duration::hours(24),
+            Granularity::Day => to - chrono::Duration::days(30),
+        },
+    };
+
+    if from > to {
+        return Err(AppError::bad_request(anyhow::anyhow!("from 不能晚于 to")));
+    }
+    if to - from > granularity.max_range() {
+        return Err(AppError::bad_request(anyhow::anyhow!(
+            "按{}统计时最多查询 {} 天",
+            granularity.unit(),
+            granularity.max_range().num_days()
+        )));
+    }
+    Ok((from, to))
+}
+
+fn from_millis(millis: i64) -> Result<NaiveDateTime, AppError> {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|dt| dt.naive_utc())
+        .ok_or_else(|| AppError::bad_request(anyhow::anyhow!("无效的时间戳: {}", millis)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+    use crate::test_support;
+
+    /// 汇总任务重新计算所有点击，并发执行时相互等待
+    static ROLLUP: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    async fn insert_click(state: &IState, link_id: i64, click_time: NaiveDateTime, is_bot: bool) {
+        sqlx::query("INSERT INTO click_event (link_id, click_time, is_bot) VALUES ($1, $2, $3)")
+            .bind(link_id)
+            .bind(click_time)
+            .bind(is_bot)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+    }
+
+    fn millis(time: NaiveDateTime) -> i64 {
+        time.and_utc().timestamp_millis()
+    }
+
+    #[tokio::test]
+    async fn rollup_buckets_by_hour() {
+        let Some(state) = test_support::state().await else { return };
+        let _guard = ROLLUP.lock().await;
+        let ws = test_support::workspace_id();
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/rollup").await;
+        let now = Utc::now().naive_utc();
+        let previous = now - chrono::Duration::hours(1);
+        insert_click(&state, link.id, previous, false).await;
+        insert_click(&state, link.id, previous, false).await;
+        insert_click(&state, link.id, previous, true).await;
+        insert_click(&state, link.id, now, false).await;
+
+        // 独立访客的估算值在Redis中，未配置Redis时点击已汇总但返回错误
+        let _ = rollup_clicks(&state).await;
+
+        let code = encode_base62(link.id as usize);
+        let series = |include_bots| {
+            get_click_series(
+                state.clone(),
+                ws,
+                code.clone(),
+                Some(millis(previous)),
+                Some(millis(now)),
+                Granularity::Hour,
+                include_bots,
+            )
+        };
+        let humans = series(false).await.unwrap();
+        let clicks: Vec<i64> = humans.points.iter().map(|point| point.clicks).collect();
+        assert_eq!(clicks, vec![2, 1]);
+        assert_eq!(humans.total, 3);
+        let first_bucket = previous.date().and_hms_opt(previous.time().hour(), 0, 0).unwrap();
+        assert_eq!(humans.points[0].time, millis(first_bucket));
+
+        let all = series(true).await.unwrap();
+        let clicks: Vec<i64> = all.points.iter().map(|point| point.clicks).collect();
+        assert_eq!(clicks, vec![3, 1]);
+
+        // 其他工作空间查询不到该链接
+        let other = get_click_series(state.clone(), ws + 1, code, None, None, Granularity::Hour, false).await;
+        assert!(other.is_err());
+    }
+
+    #[tokio::test]
+    async fn unique_visitors_from_hyperloglog() {
+        let Some(state) = test_support::redis_state().await else { return };
+        let _guard = ROLLUP.lock().await;
+        let ws = test_support::workspace_id();
+        let link = test_support::insert_link(&state, ws, "https://93.184.216.34/uv").await;
+        let now = Utc::now().naive_utc();
+        insert_click(&state, link.id, now, false).await;
+
+        let uv_key = unique_visitor_key(link.id, now.date());
+        let mut r_con = state.redis_pool.get().await.unwrap();
+        let _: () = cmd("PFADD").arg(&uv_key).arg("a").arg("b").arg("a").query_async(&mut *r_con).await.unwrap();
+        rollup_clicks(&state).await.unwrap();
+
+        let unique_visitors = || async {
+            sqlx::query_scalar::<_, i64>("SELECT unique_visitors FROM click_stats_daily WHERE link_id = $1")
+                .bind(link.id)
+                .fetch_one(&state.db_pool)
+                .await
+                .unwrap()
+        };
+        assert_eq!(unique_visitors().await, 2);
+
+        // HyperLogLog过期或丢失后不覆盖已持久化的值
+        let _: () = cmd("DEL").arg(&uv_key).query_async(&mut *r_con).await.unwrap();
+        rollup_clicks(&state).await.unwrap();
+        assert_eq!(unique_visitors().await, 2);
+    }
+
+    #[tokio::test]
+    async fn overview_ranks_workspace_links() {
+        let Some(state) = test_support::state().await else { return };
+        let _guard = ROLLUP.lock().await;
+        let ws = test_support::workspace_id();
+        let popular = test_support::insert_link(&state, ws, "https://93.184.216.34/popular").await;
+        let quiet = test_support::insert_link(&state, ws, "https://93.184.216.34/quiet").await;
+        let inactive = test_support::insert_link(&state, ws, "https://93.184.216.34/inactive").await;
+        sqlx::query("UPDATE link_history SET active = false WHERE id = $1")
+            .bind(inactive.id)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+        let other_ws = test_support::workspace_id();
+        let other = test_support::insert_link(&state, other_ws, "https://93.184.216.34/other").await;
+
+        let now = Utc::now().naive_utc();
+        for _ in 0..3 {
+            insert_click(&state, popular.id, now, false).await;
+        }
+        insert_click(&state, quiet.id, now, false).await;
+        insert_click(&state, quiet.id, now, true).await;
+        for _ in 0..5 {
+            insert_click(&state, other.id, now, false).await;
+        }
+        let _ = rollup_clicks(&state).await;
+
+        let overview = get_overview(state.clone(), ws, None, None, Some(10), false).await.unwrap();
+        assert_eq!(overview.total_links, 3);
+        assert_eq!(overview.active_links, 2);
+        assert_eq!(overview.inactive_links, 1);
+        assert_eq!(overview.total_clicks, 4);
+        let top: Vec<(String, i64)> = overview
+            .top_links
+            .iter()
+            .map(|link| (link.link_code.clone(), link.clicks))
+            .collect();
+        assert_eq!(
+            top,
+            vec![(encode_base62(popular.id as usize), 3), (encode_base62(quiet.id as usize), 1)]
+        );
+
+        let with_bots = get_overview(state.clone(), ws, None, None, Some(1), true).await.unwrap();
+        assert_eq!(with_bots.total_clicks, 5);
+        assert_eq!(with_bots.top_links.len(), 1);
+    }
+}
+
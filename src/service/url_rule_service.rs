@@ -0,0 +1,241 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::select;
+use tokio::sync::broadcast;
+
+use crate::idgen::YitIdHelper;
+use crate::pojo::url_rule::{normalize_domain, UrlRule, UrlRuleFile, UrlRuleResponse, UrlRules};
+use crate::pojo::AppError;
+use crate::service::audit_service::{self, Auditor};
+use crate::types::enums::{AuditAction, UrlRuleKind};
+use crate::types::{HandlerResult, IState};
+
+/// 规则对全部工作空间生效，只由默认工作空间维护，审计日志记录在默认工作空间下
+const RULE_AUDIT_WORKSPACE_ID: i64 = 0;
+
+/// 定时重新加载规则文件和数据库中的规则，加载失败时继续使用上一次的规则
+pub async fn url_rule_refresh_task(state: Arc<IState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let reload_secs = state.blocklist_config.reload_secs.unwrap_or(30).max(1);
+    let mut interval = tokio::time::interval(Duration::from_secs(reload_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    tracing::info!("目标地址规则加载任务已启动，刷新间隔: {} 秒", reload_secs);
+    // 启动时已同步加载，跳过立即触发的第一次刷新
+    interval.tick().await;
+
+    loop {
+        select! {
+            _ = interval.tick() => {
+                match refresh_rules(&state).await {
+                    Ok(count) => tracing::debug!("已加载 {} 条目标地址规则", count),
+                    Err(e) => tracing::error!("加载目标地址规则失败: {}", e),
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                tracing::info!("收到关闭信号，停止目标地址规则加载任务");
+                break;
+            }
+        }
+    }
+
+    tracing::info!("目标地址规则加载任务已停止");
+}
+
+/// 合并规则文件和数据库中的规则并替换当前规则，返回规则数量
+///
+/// 规则文件读取或解析失败时使用上一次成功加载的规则文件，数据库中的规则照常更新
+pub async fn refresh_rules(state: &IState) -> Result<usize, AppError> {
+    let file = match &state.blocklist_config.path {
+        None => UrlRuleFile::default(),
+        Some(path) => match load_rule_file(path).await {
+            Ok(file) => {
+                if let Ok(mut last) = state.url_rule_file.write() {
+                    *last = file.clone();
+                }
+                file
+            }
+            Err(e) => {
+                tracing::error!("加载规则文件 {} 失败，继续使用上一次的规则: {}", path, e);
+                state.url_rule_file.read().map(|last| last.clone()).unwrap_or_default()
+            }
+        },
+    };
+
+    let mut rules = UrlRules::default();
+    let entries = [
+        (UrlRuleKind::BlockDomain, file.block_domains),
+        (UrlRuleKind::BlockPattern, file.block_patterns),
+        (UrlRuleKind::AllowDomain, file.allow_domains),
+    ];
+    for (kind, values) in entries {
+        for value in values {
+            if let Err(e) = rules.add(kind, &value) {
+                tracing::warn!("忽略规则文件中的 {} 规则: {}", kind.as_str(), e);
+            }
+        }
+    }
+
+    let stored = sqlx::query_as::<_, UrlRule>("SELECT * FROM url_rule")
+        .fetch_all(&state.db_pool)
+        .await?;
+    for rule in stored {
+        let added = match UrlRuleKind::parse(&rule.kind) {
+            Some(kind) => rules.add(kind, &rule.value),
+            None => Err(format!("未知的规则类型: {}", rule.kind)),
+        };
+        if let Err(e) = added {
+            tracing::warn!("忽略目标地址规则 {}: {}", rule.id, e);
+        }
+    }
+
+    let count = rules.len();
+    if let Ok(mut cached) = state.url_rules.write() {
+        *cached = rules;
+    }
+    Ok(count)
+}
+
+async fn load_rule_file(path: &str) -> Result<UrlRuleFile, AppError> {
+    let content = tokio::fs::read_to_string(path).await?;
+    Ok(serde_yaml::from_str(&content)?)
+}
+
+/// 创建或修改链接时校验目标地址，被屏蔽或不在允许列表中时返回400
+pub fn check_url(state: &IState, url: &str) -> Result<(), AppError> {
+    let allowlist_only = state.blocklist_config.allowlist_only.unwrap_or(false);
+    match state.url_rules.read() {
+        Ok(rules) => rules
+            .check(url, allowlist_only)
+            .map_err(|reason| AppError::bad_request(anyhow::anyhow!(reason))),
+        Err(_) => Err(AppError::from(anyhow::anyhow!("读取目标地址规则失败"))),
+    }
+}
+
+/// 跳转时校验目标地址，新增的规则对存量链接同样生效
+pub fn is_blocked(state: &IState, url: &str) -> bool {
+    check_url(state, url).is_err()
+}
+
+/// 新增规则并立即重新加载，其他实例在下一次定时加载时生效
+pub async fn create_rule(
+    state: &IState,
+    auditor: &Auditor,
+    kind: UrlRuleKind,
+    value: String,
+    note: Option<String>,
+) -> HandlerResult<UrlRuleResponse> {
+    let value = match kind {
+        UrlRuleKind::BlockPattern => value.trim().to_string(),
+        UrlRuleKind::BlockDomain | UrlRuleKind::AllowDomain => normalize_domain(&value)
+            .ok_or_else(|| AppError::bad_request(anyhow::anyhow!("无效的域名: {}", value)))?,
+    };
+    UrlRules::default()
+        .add(kind, &value)
+        .map_err(|reason| AppError::bad_request(anyhow::anyhow!(reason)))?;
+
+    let mut tx = state.db_pool.begin().await?;
+    let rule = sqlx::query_as::<_, UrlRule>(
+        r#"
+        INSERT INTO url_rule (id, kind, value, note, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (kind, value) DO NOTHING
+        RETURNING *
+        "#,
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(kind.as_str())
+    .bind(&value)
+    .bind(&note)
+    .bind(&auditor.actor)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::bad_request(anyhow::anyhow!("规则已存在")))?
+    .to_response();
+    audit_service::record(
+        &mut tx,
+        auditor,
+        RULE_AUDIT_WORKSPACE_ID,
+        AuditAction::UrlRuleCreate,
+        rule.id,
+        None,
+        serde_json::to_value(&rule).ok(),
+    )
+    .await?;
+    tx.commit().await?;
+
+    refresh_rules(state).await?;
+    Ok(rule)
+}
+
+/// 查询管理接口维护的全部规则，不包含规则文件中的规则
+pub async fn list_rules(state: &IState) -> HandlerResult<Vec<UrlRuleResponse>> {
+    let rules = sqlx::query_as::<_, UrlRule>("SELECT * FROM url_rule ORDER BY create_time DESC")
+        .fetch_all(&state.db_pool)
+        .await?;
+    Ok(rules.iter().map(|rule| rule.to_response()).collect())
+}
+
+/// 删除规则并立即重新加载，返回被删除的规则
+pub async fn delete_rule(state: &IState, auditor: &Auditor, id: i64) -> HandlerResult<UrlRuleResponse> {
+    let mut tx = state.db_pool.begin().await?;
+    let rule = sqlx::query_as::<_, UrlRule>("DELETE FROM url_rule WHERE id = $1 RETURNING *")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("规则不存在")))?
+        .to_response();
+    audit_service::record(
+        &mut tx,
+        auditor,
+        RULE_AUDIT_WORKSPACE_ID,
+        AuditAction::UrlRuleDelete,
+        id,
+        serde_json::to_value(&rule).ok(),
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    refresh_rules(state).await?;
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Blocklist, Config};
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn malformed_file_keeps_last_rules() {
+        let id = YitIdHelper::next_id();
+        let path = std::env::temp_dir().join(format!("url_rules_{}.yaml", id));
+        let file_domain = format!("file-{}.example", id);
+        let db_domain = format!("db-{}.example", id);
+        std::fs::write(&path, format!("block_domains:\n  - {}\n", file_domain)).unwrap();
+        let cfg = Config {
+            blocklist: Some(Blocklist {
+                path: Some(path.to_string_lossy().to_string()),
+                ..Blocklist::default()
+            }),
+            ..Config::default()
+        };
+        let Some((state, _receivers)) = test_support::state_with_config(cfg).await else { return };
+
+        refresh_rules(&state).await.unwrap();
+        assert!(is_blocked(&state, &format!("https://{}/", file_domain)));
+
+        // 规则文件格式错误时保留上一次的文件规则，新增的数据库规则照常生效
+        std::fs::write(&path, "block_domains: [").unwrap();
+        let rule = create_rule(&state, &Auditor::system(), UrlRuleKind::BlockDomain, db_domain.clone(), None)
+            .await
+            .unwrap();
+        assert!(is_blocked(&state, &format!("https://{}/", file_domain)));
+        assert!(is_blocked(&state, &format!("https://{}/", db_domain)));
+
+        delete_rule(&state, &Auditor::system(), rule.id).await.unwrap();
+        assert!(is_blocked(&state, &format!("https://{}/", file_domain)));
+        assert!(!is_blocked(&state, &format!("https://{}/", db_domain)));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,748 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rand::RngCore;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Postgres, QueryBuilder};
+use tokio::select;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::config::Webhook;
+use crate::idgen::YitIdHelper;
+use crate::pojo::click_event::LiveClick;
+use crate::pojo::webhook::{
+    WebhookDeadLetter, WebhookDeadLetterListResponse, WebhookDelivery, WebhookSubscription,
+    WebhookSubscriptionResponse,
+};
+use crate::pojo::{AppError, Pagination};
+use crate::service::audit_service::{self, Auditor};
+use crate::service::link_service::{check_public_host, lookup_public_host};
+use crate::types::enums::{AuditAction, WebhookEvent};
+use crate::types::{HandlerResult, IState};
+use crate::utils::helper::hmac_sha256_hex;
+
+// 每条待投递记录占用5个参数
+const MAX_INSERT_ROWS: usize = 10000;
+
+/// 待投递记录：(id, 订阅id, 事件id, payload)
+type DeliveryRow = (i64, i64, i64, String);
+
+/// 是否有订阅了该事件的Webhook，用于在构造事件数据前提前返回
+pub fn is_subscribed(state: &IState, event: WebhookEvent) -> bool {
+    match state.webhook_subscriptions.read() {
+        Ok(subscriptions) => subscriptions.iter().any(|sub| sub.accepts(event.as_str())),
+        Err(_) => false,
+    }
+}
+
+/// 派发事件，为该工作空间内每个订阅了该事件的Webhook写入一条待投递记录，异步执行不阻塞调用方
+///
+/// 每个数据项是一个独立的事件，payload格式：`{"id", "type", "created_at", "data"}`；
+/// 点击事件随访问量增长，需通过 [`emit_click`] 排队批量写入
+pub fn dispatch<T: Serialize>(
+    state: &Arc<IState>,
+    workspace_id: i64,
+    event: WebhookEvent,
+    items: &[T],
+) {
+    let subscription_ids = match state.webhook_subscriptions.read() {
+        Ok(subscriptions) => subscriber_ids(&subscriptions, workspace_id, event),
+        Err(_) => return,
+    };
+    if subscription_ids.is_empty() || items.is_empty() {
+        return;
+    }
+
+    let created_at = Utc::now().timestamp_millis();
+    let mut rows: Vec<DeliveryRow> = Vec::new();
+    for item in items {
+        push_rows(&mut rows, &subscription_ids, event, created_at, item);
+    }
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = insert_deliveries(&state.db_pool, event, &rows).await {
+            let event_type = event.as_str();
+            tracing::error!("写入 {} 条 {} 事件的Webhook投递记录失败: {}", rows.len(), event_type, e);
+        }
+    });
+}
+
+/// 派发点击事件，放入队列由写入任务批量写入投递记录，队列已满时直接丢弃并计数，不阻塞重定向
+pub fn emit_click(state: &IState, click: LiveClick) {
+    let subscribed = match state.webhook_subscriptions.read() {
+        Ok(subscriptions) => subscriptions
+            .iter()
+            .any(|sub| sub.accepts_in(click.workspace_id, WebhookEvent::LinkClicked.as_str())),
+        Err(_) => false,
+    };
+    if !subscribed {
+        return;
+    }
+    match state.webhook_click_tx.try_send(click) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => {
+            state.webhook_click_dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 点击事件Webhook写入任务，攒满一批或到达写入间隔时为订阅写入投递记录
+pub async fn click_dispatch_task(
+    state: Arc<IState>,
+    mut click_rx: mpsc::Receiver<LiveClick>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) {
+    let config = &state.webhook_config;
+    let batch_size = config.click_batch_size.unwrap_or(500).max(1);
+    let flush_interval_ms = config.click_flush_interval_ms.unwrap_or(1000);
+
+    let mut interval = tokio::time::interval(Duration::from_millis(flush_interval_ms));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut buffer: Vec<LiveClick> = Vec::with_capacity(batch_size);
+
+    tracing::info!("点击事件Webhook写入任务已启动，批量大小: {}，写入间隔: {} 毫秒", batch_size, flush_interval_ms);
+
+    loop {
+        // 缓冲区满时已经写入，剩余容量至少为1
+        let limit = batch_size - buffer.len();
+        select! {
+            received = click_rx.recv_many(&mut buffer, limit) => {
+                if received == 0 {
+                    break;
+                }
+                if buffer.len() >= batch_size {
+                    write_click_batch(&state, &mut buffer).await;
+                }
+            }
+            _ = interval.tick() => {
+                if !buffer.is_empty() {
+                    write_click_batch(&state, &mut buffer).await;
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                // 写入队列中剩余的事件
+                while let Ok(click) = click_rx.try_recv() {
+                    buffer.push(click);
+                    if buffer.len() >= batch_size {
+                        write_click_batch(&state, &mut buffer).await;
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    if !buffer.is_empty() {
+        write_click_batch(&state, &mut buffer).await;
+    }
+    tracing::info!("点击事件Webhook写入任务已停止");
+}
+
+async fn write_click_batch(state: &IState, buffer: &mut Vec<LiveClick>) {
+    let event = WebhookEvent::LinkClicked;
+    let created_at = Utc::now().timestamp_millis();
+    let mut rows: Vec<DeliveryRow> = Vec::new();
+    if let Ok(subscriptions) = state.webhook_subscriptions.read() {
+        for click in buffer.iter() {
+            let subscription_ids = subscriber_ids(&subscriptions, click.workspace_id, event);
+            push_rows(&mut rows, &subscription_ids, event, created_at, click);
+        }
+    }
+    if let Err(e) = insert_deliveries(&state.db_pool, event, &rows).await {
+        tracing::error!("写入 {} 条点击事件的Webhook投递记录失败: {}", rows.len(), e);
+        state.webhook_click_dropped.fetch_add(buffer.len() as u64, Ordering::Relaxed);
+    }
+    buffer.clear();
+}
+
+/// 工作空间内订阅了该事件的Webhook
+fn subscriber_ids(
+    subscriptions: &[WebhookSubscription],
+    workspace_id: i64,
+    event: WebhookEvent,
+) -> Vec<i64> {
+    subscriptions
+        .iter()
+        .filter(|sub| sub.accepts_in(workspace_id, event.as_str()))
+        .map(|sub| sub.id)
+        .collect()
+}
+
+/// 为一个事件生成每个订阅的待投递记录，同一事件的各条记录共用事件id和payload
+fn push_rows<T: Serialize>(
+    rows: &mut Vec<DeliveryRow>,
+    subscription_ids: &[i64],
+    event: WebhookEvent,
+    created_at: i64,
+    item: &T,
+) {
+    if subscription_ids.is_empty() {
+        return;
+    }
+    let event_id = YitIdHelper::next_id();
+    let payload = serde_json::json!({
+        "id": event_id.to_string(),
+        "type": event.as_str(),
+        "created_at": created_at,
+        "data": item,
+    })
+    .to_string();
+    for subscription_id in subscription_ids {
+        rows.push((YitIdHelper::next_id(), *subscription_id, event_id, payload.clone()));
+    }
+}
+
+async fn insert_deliveries(
+    m_conn: &sqlx::PgPool,
+    event: WebhookEvent,
+    rows: &[DeliveryRow],
+) -> Result<(), AppError> {
+    for chunk in rows.chunks(MAX_INSERT_ROWS) {
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO webhook_delivery (id, subscription_id, event_id, event_type, payload) ",
+        );
+        builder.push_values(chunk, |mut row, (id, subscription_id, event_id, payload)| {
+            row.push_bind(id)
+                .push_bind(subscription_id)
+                .push_bind(event_id)
+                .push_bind(event.as_str())
+                .push_bind(payload);
+        });
+        builder.build().execute(m_conn).await?;
+    }
+    Ok(())
+}
+
+/// Webhook投递任务，定时领取到期的待投递记录并发送，同时定时刷新订阅缓存
+pub async fn webhook_delivery_task(state: Arc<IState>, mut shutdown_rx: broadcast::Receiver<()>) {
+    let config = &state.webhook_config;
+    let poll_interval_ms = config.poll_interval_ms.unwrap_or(1000);
+    let refresh_interval_secs = config.refresh_interval_secs.unwrap_or(30);
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs.unwrap_or(10)))
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("创建Webhook HTTP客户端失败: {}", e);
+            return;
+        }
+    };
+
+    let mut poll = tokio::time::interval(Duration::from_millis(poll_interval_ms));
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut refresh = tokio::time::interval(Duration::from_secs(refresh_interval_secs));
+    refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    tracing::info!("Webhook投递任务已启动，轮询间隔: {} 毫秒", poll_interval_ms);
+
+    loop {
+        select! {
+            _ = refresh.tick() => {
+                if let Err(e) = refresh_subscriptions(&state).await {
+                    tracing::error!("刷新Webhook订阅失败: {}", e);
+                }
+            }
+            _ = poll.tick() => {
+                match deliver_due(&state, &client).await {
+                    Ok(count) if count > 0 => tracing::debug!("本轮投递 {} 条Webhook", count),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("投递Webhook失败: {}", e),
+                }
+            }
+            _ = shutdown_rx.recv() => {
+                tracing::info!("收到关闭信号，停止Webhook投递任务");
+                break;
+            }
+        }
+    }
+
+    tracing::info!("Webhook投递任务已停止");
+}
+
+/// 从数据库重新加载有效的订阅
+pub async fn refresh_subscriptions(state: &IState) -> Result<(), AppError> {
+    let subscriptions = sqlx::query_as::<_, WebhookSubscription>(
+        "SELECT * FROM webhook_subscription WHERE active = true",
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    if let Ok(mut cache) = state.webhook_subscriptions.write() {
+        *cache = subscriptions;
+    }
+    Ok(())
+}
+
+/// 领取一批到期的待投递记录并发送，领取时顺延下次投递时间，避免多实例重复投递
+async fn deliver_due(state: &IState, client: &reqwest::Client) -> Result<usize, AppError> {
+    let config = &state.webhook_config;
+    let batch_size = config.batch_size.unwrap_or(100).max(1);
+    // 租约需长于请求超时，实例中途退出时到期后由其他实例重试
+    let lease_secs = config.timeout_secs.unwrap_or(10) + 60;
+
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        WITH claimed AS (
+            UPDATE webhook_delivery
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE id IN (
+                SELECT id FROM webhook_delivery
+                WHERE next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        )
+        SELECT c.id, c.event_id, c.event_type, c.payload, c.attempts,
+               s.url, s.secret
+        FROM claimed c JOIN webhook_subscription s ON s.id = c.subscription_id
+        "#,
+    )
+    .bind(batch_size as i64)
+    .bind(lease_secs as f64)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let count = deliveries.len();
+    let sends = deliveries.iter().map(|delivery| send(client, delivery));
+    let results = futures_util::future::join_all(sends).await;
+    for (delivery, result) in deliveries.iter().zip(results) {
+        if let Err(e) = record_result(state, delivery, result).await {
+            tracing::error!("更新Webhook投递 {} 的状态失败: {}", delivery.id, e);
+        }
+    }
+    Ok(count)
+}
+
+/// 发送一次Webhook请求，签名内容为 `{timestamp}.{payload}`
+///
+/// 每次发送前重新校验订阅地址，建立连接时的DNS解析同样只接受公网地址，避免DNS重绑定绕过创建时的校验
+async fn send(client: &reqwest::Client, delivery: &WebhookDelivery) -> Result<(), String> {
+    let parsed = parse_webhook_url(&delivery.url)?;
+    check_public_host(&parsed)?;
+    let timestamp = Utc::now().timestamp();
+    let signature =
+        hmac_sha256_hex(&delivery.secret, &format!("{}.{}", timestamp, delivery.payload));
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Webhook-Id", delivery.event_id.to_string())
+        .header("X-Webhook-Event", &delivery.event_type)
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Signature", format!("sha256={}", signature))
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", status))
+    }
+}
+
+/// 校验订阅地址：只支持http和https，主机不能是内网或本机地址，域名的全部解析结果都必须是公网地址
+async fn check_webhook_url(url: &str) -> Result<(), String> {
+    let parsed = parse_webhook_url(url)?;
+    if let Some((host, port)) = check_public_host(&parsed)? {
+        lookup_public_host(&host, port).await?;
+    }
+    Ok(())
+}
+
+fn parse_webhook_url(url: &str) -> Result<url::Url, String> {
+    let parsed = url::Url::parse(url).map_err(|_| "无效的Webhook地址".to_string())?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(format!("Webhook地址不支持的协议: {}", scheme)),
+    }
+}
+
+/// 投递请求使用的DNS解析，解析到内网或本机地址时拒绝连接
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            // 端口由请求地址决定，解析结果中的端口会被替换
+            let addrs = lookup_public_host(name.as_str(), 0).await?;
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// 投递成功删除记录；失败按指数退避安排重试，超过最大次数转入死信表
+async fn record_result(
+    state: &IState,
+    delivery: &WebhookDelivery,
+    result: Result<(), String>,
+) -> Result<(), AppError> {
+    let db_pool = &state.db_pool;
+    let error = match result {
+        Ok(()) => {
+            sqlx::query("DELETE FROM webhook_delivery WHERE id = $1")
+                .bind(delivery.id)
+                .execute(db_pool)
+                .await?;
+            return Ok(());
+        }
+        Err(error) => error,
+    };
+
+    let attempts = delivery.attempts + 1;
+    let max_attempts = state.webhook_config.max_attempts.unwrap_or(8);
+    if attempts >= max_attempts {
+        tracing::warn!(
+            "Webhook投递 {} 已失败 {} 次，转入死信表: {}",
+            delivery.id,
+            attempts,
+            error
+        );
+        let mut tx = db_pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_dead_letter
+                (id, subscription_id, event_id, event_type, payload, attempts, last_error, create_time)
+            SELECT id, subscription_id, event_id, event_type, payload, $2, $3, create_time
+            FROM webhook_delivery WHERE id = $1
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(attempts)
+        .bind(&error)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM webhook_delivery WHERE id = $1")
+            .bind(delivery.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        return Ok(());
+    }
+
+    let delay = retry_delay(&state.webhook_config, attempts);
+    tracing::debug!("Webhook投递 {} 失败: {}，{} 秒后重试", delivery.id, error, delay);
+    sqlx::query(
+        r#"
+        UPDATE webhook_delivery
+        SET attempts = $2, last_error = $3, next_attempt_at = NOW() + make_interval(secs => $4)
+        WHERE id = $1
+        "#,
+    )
+    .bind(delivery.id)
+    .bind(attempts)
+    .bind(&error)
+    .bind(delay as f64)
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+/// 第n次失败后的重试间隔：base * 2^(n-1)，不超过最大间隔
+fn retry_delay(config: &Webhook, attempts: i32) -> u64 {
+    let base = config.backoff_base_secs.unwrap_or(10);
+    let max = config.backoff_max_secs.unwrap_or(3600);
+    let exponent = (attempts - 1).clamp(0, 32) as u32;
+    base.saturating_mul(1u64 << exponent).min(max)
+}
+
+/// 在工作空间内创建订阅，未指定密钥时随机生成，密钥只在创建时返回
+pub async fn create_subscription(
+    state: Arc<IState>,
+    auditor: &Auditor,
+    workspace_id: i64,
+    url: String,
+    events: Vec<String>,
+    secret: Option<String>,
+) -> HandlerResult<WebhookSubscriptionResponse> {
+    if let Some(unknown) = events.iter().find(|event| WebhookEvent::parse(event).is_none()) {
+        return Err(AppError::bad_request(anyhow::anyhow!("不支持的事件类型: {}", unknown)));
+    }
+    check_webhook_url(&url)
+        .await
+        .map_err(|reason| AppError::bad_request(anyhow::anyhow!(reason)))?;
+    let secret = match secret.filter(|secret| !secret.trim().is_empty()) {
+        Some(secret) => secret,
+        None => {
+            let mut bytes = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            hex::encode(bytes)
+        }
+    };
+
+    let mut tx = state.db_pool.begin().await?;
+    let subscription = sqlx::query_as::<_, WebhookSubscription>(
+        r#"
+        INSERT INTO webhook_subscription (id, workspace_id, url, secret, events)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(workspace_id)
+    .bind(&url)
+    .bind(&secret)
+    .bind(&events)
+    .fetch_one(&mut *tx)
+    .await?;
+    // 签名密钥不写入审计日志
+    let after = json!({
+        "url": subscription.url,
+        "events": subscription.events,
+        "active": subscription.active,
+    });
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace_id,
+        AuditAction::WebhookCreate,
+        subscription.id,
+        None,
+        Some(after),
+    )
+    .await?;
+    tx.commit().await?;
+
+    refresh_subscriptions(&state).await?;
+    Ok(subscription.to_response(true))
+}
+
+/// 查询工作空间内的全部订阅，不返回密钥
+pub async fn list_subscriptions(
+    state: Arc<IState>,
+    workspace_id: i64,
+) -> HandlerResult<Vec<WebhookSubscriptionResponse>> {
+    let subscriptions = sqlx::query_as::<_, WebhookSubscription>(
+        "SELECT * FROM webhook_subscription WHERE workspace_id = $1 ORDER BY create_time DESC",
+    )
+    .bind(workspace_id)
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(subscriptions.iter().map(|sub| sub.to_response(false)).collect())
+}
+
+/// 删除订阅，待投递记录和死信一并删除，返回被删除的订阅（不含密钥）
+pub async fn delete_subscription(
+    state: Arc<IState>,
+    auditor: &Auditor,
+    workspace_id: i64,
+    id: i64,
+) -> HandlerResult<WebhookSubscriptionResponse> {
+    let mut tx = state.db_pool.begin().await?;
+    let subscription = sqlx::query_as::<_, WebhookSubscription>(
+        "DELETE FROM webhook_subscription WHERE id = $1 AND workspace_id = $2 RETURNING *",
+    )
+    .bind(id)
+    .bind(workspace_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found(anyhow::anyhow!("订阅不存在")))?
+    .to_response(false);
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace_id,
+        AuditAction::WebhookDelete,
+        id,
+        serde_json::to_value(&subscription).ok(),
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    refresh_subscriptions(&state).await?;
+    Ok(subscription)
+}
+
+/// 分页查询工作空间内的死信，按转入时间倒序
+pub async fn list_dead_letters(
+    state: Arc<IState>,
+    workspace_id: i64,
+    pagination: Pagination,
+) -> HandlerResult<WebhookDeadLetterListResponse> {
+    let db_pool = &state.db_pool;
+    let page = pagination.page.max(1);
+    let page_size = pagination.page_size.clamp(1, 100);
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM webhook_dead_letter d
+        JOIN webhook_subscription s ON s.id = d.subscription_id
+        WHERE s.workspace_id = $1
+        "#,
+    )
+    .bind(workspace_id)
+    .fetch_one(db_pool)
+    .await?;
+    let dead_letters = sqlx::query_as::<_, WebhookDeadLetter>(
+        r#"
+        SELECT d.* FROM webhook_dead_letter d
+        JOIN webhook_subscription s ON s.id = d.subscription_id
+        WHERE s.workspace_id = $1
+        ORDER BY d.failed_time DESC LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(workspace_id)
+    .bind(page_size as i64)
+    .bind(((page - 1) * page_size) as i64)
+    .fetch_all(db_pool)
+    .await?;
+
+    let total_pages = ((total as f64) / (page_size as f64)).ceil() as usize;
+    Ok(WebhookDeadLetterListResponse {
+        data: dead_letters.iter().map(|dead_letter| dead_letter.to_response()).collect(),
+        page,
+        page_size,
+        total,
+        last_page: page >= total_pages,
+    })
+}
+
+/// 将死信重新放入待投递表，投递次数清零，原payload和事件id不变
+pub async fn replay_dead_letter(
+    state: Arc<IState>,
+    auditor: &Auditor,
+    workspace_id: i64,
+    id: i64,
+) -> HandlerResult<()> {
+    let mut tx = state.db_pool.begin().await?;
+    let result = sqlx::query(
+        r#"
+        INSERT INTO webhook_delivery (id, subscription_id, event_id, event_type, payload)
+        SELECT $2, d.subscription_id, d.event_id, d.event_type, d.payload
+        FROM webhook_dead_letter d JOIN webhook_subscription s ON s.id = d.subscription_id
+        WHERE d.id = $1 AND s.workspace_id = $3
+        "#,
+    )
+    .bind(id)
+    .bind(YitIdHelper::next_id())
+    .bind(workspace_id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(anyhow::anyhow!("死信不存在")));
+    }
+    sqlx::query("DELETE FROM webhook_dead_letter WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    audit_service::record(&mut tx, auditor, workspace_id, AuditAction::WebhookReplay, id, None, None)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn subscription(workspace_id: i64, events: &[&str]) -> WebhookSubscription {
+        WebhookSubscription {
+            id: YitIdHelper::next_id(),
+            workspace_id,
+            url: "https://93.184.216.34/hooks".to_string(),
+            secret: "secret".to_string(),
+            events: events.iter().map(|event| event.to_string()).collect(),
+            active: true,
+            create_time: None,
+            update_time: None,
+        }
+    }
+
+    fn click(workspace_id: i64) -> LiveClick {
+        LiveClick {
+            workspace_id,
+            link_code: "abc".to_string(),
+            click_time: Utc::now().timestamp_millis(),
+            referrer_domain: None,
+            browser: None,
+            os: None,
+            device: None,
+            is_bot: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn webhook_url_must_be_public() {
+        assert!(check_webhook_url("https://93.184.216.34/hooks").await.is_ok());
+        assert!(check_webhook_url("ftp://93.184.216.34/hooks").await.is_err());
+        assert!(check_webhook_url("file:///etc/passwd").await.is_err());
+        assert!(check_webhook_url("http://169.254.169.254/latest/meta-data").await.is_err());
+        assert!(check_webhook_url("http://127.0.0.1:6379/").await.is_err());
+        assert!(check_webhook_url("http://[::1]/").await.is_err());
+        assert!(check_webhook_url("http://10.0.0.8/hooks").await.is_err());
+        assert!(check_webhook_url("http://redis.localhost/").await.is_err());
+        // .invalid 保留域名不会被解析
+        assert!(check_webhook_url("https://hooks.invalid/").await.is_err());
+    }
+
+    #[test]
+    fn clicks_are_opt_in() {
+        let all = subscription(1, &[]);
+        assert!(all.accepts("link.created"));
+        assert!(!all.accepts("link.clicked"));
+        assert!(subscription(1, &["link.clicked"]).accepts("link.clicked"));
+        assert!(!subscription(2, &["link.clicked"]).accepts_in(1, "link.clicked"));
+    }
+
+    #[tokio::test]
+    async fn click_batch_writes_deliveries() {
+        let Some((state, mut receivers)) = test_support::state_with_config(Default::default()).await else { return };
+        let ws = test_support::workspace_id();
+        let admin = test_support::actor(ws, crate::types::enums::Role::Admin);
+        let auditor = test_support::auditor(&admin);
+        let clicks = create_subscription(
+            state.clone(),
+            &auditor,
+            ws,
+            "https://93.184.216.34/hooks/clicks".to_string(),
+            vec!["link.clicked".to_string()],
+            None,
+        )
+        .await
+        .unwrap();
+        create_subscription(state.clone(), &auditor, ws, "https://93.184.216.34/hooks/all".to_string(), vec![], None)
+            .await
+            .unwrap();
+
+        emit_click(&state, click(ws));
+        emit_click(&state, click(test_support::workspace_id()));
+        let mut buffer = Vec::new();
+        receivers.webhook_click_rx.recv_many(&mut buffer, 10).await;
+        // 其他工作空间没有订阅，不进入队列
+        assert_eq!(buffer.len(), 1);
+        write_click_batch(&state, &mut buffer).await;
+
+        let subscription_ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT d.subscription_id FROM webhook_delivery d \
+             JOIN webhook_subscription s ON s.id = d.subscription_id WHERE s.workspace_id = $1",
+        )
+        .bind(ws)
+        .fetch_all(&state.db_pool)
+        .await
+        .unwrap();
+        assert_eq!(subscription_ids, vec![clicks.id]);
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let config = Webhook::default();
+        assert_eq!(retry_delay(&config, 1), 10);
+        assert_eq!(retry_delay(&config, 2), 20);
+        assert_eq!(retry_delay(&config, 4), 80);
+        assert_eq!(retry_delay(&config, 10), 3600);
+        assert_eq!(retry_delay(&config, 100), 3600);
+    }
+}
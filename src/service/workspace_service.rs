@@ -0,0 +1,184 @@
+use serde_json::json;
+
+use crate::idgen::YitIdHelper;
+use crate::pojo::workspace::{AppUser, AppUserResponse, Workspace, WorkspaceResponse};
+use crate::pojo::AppError;
+use crate::service::audit_service::{self, Auditor};
+use crate::types::enums::{AuditAction, Role};
+
+/// 创建工作空间
+pub async fn create_workspace(
+    m_conn: &sqlx::PgPool,
+    auditor: &Auditor,
+    name: String,
+) -> Result<WorkspaceResponse, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::bad_request(anyhow::anyhow!("名称不能为空")));
+    }
+    let mut tx = m_conn.begin().await?;
+    let workspace = sqlx::query_as::<_, Workspace>(
+        "INSERT INTO workspace (id, name) VALUES ($1, $2) RETURNING *",
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(&name)
+    .fetch_one(&mut *tx)
+    .await?
+    .to_response();
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace.id,
+        AuditAction::WorkspaceCreate,
+        workspace.id,
+        None,
+        serde_json::to_value(&workspace).ok(),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(workspace)
+}
+
+/// 查询全部工作空间
+pub async fn list_workspaces(m_conn: &sqlx::PgPool) -> Result<Vec<WorkspaceResponse>, AppError> {
+    let workspaces = sqlx::query_as::<_, Workspace>("SELECT * FROM workspace ORDER BY id")
+        .fetch_all(m_conn)
+        .await?;
+    Ok(workspaces.iter().map(|workspace| workspace.to_response()).collect())
+}
+
+/// 查询工作空间，不存在时返回错误
+pub async fn get_workspace(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+) -> Result<WorkspaceResponse, AppError> {
+    let workspace = sqlx::query_as::<_, Workspace>("SELECT * FROM workspace WHERE id = $1")
+        .bind(workspace_id)
+        .fetch_optional(m_conn)
+        .await?
+        .ok_or_else(|| AppError::not_found(anyhow::anyhow!("工作空间不存在: {}", workspace_id)))?;
+    Ok(workspace.to_response())
+}
+
+/// 在工作空间内创建用户，邮箱全局唯一
+pub async fn create_user(
+    m_conn: &sqlx::PgPool,
+    auditor: &Auditor,
+    workspace_id: i64,
+    name: String,
+    email: Option<String>,
+    role: Role,
+) -> Result<AppUserResponse, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::bad_request(anyhow::anyhow!("名称不能为空")));
+    }
+    get_workspace(m_conn, workspace_id).await?;
+    let email = email
+        .map(|email| email.trim().to_lowercase())
+        .filter(|email| !email.is_empty());
+
+    let mut tx = m_conn.begin().await?;
+    let result = sqlx::query_as::<_, AppUser>(
+        r#"
+        INSERT INTO app_user (id, workspace_id, name, email, role)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(YitIdHelper::next_id())
+    .bind(workspace_id)
+    .bind(&name)
+    .bind(&email)
+    .bind(role.as_str())
+    .fetch_one(&mut *tx)
+    .await;
+    let user = match result {
+        Ok(user) => user.to_response(),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Err(AppError::bad_request(anyhow::anyhow!("邮箱已被使用")));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace_id,
+        AuditAction::UserCreate,
+        user.id,
+        None,
+        serde_json::to_value(&user).ok(),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(user)
+}
+
+/// 修改工作空间内用户的角色
+pub async fn update_user_role(
+    m_conn: &sqlx::PgPool,
+    auditor: &Auditor,
+    workspace_id: i64,
+    user_id: i64,
+    role: Role,
+) -> Result<AppUserResponse, AppError> {
+    let mut tx = m_conn.begin().await?;
+    let before = sqlx::query_as::<_, AppUser>(
+        "SELECT * FROM app_user WHERE id = $1 AND workspace_id = $2 FOR UPDATE",
+    )
+    .bind(user_id)
+    .bind(workspace_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::not_found(anyhow::anyhow!("用户不存在: {}", user_id)))?;
+    let user = sqlx::query_as::<_, AppUser>(
+        "UPDATE app_user SET role = $1 WHERE id = $2 RETURNING *",
+    )
+    .bind(role.as_str())
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?
+    .to_response();
+    audit_service::record(
+        &mut tx,
+        auditor,
+        workspace_id,
+        AuditAction::UserRoleUpdate,
+        user_id,
+        Some(json!({ "role": before.role })),
+        Some(json!({ "role": user.role })),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(user)
+}
+
+/// 查询工作空间内的用户
+pub async fn list_users(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+) -> Result<Vec<AppUserResponse>, AppError> {
+    let users = sqlx::query_as::<_, AppUser>(
+        "SELECT * FROM app_user WHERE workspace_id = $1 ORDER BY create_time DESC",
+    )
+    .bind(workspace_id)
+    .fetch_all(m_conn)
+    .await?;
+    Ok(users.iter().map(|user| user.to_response()).collect())
+}
+
+/// 用户是否属于该工作空间
+pub async fn user_in_workspace(
+    m_conn: &sqlx::PgPool,
+    workspace_id: i64,
+    user_id: i64,
+) -> Result<bool, AppError> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM app_user WHERE id = $1 AND workspace_id = $2)",
+    )
+    .bind(user_id)
+    .bind(workspace_id)
+    .fetch_one(m_conn)
+    .await?;
+    Ok(exists)
+}
@@ -0,0 +1,154 @@
+//! 依赖数据库和Redis的测试使用的状态
+//!
+//! 设置 `TEST_DATABASE_URL`（以及需要Redis的测试设置 `TEST_REDIS_URL`）后运行，未设置时测试直接跳过：
+//!
+//! ```bash
+//! TEST_DATABASE_URL=postgres://postgres@localhost/short_link_test TEST_REDIS_URL=redis://localhost/15 cargo test
+//! ```
+//!
+//! 首次连接时执行 `sql/ddl.sql`，各测试使用独立的工作空间id，互不影响
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+
+use bb8_redis::RedisConnectionManager;
+use sqlx::postgres::PgPoolOptions;
+use tokio::sync::{broadcast, mpsc, OnceCell, RwLock};
+
+use crate::config::Config;
+use crate::idgen::YitIdHelper;
+use crate::link_base_service::{query_by_id, save};
+use crate::pojo::api_key::ApiActor;
+use crate::pojo::link_history::{LinkHistory, LinkOwner};
+use crate::pojo::url_rule::{UrlRuleFile, UrlRules};
+use crate::service::audit_service::Auditor;
+use crate::types::enums::Role;
+use crate::types::{CleanupStats, ClickEventStats, EventReceivers, IState, RequestId};
+use crate::utils::helper::calculate_sha256;
+
+static SCHEMA: OnceCell<()> = OnceCell::const_new();
+
+/// 未配置Redis时使用不可达的地址，只访问数据库的测试不受影响
+const UNREACHABLE_REDIS: &str = "redis://127.0.0.1:1";
+
+/// 测试用的状态，未设置TEST_DATABASE_URL时返回None
+pub async fn state() -> Option<Arc<IState>> {
+    let (state, _) = state_with_config(Config::default()).await?;
+    Some(state)
+}
+
+/// 需要Redis的测试使用的状态，TEST_DATABASE_URL和TEST_REDIS_URL都设置时才返回
+pub async fn redis_state() -> Option<Arc<IState>> {
+    std::env::var("TEST_REDIS_URL").ok()?;
+    state().await
+}
+
+/// 使用指定配置创建测试状态，同时返回事件队列的接收端
+pub async fn state_with_config(cfg: Config) -> Option<(Arc<IState>, EventReceivers)> {
+    let db_url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let db_pool = PgPoolOptions::new()
+        .max_connections(4)
+        .connect(&db_url)
+        .await
+        .expect("连接测试数据库失败");
+    SCHEMA
+        .get_or_init(|| async {
+            sqlx::raw_sql(include_str!("../sql/ddl.sql"))
+                .execute(&db_pool)
+                .await
+                .expect("初始化测试数据库失败");
+        })
+        .await;
+
+    let redis_url = std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| UNREACHABLE_REDIS.to_string());
+    let redis_client = bb8_redis::redis::Client::open(redis_url.as_str()).expect("无效的Redis地址");
+    let redis_pool = bb8::Pool::builder()
+        .max_size(4)
+        .connection_timeout(std::time::Duration::from_millis(500))
+        .build_unchecked(RedisConnectionManager::new(redis_url).expect("无效的Redis地址"));
+
+    let mut analytics_config = cfg.analytics.unwrap_or_default();
+    analytics_config.normalize_bot_patterns();
+    let access_config = cfg.access.unwrap_or_default();
+    let (click_tx, click_rx) = mpsc::channel(analytics_config.channel_capacity.unwrap_or(10000));
+    let (access_tx, access_rx) = mpsc::channel(access_config.channel_capacity.unwrap_or(10000).max(1));
+    let (live_publish_tx, live_publish_rx) =
+        mpsc::channel(analytics_config.channel_capacity.unwrap_or(10000).max(1));
+    let webhook_config = cfg.webhook.unwrap_or_default();
+    let (webhook_click_tx, webhook_click_rx) =
+        mpsc::channel(webhook_config.click_channel_capacity.unwrap_or(10000).max(1));
+    let state = Arc::new(IState {
+        db_pool,
+        redis_pool,
+        redis_client,
+        redis_db: None,
+        cleanup_config: cfg.cleanup.unwrap_or_default(),
+        link_config: cfg.link.unwrap_or_default(),
+        access_config,
+        analytics_config,
+        webhook_config,
+        auth_config: cfg.auth.unwrap_or_default(),
+        rate_limit_config: cfg.rate_limit.unwrap_or_default(),
+        blocklist_config: cfg.blocklist.unwrap_or_default(),
+        abuse_config: cfg.abuse.unwrap_or_default(),
+        signed_link_config: cfg.signed_link.unwrap_or_default(),
+        trusted_proxies: cfg.server.unwrap_or_default().trusted_proxies(),
+        click_tx,
+        click_stats: Arc::new(ClickEventStats::default()),
+        access_tx,
+        live_publish_tx,
+        live_tx: broadcast::channel(16).0,
+        stream_shutdown: broadcast::channel(1).0,
+        cleanup_stats: Arc::new(RwLock::new(CleanupStats::default())),
+        webhook_click_tx,
+        webhook_click_dropped: Arc::new(AtomicU64::new(0)),
+        webhook_subscriptions: Arc::new(std::sync::RwLock::new(Vec::new())),
+        ip_salt: Arc::new(std::sync::RwLock::new(None)),
+        jwks: Arc::new(std::sync::RwLock::new(None)),
+        url_rules: Arc::new(std::sync::RwLock::new(UrlRules::default())),
+        url_rule_file: Arc::new(std::sync::RwLock::new(UrlRuleFile::default())),
+    });
+    Some((state, EventReceivers { click_rx, access_rx, live_publish_rx, webhook_click_rx }))
+}
+
+/// 每个测试使用独立的工作空间
+pub fn workspace_id() -> i64 {
+    YitIdHelper::next_id()
+}
+
+/// 工作空间内的调用方
+pub fn actor(workspace_id: i64, role: Role) -> ApiActor {
+    ApiActor {
+        key_id: None,
+        name: format!("test-{}", role.as_str()),
+        role,
+        workspace_id,
+        user_id: Some(YitIdHelper::next_id()),
+    }
+}
+
+/// 调用方在测试请求中的审计操作人
+pub fn auditor(actor: &ApiActor) -> Auditor {
+    let request_id = RequestId {
+        id: format!("test-{}", YitIdHelper::next_id()),
+        upstream: None,
+    };
+    Auditor::new(actor, &request_id)
+}
+
+/// 直接写入数据库创建链接，不经过Redis，返回写入后的链接
+pub async fn insert_link(state: &IState, workspace_id: i64, origin_url: &str) -> LinkHistory {
+    let id = YitIdHelper::next_id();
+    let domain = state.link_config.default_domain();
+    let link = LinkHistory::from_url(
+        id,
+        workspace_id,
+        LinkOwner::default(),
+        &domain,
+        origin_url,
+        calculate_sha256(origin_url),
+        None,
+    );
+    assert!(save(&state.db_pool, &link, &Auditor::system()).await.unwrap().is_some());
+    query_by_id(&state.db_pool, workspace_id, id).await.unwrap().unwrap()
+}
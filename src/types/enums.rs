@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 pub enum LinkType {
     /// 短期的
     #[allow(dead_code)]
@@ -15,3 +17,445 @@ impl LinkType {
         }
     }
 }
+
+/// 统计数据的时间粒度
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    Day,
+}
+
+impl Granularity {
+    /// 对应的汇总表
+    pub fn table(&self) -> &'static str {
+        match self {
+            Granularity::Hour => "click_stats_hourly",
+            Granularity::Day => "click_stats_daily",
+        }
+    }
+
+    /// PostgreSQL中的时间单位
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Granularity::Hour => "hour",
+            Granularity::Day => "day",
+        }
+    }
+
+    /// 单次查询允许的最大时间跨度
+    pub fn max_range(&self) -> chrono::Duration {
+        match self {
+            Granularity::Hour => chrono::Duration::days(31),
+            Granularity::Day => chrono::Duration::days(366),
+        }
+    }
+}
+
+/// 点击事件中IP的保存方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpMode {
+    /// IPv4保留前24位，IPv6保留前48位
+    Truncate,
+    /// 加盐哈希，盐按周期轮换
+    Hash,
+}
+
+/// API Key的权限范围，分别授予对应的角色
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// 查询链接和统计数据，对应viewer
+    Read,
+    /// 创建和修改链接，对应editor
+    Create,
+    /// 全部权限，对应admin
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Create => "create",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ApiScope> {
+        [ApiScope::Read, ApiScope::Create, ApiScope::Admin]
+            .into_iter()
+            .find(|scope| scope.as_str() == value)
+    }
+
+    pub fn role(&self) -> Role {
+        match self {
+            ApiScope::Read => Role::Viewer,
+            ApiScope::Create => Role::Editor,
+            ApiScope::Admin => Role::Admin,
+        }
+    }
+}
+
+/// 用户角色，按权限从低到高排序
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// 只读
+    Viewer,
+    /// 创建链接，只能修改自己创建的链接
+    Editor,
+    /// 全部权限
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Viewer, Role::Editor, Role::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.as_str() == value)
+    }
+
+    /// 角色拥有的权限
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Viewer => &[LinkRead, StatsRead],
+            Role::Editor => &[LinkRead, StatsRead, LinkCreate, LinkEdit],
+            Role::Admin => &[
+                LinkRead,
+                StatsRead,
+                LinkCreate,
+                LinkEdit,
+                LinkEditAny,
+                AnalyticsPurge,
+                WebhookManage,
+                ApiKeyManage,
+                UserManage,
+                UrlRuleManage,
+                AbuseManage,
+                AuditRead,
+            ],
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+/// 管理接口的操作权限，每个接口校验所需的权限
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Permission {
+    /// 查询链接和修改历史
+    LinkRead,
+    /// 查询统计数据、实时点击流和健康检查
+    StatsRead,
+    /// 创建链接
+    LinkCreate,
+    /// 修改、回滚、延期自己创建的链接
+    LinkEdit,
+    /// 修改、回滚、延期工作空间内的任意链接
+    LinkEditAny,
+    /// 清除统计数据
+    AnalyticsPurge,
+    /// 管理Webhook订阅和死信
+    WebhookManage,
+    /// 管理API Key
+    ApiKeyManage,
+    /// 管理用户和角色
+    UserManage,
+    /// 管理目标地址的屏蔽和允许规则，只限默认工作空间
+    UrlRuleManage,
+    /// 处理滥用举报，下架或解除隔离链接
+    AbuseManage,
+    /// 查询审计日志
+    AuditRead,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::LinkRead => "link:read",
+            Permission::StatsRead => "stats:read",
+            Permission::LinkCreate => "link:create",
+            Permission::LinkEdit => "link:edit",
+            Permission::LinkEditAny => "link:edit_any",
+            Permission::AnalyticsPurge => "analytics:purge",
+            Permission::WebhookManage => "webhook:manage",
+            Permission::ApiKeyManage => "apikey:manage",
+            Permission::UserManage => "user:manage",
+            Permission::UrlRuleManage => "url_rule:manage",
+            Permission::AbuseManage => "abuse:manage",
+            Permission::AuditRead => "audit:read",
+        }
+    }
+}
+
+/// Webhook事件类型
+#[allow(clippy::enum_variant_names)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WebhookEvent {
+    #[serde(rename = "link.created")]
+    LinkCreated,
+    #[serde(rename = "link.updated")]
+    LinkUpdated,
+    #[serde(rename = "link.expired")]
+    LinkExpired,
+    /// 点击事件经队列批量写入投递记录，需显式订阅
+    #[serde(rename = "link.clicked")]
+    LinkClicked,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::LinkCreated,
+        WebhookEvent::LinkUpdated,
+        WebhookEvent::LinkExpired,
+        WebhookEvent::LinkClicked,
+    ];
+
+    pub fn parse(value: &str) -> Option<WebhookEvent> {
+        WebhookEvent::ALL.into_iter().find(|event| event.as_str() == value)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::LinkCreated => "link.created",
+            WebhookEvent::LinkUpdated => "link.updated",
+            WebhookEvent::LinkExpired => "link.expired",
+            WebhookEvent::LinkClicked => "link.clicked",
+        }
+    }
+}
+
+/// 目标地址规则的类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlRuleKind {
+    /// 屏蔽域名及其子域名
+    BlockDomain,
+    /// 屏蔽匹配正则的完整地址
+    BlockPattern,
+    /// 允许域名及其子域名，只在仅允许模式下生效
+    AllowDomain,
+}
+
+impl UrlRuleKind {
+    pub const ALL: [UrlRuleKind; 3] = [
+        UrlRuleKind::BlockDomain,
+        UrlRuleKind::BlockPattern,
+        UrlRuleKind::AllowDomain,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UrlRuleKind::BlockDomain => "block_domain",
+            UrlRuleKind::BlockPattern => "block_pattern",
+            UrlRuleKind::AllowDomain => "allow_domain",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<UrlRuleKind> {
+        UrlRuleKind::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+}
+
+/// 链接失效的原因
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeactivateReason {
+    /// 超过过期时间
+    Expired,
+    /// 超过空闲有效期未被访问
+    Idle,
+    /// 确认举报后下架
+    Abuse,
+}
+
+impl DeactivateReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeactivateReason::Expired => "expired",
+            DeactivateReason::Idle => "idle",
+            DeactivateReason::Abuse => "abuse",
+        }
+    }
+
+    /// 过期和空闲失效的链接可以通过延期重新激活
+    pub fn is_reactivatable(reason: Option<&str>) -> bool {
+        matches!(reason, Some("expired") | Some("idle"))
+    }
+}
+
+/// 滥用举报的原因
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AbuseReason {
+    /// 钓鱼网站
+    Phishing,
+    /// 恶意软件
+    Malware,
+    /// 垃圾信息
+    Spam,
+    Other,
+}
+
+impl AbuseReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AbuseReason::Phishing => "phishing",
+            AbuseReason::Malware => "malware",
+            AbuseReason::Spam => "spam",
+            AbuseReason::Other => "other",
+        }
+    }
+}
+
+/// 滥用举报的处理状态
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    /// 待处理
+    Open,
+    /// 已确认，链接已下架
+    Confirmed,
+    /// 已驳回，链接已解除隔离
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Confirmed => "confirmed",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+}
+
+/// 限流算法
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateAlgorithm {
+    /// 滑动窗口，按上一窗口的剩余比例加权计数
+    SlidingWindow,
+    /// 令牌桶，容量为limit，每个窗口补满，允许短时突发
+    TokenBucket,
+}
+
+/// 限流的路由分组，各分组使用独立的策略和计数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateGroup {
+    /// 短链跳转
+    Redirect,
+    /// 创建链接
+    Create,
+    /// 其他管理接口
+    Admin,
+}
+
+impl RateGroup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateGroup::Redirect => "redirect",
+            RateGroup::Create => "create",
+            RateGroup::Admin => "admin",
+        }
+    }
+}
+
+/// 审计日志的操作类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    LinkCreate,
+    LinkUpdate,
+    LinkRollback,
+    LinkExtend,
+    /// 清理任务将过期或空闲的链接置为失效
+    LinkDeactivate,
+    /// 清理任务物理删除失效的链接
+    LinkPurge,
+    LinkQuarantine,
+    LinkTakedown,
+    LinkRelease,
+    /// 开启签名校验，之后只能通过签名链接访问
+    LinkRequireSignature,
+    AnalyticsErase,
+    WebhookCreate,
+    WebhookDelete,
+    WebhookReplay,
+    ApiKeyCreate,
+    ApiKeyRevoke,
+    UserCreate,
+    UserRoleUpdate,
+    UrlRuleCreate,
+    UrlRuleDelete,
+    WorkspaceCreate,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::LinkCreate => "link.create",
+            AuditAction::LinkUpdate => "link.update",
+            AuditAction::LinkRollback => "link.rollback",
+            AuditAction::LinkExtend => "link.extend",
+            AuditAction::LinkDeactivate => "link.deactivate",
+            AuditAction::LinkPurge => "link.purge",
+            AuditAction::LinkQuarantine => "link.quarantine",
+            AuditAction::LinkTakedown => "link.takedown",
+            AuditAction::LinkRelease => "link.release",
+            AuditAction::LinkRequireSignature => "link.require_signature",
+            AuditAction::AnalyticsErase => "analytics.erase",
+            AuditAction::WebhookCreate => "webhook.create",
+            AuditAction::WebhookDelete => "webhook.delete",
+            AuditAction::WebhookReplay => "webhook.replay",
+            AuditAction::ApiKeyCreate => "apikey.create",
+            AuditAction::ApiKeyRevoke => "apikey.revoke",
+            AuditAction::UserCreate => "user.create",
+            AuditAction::UserRoleUpdate => "user.role_update",
+            AuditAction::UrlRuleCreate => "url_rule.create",
+            AuditAction::UrlRuleDelete => "url_rule.delete",
+            AuditAction::WorkspaceCreate => "workspace.create",
+        }
+    }
+
+    /// 操作对象类型，统计数据的清除记录在链接上
+    pub fn target_type(&self) -> &'static str {
+        match self {
+            AuditAction::WebhookCreate | AuditAction::WebhookDelete | AuditAction::WebhookReplay => {
+                "webhook"
+            }
+            AuditAction::ApiKeyCreate | AuditAction::ApiKeyRevoke => "apikey",
+            AuditAction::UserCreate | AuditAction::UserRoleUpdate => "user",
+            AuditAction::UrlRuleCreate | AuditAction::UrlRuleDelete => "url_rule",
+            AuditAction::WorkspaceCreate => "workspace",
+            _ => "link",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_permissions() {
+        assert!(Role::Viewer.allows(Permission::StatsRead));
+        assert!(!Role::Viewer.allows(Permission::LinkCreate));
+        assert!(Role::Editor.allows(Permission::LinkEdit));
+        assert!(!Role::Editor.allows(Permission::LinkEditAny));
+        assert!(!Role::Editor.allows(Permission::AnalyticsPurge));
+        assert!(Role::Admin.allows(Permission::AnalyticsPurge));
+        assert!(Role::Viewer < Role::Editor && Role::Editor < Role::Admin);
+    }
+}
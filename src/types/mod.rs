@@ -1,10 +1,19 @@
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::Redirect;
-use tokio::sync::RwLock;
+use jsonwebtoken::jwk::JwkSet;
+use tokio::sync::{broadcast, mpsc, RwLock};
 
 use crate::Message;
-use crate::config::Cleanup;
+use crate::config::{
+    Abuse, Access, Analytics, Auth, Blocklist, Cleanup, Link, RateLimit, SignedLink, Webhook,
+};
+use crate::pojo::access_event::AccessEvent;
+use crate::pojo::click_event::{ClickEvent, LiveClick};
+use crate::pojo::url_rule::{UrlRuleFile, UrlRules};
+use crate::pojo::webhook::WebhookSubscription;
+use crate::utils::helper::IpNetwork;
 
 pub mod enums;
 
@@ -17,6 +26,15 @@ pub type RedirectResult = HandlerResult<Redirect>;
 #[allow(dead_code)]
 pub type MessageResult<T> = HandlerResult<Message<T>>;
 
+/// 当前请求的id，由请求日志中间件生成并写入请求扩展，通过响应头X-Request-Id返回
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    /// 服务端生成的全局唯一id
+    pub id: String,
+    /// 可信代理传入的X-Request-Id，只用于与上游网关的日志关联
+    pub upstream: Option<String>,
+}
+
 /// 清理任务健康检查数据
 #[derive(Debug, Clone, Default)]
 pub struct CleanupStats {
@@ -28,13 +46,71 @@ pub struct CleanupStats {
     pub total_cleanup_runs: u64,
     /// 总清理数量
     pub total_cleaned: u64,
+    /// 最后一次物理删除数量
+    pub last_purge_count: usize,
+    /// 总物理删除数量
+    pub total_purged: u64,
+}
+
+/// 点击事件写入统计
+#[derive(Debug, Default)]
+pub struct ClickEventStats {
+    /// 已写入数据库的事件数量
+    pub written: AtomicU64,
+    /// 因队列已满或写入失败而丢弃的事件数量
+    pub dropped: AtomicU64,
+}
+
+/// 后台写入任务消费的事件队列接收端，与IState中的发送端一一对应
+pub struct EventReceivers {
+    pub click_rx: mpsc::Receiver<ClickEvent>,
+    pub access_rx: mpsc::Receiver<AccessEvent>,
+    pub live_publish_rx: mpsc::Receiver<LiveClick>,
+    pub webhook_click_rx: mpsc::Receiver<LiveClick>,
 }
 
 #[derive(Clone)]
 pub struct IState {
     pub db_pool: sqlx::PgPool,
     pub redis_pool: bb8::Pool<bb8_redis::RedisConnectionManager>,
+    /// 发布订阅需要独占连接，不走连接池
+    pub redis_client: bb8_redis::redis::Client,
     pub redis_db: Option<usize>,
     pub cleanup_config: Cleanup,
+    pub link_config: Link,
+    pub access_config: Access,
+    pub analytics_config: Analytics,
+    pub webhook_config: Webhook,
+    pub auth_config: Auth,
+    pub rate_limit_config: RateLimit,
+    pub blocklist_config: Blocklist,
+    pub abuse_config: Abuse,
+    pub signed_link_config: SignedLink,
+    /// 可信反向代理，解析客户端IP时使用
+    pub trusted_proxies: Vec<IpNetwork>,
+    pub click_tx: mpsc::Sender<ClickEvent>,
+    pub click_stats: Arc<ClickEventStats>,
+    /// 访问记录队列，由写入任务批量写入Redis
+    pub access_tx: mpsc::Sender<AccessEvent>,
+    /// 待发布到Redis频道的实时点击，由发布任务批量发布
+    pub live_publish_tx: mpsc::Sender<LiveClick>,
+    /// 实时点击流，由Redis订阅任务写入，SSE连接各自订阅
+    pub live_tx: broadcast::Sender<LiveClick>,
+    /// 服务关闭时通知SSE连接结束，否则长连接会阻塞优雅关闭
+    pub stream_shutdown: broadcast::Sender<()>,
     pub cleanup_stats: Arc<RwLock<CleanupStats>>,
+    /// 待写入投递记录的点击事件，由写入任务批量派发给订阅了link.clicked的Webhook
+    pub webhook_click_tx: mpsc::Sender<LiveClick>,
+    /// 因队列已满或写入失败而丢弃的点击事件Webhook数量
+    pub webhook_click_dropped: Arc<AtomicU64>,
+    /// 有效的Webhook订阅缓存，投递任务定时刷新，派发事件时同步读取
+    pub webhook_subscriptions: Arc<std::sync::RwLock<Vec<WebhookSubscription>>>,
+    /// 当前周期的IP哈希盐：(周期序号, 盐)
+    pub ip_salt: Arc<std::sync::RwLock<Option<(u64, String)>>>,
+    /// 校验JWT的公钥集合，由加载任务定时刷新，未配置或尚未加载成功时为空
+    pub jwks: Arc<std::sync::RwLock<Option<JwkSet>>>,
+    /// 目标地址的屏蔽和允许规则，由加载任务定时刷新，创建和跳转时同步读取
+    pub url_rules: Arc<std::sync::RwLock<UrlRules>>,
+    /// 最近一次成功加载的规则文件，规则文件格式错误时继续使用
+    pub url_rule_file: Arc<std::sync::RwLock<UrlRuleFile>>,
 }
@@ -1,4 +1,8 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use axum::http::HeaderMap;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha256};
 
 const BASE62_ALPHABET: [u8; 62] =
@@ -100,10 +104,299 @@ pub fn calculate_sha256(input: &str) -> String {
     URL_SAFE_NO_PAD.encode(result)
 }
 
+/// HMAC-SHA256 signature
+///
+/// # Arguments
+///
+/// * `secret`: signing key
+/// * `message`: signed content
+///
+/// returns: lowercase hex string
+pub fn hmac_sha256_hex(secret: &str, message: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// host header value to lowercase domain without port
+///
+/// # Arguments
+///
+/// * `host`: host header value, e.g. `example.com:8008`
+///
+/// returns: domain string
+///
+/// # Examples
+///
+/// ```
+/// let result = helper::strip_port("Example.com:8008");
+/// assert_eq!(result, "example.com");
+/// ```
+pub fn strip_port(host: &str) -> String {
+    let host = host.trim();
+    let domain = if host.starts_with('[') {
+        // IPv6 字面量，例如 [::1]:8008
+        match host.find(']') {
+            Some(end) => &host[..=end],
+            None => host,
+        }
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+    domain.to_lowercase()
+}
+
+/// escape text for use in html content and quoted attribute values
+///
+/// # Arguments
+///
+/// * `text`: raw text
+///
+/// returns: text with `&`, `<`, `>`, `"` and `'` replaced by entities
+///
+/// # Examples
+///
+/// ```
+/// let result = helper::escape_html("<a href=\"x\">");
+/// assert_eq!(result, "&lt;a href=&quot;x&quot;&gt;");
+/// ```
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// domain of a referrer url
+///
+/// # Arguments
+///
+/// * `referrer`: value of the `Referer` header
+///
+/// returns: lowercase domain without port, None if it is not an absolute url
+///
+/// # Examples
+///
+/// ```
+/// let result = helper::referrer_domain("https://www.Example.com:8443/path?q=1");
+/// assert_eq!(result, Some("www.example.com".to_string()));
+/// ```
+pub fn referrer_domain(referrer: &str) -> Option<String> {
+    let (_, rest) = referrer.trim().split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    // 去掉用户信息部分
+    let host = authority.rsplit('@').next()?;
+    let domain = strip_port(host);
+    if domain.is_empty() {
+        None
+    } else {
+        Some(domain)
+    }
+}
+
+/// an ip network in CIDR notation, a single address is a network with the full prefix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// parse `10.0.0.0/8`, `fd00::/8` or a single address
+    pub fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.trim().split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+            None => (value.trim().parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) if self.addr.is_ipv4() => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            _ => ip,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// client ip of the request, proxy headers are only honored when sent by a trusted proxy
+///
+/// # Arguments
+///
+/// * `headers`: request headers, `X-Forwarded-For` is walked from right to left, then `X-Real-IP`
+/// * `remote`: remote ip of the connection
+/// * `trusted_proxies`: networks of the reverse proxies in front of the service
+///
+/// returns: the right-most address that is not a trusted proxy, or `remote` when it is not trusted
+pub fn client_ip(headers: &HeaderMap, remote: IpAddr, trusted_proxies: &[IpNetwork]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|network| network.contains(ip));
+    if !is_trusted(remote) {
+        return remote;
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    if forwarded.is_empty() {
+        return headers
+            .get("x-real-ip")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(remote);
+    }
+
+    // 左侧的地址可以由客户端任意伪造，从右向左取第一个不可信的地址
+    let mut client = remote;
+    for hop in forwarded.iter().rev() {
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// anonymize ip address by truncating the host part
+///
+/// # Arguments
+///
+/// * `ip`: ip address
+///
+/// returns: IPv4 keeps the first 24 bits, IPv6 keeps the first 48 bits
+///
+/// # Examples
+///
+/// ```
+/// let result = helper::anonymize_ip("192.168.1.100".parse().unwrap());
+/// assert_eq!(result, "192.168.1.0");
+/// ```
+pub fn anonymize_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            Ipv4Addr::new(a, b, c, 0).to_string()
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            Ipv6Addr::new(segments[0], segments[1], segments[2], 0, 0, 0, 0, 0).to_string()
+        }
+    }
+}
+
+/// whether the ip address is publicly routable
+///
+/// # Arguments
+///
+/// * `ip`: ip address
+///
+/// returns: false for private, loopback, link-local, shared, documentation, benchmarking, reserved,
+/// NAT64, multicast and unspecified addresses
+///
+/// # Examples
+///
+/// ```
+/// assert!(helper::is_public_ip("93.184.216.34".parse().unwrap()));
+/// assert!(!helper::is_public_ip("10.0.0.1".parse().unwrap()));
+/// ```
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            // 100.64.0.0/10 运营商共享地址
+            let shared = a == 100 && (b & 0xc0) == 64;
+            // 198.18.0.0/15 基准测试地址
+            let benchmarking = a == 198 && (b & 0xfe) == 18;
+            // 192.0.0.0/24 IETF协议分配地址
+            let protocol = a == 192 && b == 0 && c == 0;
+            // 240.0.0.0/4 保留地址，包含广播地址
+            let reserved = a >= 240;
+            !(a == 0
+                || shared
+                || benchmarking
+                || protocol
+                || reserved
+                || v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast())
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_ip(IpAddr::V4(v4)),
+            None => {
+                let segments = v6.segments();
+                // 64:ff9b::/96 NAT64地址会被转换为内嵌的IPv4地址访问
+                let nat64 = segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0];
+                // 2001:db8::/32 文档地址
+                let documentation = segments[0] == 0x2001 && segments[1] == 0xdb8;
+                !(nat64
+                    || documentation
+                    || v6.is_unspecified()
+                    || v6.is_loopback()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local()
+                    || v6.is_multicast())
+            }
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn client_ip_from_trusted_proxy() {
+        let trusted: Vec<IpNetwork> = ["10.0.0.0/8", "fd00::/8"]
+            .iter()
+            .map(|network| IpNetwork::parse(network).unwrap())
+            .collect();
+        let headers = |xff: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-forwarded-for", xff.parse().unwrap());
+            headers
+        };
+        let ip = |value: &str| value.parse::<IpAddr>().unwrap();
+
+        // 不可信的连接直接使用连接地址
+        assert_eq!(client_ip(&headers("1.1.1.1"), ip("203.0.113.9"), &trusted), ip("203.0.113.9"));
+        // 客户端伪造的左侧地址被忽略
+        assert_eq!(
+            client_ip(&headers("1.1.1.1, 198.51.100.7, 10.0.0.2"), ip("10.0.0.1"), &trusted),
+            ip("198.51.100.7")
+        );
+        assert_eq!(client_ip(&headers("10.0.0.3, 10.0.0.2"), ip("10.0.0.1"), &trusted), ip("10.0.0.3"));
+        assert_eq!(client_ip(&headers("garbage"), ip("fd00::1"), &trusted), ip("fd00::1"));
+        assert_eq!(client_ip(&HeaderMap::new(), ip("::ffff:10.1.2.3"), &trusted), ip("::ffff:10.1.2.3"));
+        assert_eq!(client_ip(&headers("1.1.1.1"), ip("10.0.0.1"), &[]), ip("10.0.0.1"));
+
+        assert!(IpNetwork::parse("10.0.0.0/33").is_none());
+        assert!(IpNetwork::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+        assert!(!IpNetwork::parse("192.168.1.10").unwrap().contains(ip("192.168.1.11")));
+    }
+
     #[test]
     fn sha_to_256() {
         let result = calculate_sha256("abcd");
@@ -121,4 +414,60 @@ mod tests {
         let result = decode_base62("86jVtiUv").unwrap();
         assert_eq!(result, 28555415586117);
     }
+
+    #[test]
+    fn hmac_signature() {
+        let result = hmac_sha256_hex("Jefe", "what do ya want for nothing?");
+        assert_eq!(result, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn host_without_port() {
+        assert_eq!(strip_port("Example.com:8008"), "example.com");
+        assert_eq!(strip_port("example.com"), "example.com");
+        assert_eq!(strip_port("[::1]:8008"), "[::1]");
+    }
+
+    #[test]
+    fn truncate_ip() {
+        assert_eq!(anonymize_ip("192.168.1.100".parse().unwrap()), "192.168.1.0");
+        assert_eq!(anonymize_ip("2001:db8:85a3:8d3:1319:8a2e:370:7348".parse().unwrap()), "2001:db8:85a3::");
+    }
+
+    #[test]
+    fn public_ip() {
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:2800:220:1::1".parse().unwrap()));
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["64:ff9b::a9fe:a9fe", "64:ff9b::5db8:d822", "2001:db8::1", "198.18.0.1", "198.19.255.255", "192.0.0.8", "240.0.0.1", "255.255.255.254"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        // 相邻的公网地址不受影响
+        for ip in ["198.17.255.255", "198.20.0.1", "192.0.1.1", "239.255.255.255", "2001:db9::1"] {
+            let ip: IpAddr = ip.parse().unwrap();
+            assert_eq!(is_public_ip(ip), !ip.is_multicast(), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn html_escaping() {
+        assert_eq!(
+            escape_html(r#"https://93.184.216.34/?a=1&b="><script>alert('x')</script>"#),
+            "https://93.184.216.34/?a=1&amp;b=&quot;&gt;&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"
+        );
+        // 已转义的内容会再次转义，不会被还原
+        assert_eq!(escape_html("&lt;"), "&amp;lt;");
+    }
+
+    #[test]
+    fn domain_of_referrer() {
+        assert_eq!(
+            referrer_domain("https://www.Example.com:8443/path?q=1"),
+            Some("www.example.com".to_string())
+        );
+        assert_eq!(referrer_domain("android-app://com.slack"), Some("com.slack".to_string()));
+        assert_eq!(referrer_domain("not a url"), None);
+    }
 }
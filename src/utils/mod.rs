@@ -1 +1,2 @@
 pub mod helper;
+pub mod user_agent;
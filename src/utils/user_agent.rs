@@ -0,0 +1,151 @@
+/// 从User-Agent中解析出的浏览器、操作系统和设备类型
+#[derive(Debug, PartialEq)]
+pub struct UserAgentInfo {
+    pub browser: &'static str,
+    pub os: &'static str,
+    pub device: &'static str,
+}
+
+/// 按关键字匹配解析User-Agent，规则按优先级排列
+const BROWSER_RULES: [(&str, &[&str]); 10] = [
+    ("WeChat", &["micromessenger"]),
+    ("Edge", &["edg/", "edge/", "edga/", "edgios/"]),
+    ("Opera", &["opr/", "opera"]),
+    ("Samsung Internet", &["samsungbrowser"]),
+    ("UC Browser", &["ucbrowser"]),
+    ("Firefox", &["firefox/", "fxios/"]),
+    ("Chrome", &["chrome/", "crios/"]),
+    ("Safari", &["safari/"]),
+    ("Internet Explorer", &["msie ", "trident/"]),
+    ("curl", &["curl/"]),
+];
+
+const OS_RULES: [(&str, &[&str]); 6] = [
+    ("Windows", &["windows"]),
+    ("iOS", &["iphone", "ipad", "ipod"]),
+    ("Android", &["android"]),
+    ("Chrome OS", &["cros "]),
+    ("macOS", &["macintosh", "mac os x"]),
+    ("Linux", &["linux", "x11"]),
+];
+
+/// 解析User-Agent
+///
+/// # Arguments
+///
+/// * `user_agent`: User-Agent请求头
+///
+/// returns: UserAgentInfo，无法识别的部分为 `Other`
+///
+/// # Examples
+///
+/// ```
+/// let info = user_agent::parse_user_agent("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) ... Mobile/15E148 Safari/604.1");
+/// assert_eq!(info.os, "iOS");
+/// ```
+pub fn parse_user_agent(user_agent: &str) -> UserAgentInfo {
+    let ua = user_agent.to_lowercase();
+
+    let browser = match_rules(&ua, &BROWSER_RULES);
+    let os = match_rules(&ua, &OS_RULES);
+    let device = if ua.is_empty() {
+        "Other"
+    } else if ua.contains("ipad")
+        || ua.contains("tablet")
+        || (ua.contains("android") && !ua.contains("mobile"))
+    {
+        "Tablet"
+    } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("ipod") {
+        "Mobile"
+    } else if matches!(os, "Windows" | "macOS" | "Linux" | "Chrome OS") {
+        "Desktop"
+    } else {
+        "Other"
+    };
+
+    UserAgentInfo {
+        browser,
+        os,
+        device,
+    }
+}
+
+/// 根据关键字判断User-Agent是否为机器人，没有User-Agent的请求也视为机器人
+///
+/// # Arguments
+///
+/// * `user_agent`: User-Agent请求头
+/// * `patterns`: 小写的关键字列表
+///
+/// returns: bool
+///
+/// # Examples
+///
+/// ```
+/// let patterns = vec!["bot".to_string()];
+/// assert!(user_agent::is_bot(Some("Slackbot-LinkExpanding 1.0"), &patterns));
+/// ```
+pub fn is_bot(user_agent: Option<&str>, patterns: &[String]) -> bool {
+    match user_agent.map(str::trim) {
+        None | Some("") => true,
+        Some(user_agent) => {
+            let ua = user_agent.to_lowercase();
+            patterns.iter().any(|pattern| ua.contains(pattern.as_str()))
+        }
+    }
+}
+
+fn match_rules(ua: &str, rules: &[(&'static str, &[&str])]) -> &'static str {
+    rules
+        .iter()
+        .find(|(_, keywords)| keywords.iter().any(|keyword| ua.contains(keyword)))
+        .map(|(name, _)| *name)
+        .unwrap_or("Other")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_desktop_chrome() {
+        let info = parse_user_agent(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        );
+        assert_eq!(info.browser, "Chrome");
+        assert_eq!(info.os, "Windows");
+        assert_eq!(info.device, "Desktop");
+    }
+
+    #[test]
+    fn parse_mobile_safari() {
+        let info = parse_user_agent(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+        );
+        assert_eq!(info.browser, "Safari");
+        assert_eq!(info.os, "iOS");
+        assert_eq!(info.device, "Mobile");
+    }
+
+    #[test]
+    fn parse_android_tablet_edge() {
+        let info = parse_user_agent(
+            "Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 EdgA/120.0.0.0",
+        );
+        assert_eq!(info.browser, "Edge");
+        assert_eq!(info.os, "Android");
+        assert_eq!(info.device, "Tablet");
+    }
+
+    #[test]
+    fn classify_bot() {
+        let patterns = vec!["bot".to_string(), "facebookexternalhit".to_string()];
+        assert!(is_bot(Some("Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)"), &patterns));
+        assert!(is_bot(Some("facebookexternalhit/1.1"), &patterns));
+        assert!(is_bot(None, &patterns));
+        assert!(!is_bot(
+            Some("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 Safari/605.1.15"),
+            &patterns
+        ));
+    }
+}